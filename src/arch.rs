@@ -0,0 +1,373 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Processor architectures.
+//!
+//! An [Architecture] describes the processor an [OBJ](crate::OBJ) was assembled for, as
+//! identified by its [CPU](crate::Section::CPU) section: how its code is encoded, how it is
+//! disassembled, and how [Patch]es are written into it. [for_cpu] looks up the architecture
+//! for a [cputype].
+//!
+//! ```
+//! use psyk::{arch, cputype};
+//!
+//! let mips = arch::for_cpu(cputype::MIPS_R3000).unwrap();
+//! assert_eq!("MIPS R3000", mips.name());
+//! assert_eq!(binrw::Endian::Little, mips.endian());
+//! assert_eq!(4, mips.alignment());
+//! assert!(arch::for_cpu(9).is_none());
+//! ```
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use anyhow::{bail, Result};
+use binrw::Endian;
+
+use crate::disasm::{m68k, Listing};
+use crate::display::Options;
+use crate::patch::{self, Target};
+use crate::{cputype, Code, Patch};
+
+/// A processor that code in an object is assembled for.
+pub trait Architecture: Sync {
+    /// Returns the [cputype] identifying the processor.
+    fn cpu(&self) -> u8;
+
+    /// Returns the name of the processor, e.g. `MIPS R3000`.
+    fn name(&self) -> &'static str;
+
+    /// Returns the byte order of instructions and data.
+    fn endian(&self) -> Endian;
+
+    /// Returns the sizes in bytes of the shortest and longest instructions.
+    fn instruction_size(&self) -> RangeInclusive<usize>;
+
+    /// Returns the alignment in bytes of instructions.
+    fn alignment(&self) -> usize;
+
+    /// Writes the disassembly of `code`, which is followed by `patches`, at the current
+    /// offset of `listing`.
+    fn write_code(
+        &self,
+        listing: &Listing,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result;
+
+    /// Writes `value` into `data` at the offset of `patch`. See [Patch::apply].
+    ///
+    /// By default patches are not supported.
+    fn apply_patch(
+        &self,
+        target: &Target,
+        patch: &Patch,
+        data: &mut [u8],
+        value: i64,
+    ) -> Result<()> {
+        let _ = (target, patch, data, value);
+        bail!(
+            "patches for processor type {} are not supported",
+            self.cpu()
+        )
+    }
+}
+
+impl fmt::Debug for dyn Architecture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the architecture for the [cputype] `cpu`, or `None` if it is not known.
+pub fn for_cpu(cpu: u8) -> Option<&'static dyn Architecture> {
+    Some(match cpu {
+        cputype::MOTOROLA_68000 => &M68000,
+        cputype::MOTOROLA_68010 => &M68010,
+        cputype::MOTOROLA_68020 => &M68020,
+        cputype::MOTOROLA_68030 => &M68030,
+        cputype::MOTOROLA_68040 => &M68040,
+        cputype::WDC_65816 => &W65816,
+        cputype::ZILOG_Z80 => &Z80,
+        cputype::MIPS_R3000 => &MipsR3000,
+        cputype::HITACHI_SH2 => &HitachiSh2,
+        _ => return None,
+    })
+}
+
+const M68000: M68k = M68k(m68k::Model::M68000);
+const M68010: M68k = M68k(m68k::Model::M68010);
+const M68020: M68k = M68k(m68k::Model::M68020);
+const M68030: M68k = M68k(m68k::Model::M68030);
+const M68040: M68k = M68k(m68k::Model::M68040);
+
+/// MIPS R3000 with GTE, used by the PlayStation.
+///
+/// Patches use the fixed [mips](patch::mips) tags.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MipsR3000;
+
+impl Architecture for MipsR3000 {
+    fn cpu(&self) -> u8 {
+        cputype::MIPS_R3000
+    }
+
+    fn name(&self) -> &'static str {
+        "MIPS R3000"
+    }
+
+    fn endian(&self) -> Endian {
+        Endian::Little
+    }
+
+    fn instruction_size(&self) -> RangeInclusive<usize> {
+        4..=4
+    }
+
+    fn alignment(&self) -> usize {
+        4
+    }
+
+    fn write_code(
+        &self,
+        listing: &Listing,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        listing.write_mips(f, options, code, patches)
+    }
+
+    fn apply_patch(&self, _: &Target, patch: &Patch, data: &mut [u8], value: i64) -> Result<()> {
+        patch::apply_mips(patch.tag(), data, usize::from(patch.offset()), value)
+    }
+}
+
+/// Hitachi SH-2, used by the Saturn.
+///
+/// Patches write big-endian values of the size assigned to their tag.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HitachiSh2;
+
+impl Architecture for HitachiSh2 {
+    fn cpu(&self) -> u8 {
+        cputype::HITACHI_SH2
+    }
+
+    fn name(&self) -> &'static str {
+        "Hitachi SH-2"
+    }
+
+    fn endian(&self) -> Endian {
+        Endian::Big
+    }
+
+    fn instruction_size(&self) -> RangeInclusive<usize> {
+        2..=2
+    }
+
+    fn alignment(&self) -> usize {
+        2
+    }
+
+    fn write_code(
+        &self,
+        listing: &Listing,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        listing.write_sh2(f, options, code, patches)
+    }
+
+    fn apply_patch(
+        &self,
+        target: &Target,
+        patch: &Patch,
+        data: &mut [u8],
+        value: i64,
+    ) -> Result<()> {
+        patch::apply_sized(self.cpu(), target, patch, data, value)
+    }
+}
+
+/// A Motorola 680x0, used by the Genesis and Sega CD.
+///
+/// Patches write big-endian values of the size assigned to their tag.
+#[derive(Clone, Copy, Debug)]
+pub struct M68k(m68k::Model);
+
+impl M68k {
+    /// Creates the architecture of a 680x0 `model`.
+    pub fn new(model: m68k::Model) -> Self {
+        Self(model)
+    }
+
+    /// Returns the model of the processor.
+    pub fn model(&self) -> m68k::Model {
+        self.0
+    }
+}
+
+impl Architecture for M68k {
+    fn cpu(&self) -> u8 {
+        match self.0 {
+            m68k::Model::M68000 => cputype::MOTOROLA_68000,
+            m68k::Model::M68010 => cputype::MOTOROLA_68010,
+            m68k::Model::M68020 => cputype::MOTOROLA_68020,
+            m68k::Model::M68030 => cputype::MOTOROLA_68030,
+            m68k::Model::M68040 => cputype::MOTOROLA_68040,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self.0 {
+            m68k::Model::M68000 => "Motorola 68000",
+            m68k::Model::M68010 => "Motorola 68010",
+            m68k::Model::M68020 => "Motorola 68020",
+            m68k::Model::M68030 => "Motorola 68030",
+            m68k::Model::M68040 => "Motorola 68040",
+        }
+    }
+
+    fn endian(&self) -> Endian {
+        Endian::Big
+    }
+
+    fn instruction_size(&self) -> RangeInclusive<usize> {
+        if self.0 < m68k::Model::M68020 {
+            2..=10
+        } else {
+            2..=22
+        }
+    }
+
+    fn alignment(&self) -> usize {
+        2
+    }
+
+    fn write_code(
+        &self,
+        listing: &Listing,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        listing.write_m68k(f, options, code, patches, self.0)
+    }
+
+    fn apply_patch(
+        &self,
+        target: &Target,
+        patch: &Patch,
+        data: &mut [u8],
+        value: i64,
+    ) -> Result<()> {
+        patch::apply_sized(self.cpu(), target, patch, data, value)
+    }
+}
+
+/// Zilog Z80, used as the sound processor of the Genesis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Z80;
+
+impl Architecture for Z80 {
+    fn cpu(&self) -> u8 {
+        cputype::ZILOG_Z80
+    }
+
+    fn name(&self) -> &'static str {
+        "Zilog Z80"
+    }
+
+    fn endian(&self) -> Endian {
+        Endian::Little
+    }
+
+    fn instruction_size(&self) -> RangeInclusive<usize> {
+        1..=4
+    }
+
+    fn alignment(&self) -> usize {
+        1
+    }
+
+    fn write_code(
+        &self,
+        listing: &Listing,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        listing.write_z80(f, options, code, patches)
+    }
+}
+
+/// WDC 65816, used by the Ricoh 5A22 of the Super Nintendo.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct W65816;
+
+impl Architecture for W65816 {
+    fn cpu(&self) -> u8 {
+        cputype::WDC_65816
+    }
+
+    fn name(&self) -> &'static str {
+        "WDC 65816"
+    }
+
+    fn endian(&self) -> Endian {
+        Endian::Little
+    }
+
+    fn instruction_size(&self) -> RangeInclusive<usize> {
+        1..=4
+    }
+
+    fn alignment(&self) -> usize {
+        1
+    }
+
+    fn write_code(
+        &self,
+        listing: &Listing,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        listing.write_w65816(f, options, code, patches)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_for_cpu() {
+        for cpu in 0..=8 {
+            let architecture = for_cpu(cpu).expect("architecture");
+            assert_eq!(cpu, architecture.cpu());
+        }
+        assert_eq!(
+            "Hitachi SH-2",
+            for_cpu(cputype::HITACHI_SH2).unwrap().name()
+        );
+        assert_eq!(
+            Endian::Big,
+            for_cpu(cputype::MOTOROLA_68020).unwrap().endian()
+        );
+        assert_eq!(
+            1..=4,
+            for_cpu(cputype::ZILOG_Z80).unwrap().instruction_size()
+        );
+        assert!(for_cpu(0xFF).is_none());
+    }
+}
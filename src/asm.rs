@@ -0,0 +1,575 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! GNU as source for MIPS [OBJ]s.
+//!
+//! [gnu_as] writes the sections of an object as assembly that `mips-linux-gnu-as` assembles
+//! back to the same code. Instructions are written with `.set noreorder` and `.set noat` so
+//! the assembler does not fill delay slots or use `$at`, and instructions it would encode
+//! differently, such as GTE commands, are written as `.word`s. Relocated instructions use
+//! `%hi`, `%lo`, and `%gp_rel`, and branches refer to labels placed at their targets.
+//!
+//! Exported symbols are written with the `glabel` macro, local symbols as labels, and commons
+//! with `.comm`. Sections are referred to by a local label at their start, e.g. `.Ltext`.
+//!
+//! ```
+//! use psyk::{asm, Code, LNKHeader, Section, OBJ, XDEF};
+//!
+//! let obj = OBJ::new(vec![
+//!     Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+//!     Section::SectionSwitch(1),
+//!     Section::Code(Code::new(vec![0x08, 0x00, 0xE0, 0x03, 0, 0, 0, 0]).unwrap()),
+//!     Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+//!     Section::NOP,
+//! ]);
+//! let source = asm::gnu_as(&obj).unwrap();
+//! assert!(source.contains("glabel main\n    /* 000000 03E00008 */  jr          $ra\n"));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use anyhow::{anyhow, bail, Result};
+use rabbitizer::{InstrCategory, Instruction};
+
+use crate::disasm::gte;
+use crate::model::{self, ObjectModel, ObjectSection, Relocation, Symbol};
+use crate::patch::mips;
+use crate::{arch, cputype, Expression, OBJ};
+
+/// Defines `glabel`, which exports a label.
+const PRELUDE: &str = "\
+.macro glabel label
+    .global \\label
+    \\label:
+.endm
+
+.set noat
+.set noreorder
+";
+
+/// Zero filled data of at least this many bytes is written with `.space`.
+const SPACE: usize = 16;
+
+/// Returns GNU as source for the MIPS object `obj`.
+///
+/// An error is returned if the object is not for a MIPS processor, or has a patch which
+/// cannot be written as a relocation operator or `.word`.
+pub fn gnu_as(obj: &OBJ) -> Result<String> {
+    let model = ObjectModel::new(obj)?;
+    if model.cpu() != cputype::MIPS_R3000 {
+        match arch::for_cpu(model.cpu()) {
+            Some(architecture) => bail!(
+                "{} objects cannot be written as GNU as source",
+                architecture.name()
+            ),
+            None => bail!(
+                "processor type {} objects cannot be written as GNU as source",
+                model.cpu()
+            ),
+        }
+    }
+    Writer::new(&model).write()
+}
+
+struct Writer<'a> {
+    model: &'a ObjectModel,
+    names: HashMap<u16, String>,
+    out: String,
+}
+
+impl<'a> Writer<'a> {
+    fn new(model: &'a ObjectModel) -> Self {
+        let mut names: HashMap<u16, String> = model
+            .symbols()
+            .iter()
+            .map(|(number, symbol)| (*number, symbol.name()))
+            .collect();
+        for section in model.sections() {
+            names.insert(section.number(), section_label(section));
+        }
+        Self {
+            model,
+            names,
+            out: String::new(),
+        }
+    }
+
+    fn write(mut self) -> Result<String> {
+        self.out.push_str(PRELUDE);
+        for section in self.model.sections() {
+            self.section(section)?;
+        }
+        let commons: Vec<_> = self
+            .model
+            .symbols()
+            .values()
+            .filter_map(|symbol| match symbol {
+                Symbol::Common(xbss) => Some((xbss.name(), xbss.size())),
+                _ => None,
+            })
+            .collect();
+        if !commons.is_empty() {
+            self.out.push('\n');
+            for (name, size) in commons {
+                writeln!(self.out, ".comm {name}, {size}")?;
+            }
+        }
+        Ok(self.out)
+    }
+
+    /// Formats the address `relocation` refers to as a symbol or section label and an optional
+    /// constant offset.
+    fn address(&self, relocation: &Relocation) -> Result<String> {
+        let (number, addend) = match relocation.address() {
+            Some((Expression::SymbolAddressIndex(number), addend))
+            | Some((Expression::SectionAddressIndex(number), addend)) => (*number, addend),
+            _ => bail!(
+                "{} cannot be written as GNU as source",
+                relocation.expression()
+            ),
+        };
+        let name = self
+            .names
+            .get(&number)
+            .ok_or_else(|| anyhow!("unknown symbol {number:x}"))?;
+        Ok(model::format_address(name, addend))
+    }
+
+    fn section(&mut self, section: &ObjectSection) -> Result<()> {
+        let name = section.name();
+        let code = name.starts_with(".text");
+        let flags = if code {
+            "\"ax\""
+        } else if name.starts_with(".rdata") || name.starts_with(".rodata") {
+            "\"a\""
+        } else if section.data().is_empty() {
+            "\"aw\", @nobits"
+        } else {
+            "\"aw\""
+        };
+        writeln!(self.out, "\n.section {name}, {flags}")?;
+        if section.align() > 1 {
+            writeln!(self.out, ".balign {}", section.align())?;
+        }
+        writeln!(self.out, "{}:", section_label(section))?;
+
+        let mut relocations = HashMap::new();
+        for relocation in section.relocations() {
+            relocations.insert(relocation.offset(), relocation);
+        }
+        let mut labels = self.labels(section);
+        if code {
+            // branch targets
+            for (offset, word) in words(section.data()) {
+                let instruction = Instruction::new(word, offset, InstrCategory::CPU);
+                if let Some(target) = branch_target(&instruction, section) {
+                    let label = local_label(section, target);
+                    let names = labels.entry(target).or_default();
+                    if !names.contains(&label) {
+                        names.push(label);
+                    }
+                }
+            }
+            self.code(section, &relocations, &labels)?;
+        } else {
+            self.data(section, &relocations, &labels)?;
+        }
+        // uninitialized data, split at the labels in it, and labels at the end of the section
+        let mut offset = section.data().len() as u32;
+        let ends: Vec<u32> = labels
+            .range(offset..section.size())
+            .map(|(at, _)| *at)
+            .chain([section.size()])
+            .collect();
+        for end in ends {
+            if end > offset {
+                writeln!(self.out, "    {:<12}{}", ".space", end - offset)?;
+                offset = end;
+            }
+            self.write_labels(&labels, end, None)?;
+        }
+        Ok(())
+    }
+
+    /// Exported and local symbols defined in `section` by offset.
+    fn labels(&self, section: &ObjectSection) -> BTreeMap<u32, Vec<String>> {
+        let mut labels: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        for symbol in self.model.symbols().values() {
+            if let Symbol::Definition(xdef) = symbol {
+                if xdef.section() == section.number() {
+                    labels
+                        .entry(xdef.offset())
+                        .or_default()
+                        .push(format!("glabel {}", xdef.symbol_name()));
+                }
+            }
+        }
+        for local in self.model.locals() {
+            if local.section() == section.number() {
+                labels.entry(local.offset()).or_default().push(local.name());
+            }
+        }
+        labels
+    }
+
+    /// Writes the labels at `offset`, or at the offsets from `offset` up to `end`.
+    fn write_labels(
+        &mut self,
+        labels: &BTreeMap<u32, Vec<String>>,
+        offset: u32,
+        end: Option<u32>,
+    ) -> Result<()> {
+        let range = offset..=end.map_or(offset, |end| end.saturating_sub(1).max(offset));
+        for names in labels.range(range).map(|(_, names)| names) {
+            for name in names {
+                if name.starts_with("glabel ") {
+                    writeln!(self.out, "\n{name}")?;
+                } else {
+                    writeln!(self.out, "{name}:")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn code(
+        &mut self,
+        section: &ObjectSection,
+        relocations: &HashMap<u32, &Relocation>,
+        labels: &BTreeMap<u32, Vec<String>>,
+    ) -> Result<()> {
+        let data = section.data();
+        for (offset, word) in words(data) {
+            self.write_labels(labels, offset, None)?;
+            let asm = match relocations.get(&offset) {
+                Some(relocation) => self.relocated(section, relocation, word)?,
+                None => instruction(section, offset, word),
+            };
+            writeln!(self.out, "    /* {offset:06X} {word:08X} */  {asm}")?;
+        }
+        // trailing bytes which are not a whole instruction
+        let tail = data.len() & !3;
+        self.bytes(&data[tail..], tail as u32, labels)?;
+        Ok(())
+    }
+
+    /// Returns the assembly for the instruction `word` relocated by `relocation`.
+    fn relocated(
+        &self,
+        section: &ObjectSection,
+        relocation: &Relocation,
+        word: u32,
+    ) -> Result<String> {
+        let operand = match relocation.tag() {
+            mips::WORD => return Ok(format!("{:<12}{}", ".word", self.address(relocation)?)),
+            mips::JUMP => self.address(relocation)?,
+            mips::HI16 => format!("%hi({})", self.address(relocation)?),
+            mips::LO16 => format!("%lo({})", self.address(relocation)?),
+            mips::GP_RELATIVE => format!("%gp_rel({})", self.address(relocation)?),
+            tag => bail!(
+                "patch type {tag} at {:x} in section {} cannot be written as GNU as source",
+                relocation.offset(),
+                section.name()
+            ),
+        };
+        let instruction = Instruction::new(word, relocation.offset(), InstrCategory::CPU);
+        Ok(instruction.disassemble(Some(&operand), 0))
+    }
+
+    fn data(
+        &mut self,
+        section: &ObjectSection,
+        relocations: &HashMap<u32, &Relocation>,
+        labels: &BTreeMap<u32, Vec<String>>,
+    ) -> Result<()> {
+        let data = section.data();
+        let mut offset = 0;
+        while offset < data.len() {
+            let at = offset as u32;
+            self.write_labels(labels, at, None)?;
+            if let Some(relocation) = relocations.get(&at) {
+                if relocation.tag() != mips::WORD {
+                    bail!(
+                        "patch type {} at {at:x} in section {} cannot be written as GNU as source",
+                        relocation.tag(),
+                        section.name()
+                    );
+                }
+                let value = self.address(relocation)?;
+                writeln!(self.out, "    {:<12}{value}", ".word")?;
+                offset += 4;
+                continue;
+            }
+            // the next label or relocation ends a run of data
+            let next = labels
+                .range(at + 1..)
+                .map(|(offset, _)| *offset)
+                .chain(relocations.keys().copied().filter(|offset| *offset > at))
+                .min()
+                .map_or(data.len(), |next| (next as usize).min(data.len()));
+            let zeros = data[offset..next].iter().take_while(|b| **b == 0).count();
+            if zeros >= SPACE {
+                writeln!(self.out, "    {:<12}{zeros}", ".space")?;
+                offset += zeros;
+            } else if offset.is_multiple_of(4) && offset + 4 <= next {
+                let word = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+                writeln!(self.out, "    {:<12}{word:#010X}", ".word")?;
+                offset += 4;
+            } else {
+                writeln!(self.out, "    {:<12}{:#04X}", ".byte", data[offset])?;
+                offset += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn bytes(
+        &mut self,
+        bytes: &[u8],
+        offset: u32,
+        labels: &BTreeMap<u32, Vec<String>>,
+    ) -> Result<()> {
+        for (i, byte) in bytes.iter().enumerate() {
+            self.write_labels(labels, offset + i as u32, None)?;
+            writeln!(self.out, "    {:<12}{byte:#04X}", ".byte")?;
+        }
+        Ok(())
+    }
+}
+
+/// The label at the start of `section`, e.g. `.Ltext` for `.text`.
+fn section_label(section: &ObjectSection) -> String {
+    format!(".L{}", section.name().trim_start_matches('.'))
+}
+
+/// The label of a branch target in `section`.
+fn local_label(section: &ObjectSection, offset: u32) -> String {
+    format!("{}_{offset:06X}", section_label(section))
+}
+
+/// The little-endian words of `data` and their offsets.
+fn words(data: &[u8]) -> impl Iterator<Item = (u32, u32)> + '_ {
+    data.chunks_exact(4).enumerate().map(|(i, word)| {
+        (
+            i as u32 * 4,
+            u32::from_le_bytes(word.try_into().expect("4 bytes")),
+        )
+    })
+}
+
+/// Returns the offset a branch instruction jumps to if it is in `section`.
+fn branch_target(instruction: &Instruction, section: &ObjectSection) -> Option<u32> {
+    if !instruction.is_valid() || !instruction.is_branch() || !mips1(instruction.raw()) {
+        return None;
+    }
+    let target = instruction.branch_vram_generic();
+    (target.is_multiple_of(4) && target < section.data().len() as u32).then_some(target)
+}
+
+/// Returns `true` if `word` is encoded as a MIPS I instruction, which the R3000 implements.
+/// Later instructions are decoded by rabbitizer but rejected by `as -march=r3000`.
+fn mips1(word: u32) -> bool {
+    match word >> 26 {
+        // SPECIAL
+        0x00 => matches!(
+            word & 0x3F,
+            0x00 | 0x02..=0x04 | 0x06..=0x09 | 0x0C | 0x0D | 0x10..=0x13 | 0x18..=0x1B
+                | 0x20..=0x27 | 0x2A | 0x2B
+        ),
+        // REGIMM
+        0x01 => matches!((word >> 16) & 0x1F, 0x00 | 0x01 | 0x10 | 0x11),
+        0x02..=0x13 => true,
+        0x20..=0x26 | 0x28..=0x2B | 0x2E => true,
+        // lwc1, lwc2, swc1, swc2
+        0x31 | 0x32 | 0x39 | 0x3A => true,
+        _ => false,
+    }
+}
+
+/// Returns the assembly for the unrelocated instruction `word` at `offset` in `section`.
+fn instruction(section: &ObjectSection, offset: u32, word: u32) -> String {
+    let instruction = Instruction::new(word, offset, InstrCategory::CPU);
+    if let Some(command) = gte::command(word) {
+        return format!("{:<12}{word:#010X} /* {command} */", ".word");
+    }
+    if !instruction.is_valid() || !mips1(word) {
+        return format!("{:<12}{word:#010X}", ".word");
+    }
+    // `j` and `jal` without a patch jump to an absolute address which has no symbol
+    if matches!(word >> 26, 0x02 | 0x03) {
+        return format!("{:<12}{word:#010X}", ".word");
+    }
+    if instruction.is_branch() {
+        return match branch_target(&instruction, section) {
+            Some(target) => instruction.disassemble(Some(&local_label(section, target)), 0),
+            None => format!("{:<12}{word:#010X}", ".word"),
+        };
+    }
+    let asm = instruction.disassemble(None, 0);
+    // `move` may be assembled as `addu`
+    match asm.strip_prefix("move") {
+        Some(operands) => format!("{:<12}{}, $zero", "or", operands.trim_start()),
+        None => asm,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LNKHeader, LocalSymbol, Patch, Section, XBSS, XDEF, XREF};
+
+    fn obj() -> OBJ {
+        OBJ::new(vec![
+            Section::CPU(cputype::MIPS_R3000),
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".data".into())),
+            Section::LNKHeader(LNKHeader::new(3, 0, 8, ".bss".into())),
+            Section::SectionSwitch(1),
+            Section::Code(
+                Code::new(vec![
+                    0x00, 0x00, 0x04, 0x3C, // lui $a0, 0
+                    0x00, 0x00, 0x84, 0x24, // addiu $a0, $a0, 0
+                    0x00, 0x00, 0x00, 0x0C, // jal 0
+                    0x25, 0x28, 0x00, 0x02, // or $a1, $s0, $zero
+                    0xFF, 0xFF, 0x00, 0x10, // b .
+                    0x01, 0x00, 0x18, 0x4A, // rtps
+                    0xFF, 0xFF, 0xFF, 0xFF, // sd $ra, -1($ra)
+                ])
+                .unwrap(),
+            ),
+            Section::Patch(Patch::new(
+                mips::HI16,
+                0,
+                Expression::SectionAddressIndex(2),
+            )),
+            Section::Patch(Patch::new(
+                mips::LO16,
+                4,
+                Expression::SectionAddressIndex(2),
+            )),
+            Section::Patch(Patch::new(mips::JUMP, 8, Expression::SymbolAddressIndex(5))),
+            Section::SectionSwitch(2),
+            Section::Code(Code::new(vec![0, 0, 0, 0, 0x78, 0x56, 0x34, 0x12, 0xFF]).unwrap()),
+            Section::Patch(Patch::new(
+                mips::WORD,
+                0,
+                Expression::Add(
+                    Box::new(Expression::SymbolAddressIndex(4)),
+                    Box::new(Expression::Constant(8)),
+                ),
+            )),
+            Section::SectionSwitch(3),
+            Section::BSS(0x20),
+            Section::XDEF(XDEF::new(4, 1, 0, "main".into())),
+            Section::XREF(XREF::new(5, "printf".into())),
+            Section::XBSS(XBSS::new(6, 3, 4, "counter".into())),
+            Section::LocalSymbol(LocalSymbol::new(2, 4, "value".into())),
+            Section::NOP,
+        ])
+    }
+
+    #[test]
+    fn test_gnu_as() {
+        let source = gnu_as(&obj()).unwrap();
+        assert!(source.starts_with(PRELUDE));
+        assert_eq!(
+            source[PRELUDE.len()..],
+            *"
+.section .text, \"ax\"
+.balign 8
+.Ltext:
+
+glabel main
+    /* 000000 3C040000 */  lui         $a0, %hi(.Ldata)
+    /* 000004 24840000 */  addiu       $a0, $a0, %lo(.Ldata)
+    /* 000008 0C000000 */  jal         printf
+    /* 00000C 02002825 */  or          $a1, $s0, $zero
+.Ltext_000010:
+    /* 000010 1000FFFF */  b           .Ltext_000010
+    /* 000014 4A180001 */  .word       0x4A180001 /* rtps        sf=1,lm=0 */
+    /* 000018 FFFFFFFF */  .word       0xFFFFFFFF
+
+.section .data, \"aw\"
+.balign 8
+.Ldata:
+    .word       main+0x8
+value:
+    .word       0x12345678
+    .byte       0xFF
+
+.section .bss, \"aw\", @nobits
+.balign 8
+.Lbss:
+    .space      32
+
+.comm counter, 4
+"
+        );
+    }
+
+    #[test]
+    fn test_gnu_as_labels() {
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".bss".into())),
+            Section::SectionSwitch(1),
+            // jal 0x80000020 without a patch; nop
+            Section::Code(Code::new(vec![0x08, 0x00, 0x00, 0x0C, 0, 0, 0, 0]).unwrap()),
+            Section::SectionSwitch(2),
+            Section::BSS(0x20),
+            Section::XDEF(XDEF::new(3, 2, 0, "gvar".into())),
+            Section::XDEF(XDEF::new(4, 2, 8, "gvar2".into())),
+            Section::LocalSymbol(LocalSymbol::new(2, 0x10, "lvar".into())),
+            Section::NOP,
+        ]);
+        let source = gnu_as(&obj).unwrap();
+        assert_eq!(
+            source[PRELUDE.len()..],
+            *"
+.section .text, \"ax\"
+.balign 8
+.Ltext:
+    /* 000000 0C000008 */  .word       0x0C000008
+    /* 000004 00000000 */  nop
+
+.section .bss, \"aw\", @nobits
+.balign 8
+.Lbss:
+
+glabel gvar
+    .space      8
+
+glabel gvar2
+    .space      8
+lvar:
+    .space      16
+"
+        );
+    }
+
+    #[test]
+    fn test_gnu_as_errors() {
+        let obj = OBJ::new(vec![Section::CPU(cputype::HITACHI_SH2), Section::NOP]);
+        assert_eq!(
+            "Hitachi SH-2 objects cannot be written as GNU as source",
+            gnu_as(&obj).unwrap_err().to_string()
+        );
+
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 4]).unwrap()),
+            Section::Patch(Patch::new(
+                mips::WORD_BE,
+                0,
+                Expression::SectionAddressIndex(1),
+            )),
+            Section::NOP,
+        ]);
+        assert_eq!(
+            "patch type 8 at 0 in section .text cannot be written as GNU as source",
+            gnu_as(&obj).unwrap_err().to_string()
+        );
+    }
+}
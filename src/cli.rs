@@ -7,13 +7,28 @@ use std::fs::{File, FileTimes};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use anyhow::bail;
 use anyhow::Result;
+use anyhow::{anyhow, bail};
+use binrw::meta::ReadMagic;
 use clap::crate_version;
+use clap::ValueEnum;
+use serde_json::json;
 
+use super::cpe::CPE;
 use super::display;
-use super::io::{read, read_lib, write_lib, write_obj};
-use super::{Module, LIB};
+use super::elf::ELF;
+use super::exe::{self, EXE};
+use super::fingerprint;
+use super::identify::Identifier;
+use super::io::{
+    read, read_elf, read_exe, read_lib, read_obj, write_cpe, write_elf, write_exe, write_lib,
+    write_obj, Type,
+};
+use super::link::Linker;
+use super::model::ObjectModel;
+use super::sdk::Database;
+use super::types::Declarations;
+use super::{Module, LIB, OBJ};
 
 /// Prints information about an [OBJ](super::OBJ) or [LIB].
 pub fn info(
@@ -23,18 +38,287 @@ pub fn info(
     disassembly: bool,
     recursive: bool,
 ) -> Result<()> {
-    let o = read(lib_or_obj)?;
-    let mut options = display::Options::default();
-    if disassembly {
-        options.code_format = display::CodeFormat::Disassembly;
-    } else if code {
-        options.code_format = display::CodeFormat::Hex;
+    let options = ListOptions::default()
+        .with_code(code)
+        .with_disassembly(disassembly)
+        .with_recursive(recursive)
+        .build();
+    info_with_options(write, lib_or_obj, options)
+}
+
+/// Builds the [display::Options] for the flags of `psyk list`.
+#[derive(Clone, Default)]
+pub struct ListOptions {
+    options: display::Options,
+}
+
+impl ListOptions {
+    /// Shows code as hex bytes, unless it is disassembled.
+    pub fn with_code(mut self, code: bool) -> Self {
+        if code && !matches!(self.options.code_format, display::CodeFormat::Disassembly) {
+            self.options.code_format = display::CodeFormat::Hex;
+        }
+        self
+    }
+
+    /// Shows code as disassembly.
+    pub fn with_disassembly(mut self, disassembly: bool) -> Self {
+        if disassembly {
+            self.options.code_format = display::CodeFormat::Disassembly;
+        }
+        self
+    }
+
+    /// Lists the contents of each module of a [LIB].
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.options.recursive = recursive;
+        self
     }
-    options.recursive = recursive;
+
+    /// Names the symbols, sections, and groups in patch expressions.
+    pub fn with_symbolic(mut self, symbolic: bool) -> Self {
+        self.options.symbolic = symbolic;
+        self
+    }
+
+    /// Returns the options.
+    pub fn build(self) -> display::Options {
+        self.options
+    }
+}
+
+/// Prints information about an [OBJ](super::OBJ) or [LIB] with the given display options.
+pub fn info_with_options(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    options: display::Options,
+) -> Result<()> {
+    let o = read(lib_or_obj)?;
     writeln!(write, "{}", display::PsyXDisplayable::wrap(&o, options))?;
     Ok(())
 }
 
+/// Prints the section, offset, size, and name of each function in an [OBJ](super::OBJ), or
+/// in each module of a [LIB] preceded by the module name.
+pub fn functions(write: &mut impl Write, lib_or_obj: &Path) -> Result<()> {
+    let print = |write: &mut dyn Write, prefix: &str, obj: &super::OBJ| -> Result<()> {
+        let model = ObjectModel::new(obj)?;
+        for function in model.functions() {
+            let section = model
+                .section(function.section())
+                .map_or_else(|| format!("{:x}", function.section()), |s| s.name().into());
+            writeln!(
+                write,
+                "{prefix}{section:<8} {:08x} {:08x} {}",
+                function.offset(),
+                function.size(),
+                function.name()
+            )?;
+        }
+        Ok(())
+    };
+    match read(lib_or_obj)? {
+        Type::OBJ(obj) => print(write, "", &obj),
+        Type::LIB(lib) => {
+            for module in lib.modules() {
+                print(write, &format!("{:<8} ", module.name()), module.object())
+                    .map_err(|e| anyhow!("{}: {e}", module.name()))?;
+            }
+            Ok(())
+        }
+        _ => bail!("{} is not an OBJ or LIB", lib_or_obj.display()),
+    }
+}
+
+/// Prints the source file and line of the code at each `section:offset` of `addresses` in an
+/// [OBJ], or `??:0` if it has no line information. The section is a name or a hexadecimal
+/// number and the offset is hexadecimal.
+pub fn addr2line(write: &mut impl Write, obj: &Path, addresses: &[String]) -> Result<()> {
+    let model = ObjectModel::new(&read_obj(obj)?)?;
+    for address in addresses {
+        let Some((section, offset)) = address.rsplit_once(':') else {
+            bail!("expected SECTION:OFFSET, found {address}");
+        };
+        let number = match model.sections().iter().find(|s| s.name() == section) {
+            Some(section) => section.number(),
+            None => u16::from_str_radix(section.strip_prefix("0x").unwrap_or(section), 16)
+                .map_err(|_| anyhow!("unknown section {section}"))?,
+        };
+        let offset = u32::from_str_radix(offset.strip_prefix("0x").unwrap_or(offset), 16)
+            .map_err(|e| anyhow!("invalid offset {offset}: {e}"))?;
+        let lines = model.lines();
+        match lines.find(number, offset) {
+            Some(line) => {
+                let file = line.file().and_then(|file| lines.file_name(file));
+                writeln!(write, "{}:{}", file.unwrap_or("??"), line.line())?
+            }
+            None => writeln!(write, "??:0")?,
+        }
+    }
+    Ok(())
+}
+
+/// Prints the C declarations recovered from the debug records of an [OBJ], or of each module
+/// of a [LIB] preceded by a comment with the module name.
+pub fn headers(write: &mut impl Write, lib_or_obj: &Path) -> Result<()> {
+    match read(lib_or_obj)? {
+        Type::OBJ(obj) => write!(write, "{}", Declarations::new(&obj))?,
+        Type::LIB(lib) => {
+            let mut first = true;
+            for module in lib.modules() {
+                let declarations = Declarations::new(module.object());
+                if declarations.is_empty() {
+                    continue;
+                }
+                if !first {
+                    writeln!(write)?;
+                }
+                first = false;
+                writeln!(write, "/* {} */\n", module.name())?;
+                write!(write, "{declarations}")?;
+            }
+        }
+        _ => bail!("{} is not an OBJ or LIB", lib_or_obj.display()),
+    }
+    Ok(())
+}
+
+/// Writes the [fingerprints](super::fingerprint) of the functions in an [OBJ](super::OBJ),
+/// or in each module of a [LIB], as JSON.
+pub fn fingerprint(write: &mut impl Write, lib_or_obj: &Path) -> Result<()> {
+    let modules = match read(lib_or_obj)? {
+        Type::OBJ(obj) => {
+            let name = lib_or_obj
+                .file_stem()
+                .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+            vec![json!({"module": name, "functions": fingerprint::fingerprints(&obj)?})]
+        }
+        Type::LIB(lib) => lib
+            .modules()
+            .iter()
+            .map(|module| {
+                let fingerprints = fingerprint::fingerprints(module.object())
+                    .map_err(|e| anyhow!("{}: {e}", module.name()))?;
+                Ok(json!({"module": module.name(), "functions": fingerprints}))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        _ => bail!("{} is not an OBJ or LIB", lib_or_obj.display()),
+    };
+    serde_json::to_writer_pretty(&mut *write, &modules)?;
+    writeln!(write)?;
+    Ok(())
+}
+
+/// Searches a PS-X EXE, or a raw binary loaded at `address`, for the functions in `libs` and
+/// prints a symbol map of the functions found and the symbols they refer to.
+///
+/// `address` defaults to the text address of an EXE and `0x80000000` for raw binaries.
+pub fn identify(
+    write: &mut impl Write,
+    binary: &Path,
+    libs: &[PathBuf],
+    address: Option<u32>,
+) -> Result<()> {
+    let mut identifier = Identifier::new();
+    for path in libs {
+        match read(path)? {
+            Type::LIB(lib) => identifier.add_lib(&lib)?,
+            Type::OBJ(obj) => {
+                let name = path
+                    .file_stem()
+                    .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+                identifier.add(&name, &obj)?
+            }
+            _ => bail!("{} is not an OBJ or LIB", path.display()),
+        }
+    }
+
+    let (code, address) = read_code(binary, address)?;
+    write!(write, "{}", identifier.identify(&code, address))?;
+    Ok(())
+}
+
+/// Reads the text of a PS-X EXE or the whole of a raw binary and the address it is loaded at.
+fn read_code(binary: &Path, address: Option<u32>) -> Result<(Vec<u8>, u32)> {
+    let bytes = std::fs::read(binary)?;
+    if bytes.starts_with(&EXE::MAGIC) {
+        let exe = read_exe(binary)?;
+        let address = address.unwrap_or(exe.text_address());
+        Ok((exe.text().to_vec(), address))
+    } else {
+        Ok((bytes, address.unwrap_or(0x8000_0000)))
+    }
+}
+
+/// Writes a [Database] of the `LIB`s, or `DUMPOBJ` listings of `LIB`s, found under the
+/// directory of each SDK version.
+pub fn sdk_database(output: &Path, versions: &[(String, PathBuf)]) -> Result<()> {
+    let mut database = Database::new();
+    for (version, dir) in versions {
+        let mut paths = Vec::new();
+        find_files(dir, &mut paths)?;
+        paths.sort();
+        for path in paths {
+            let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            let library = path
+                .file_stem()
+                .map_or_else(String::new, |s| s.to_string_lossy().to_uppercase());
+            if extension.eq_ignore_ascii_case("lib") {
+                let lib = read_lib(&path)?;
+                database
+                    .add_lib(version, &library, &lib)
+                    .map_err(|e| anyhow!("{}: {e}", path.display()))?;
+            } else if extension.eq_ignore_ascii_case("txt") {
+                let listing = std::fs::read_to_string(&path)?;
+                // DUMPOBJ listings of OBJs have no modules
+                if listing.contains("PsyLib version") {
+                    database.add_listing(version, &library, &listing)?;
+                }
+            }
+        }
+    }
+    database.write(File::create(output)?)
+}
+
+fn find_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_files(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the SDK versions most likely used by a [LIB], [OBJ], PS-X EXE, or raw binary loaded
+/// at `address`, according to `database`.
+pub fn sdk(
+    write: &mut impl Write,
+    database: &Path,
+    input: &Path,
+    address: Option<u32>,
+) -> Result<()> {
+    let database = Database::read(File::open(database)?)?;
+    let bytes = std::fs::read(input)?;
+    let report = if bytes.starts_with(&LIB::MAGIC) {
+        database.detect_lib(&read_lib(input)?)?
+    } else if bytes.starts_with(&OBJ::MAGIC) {
+        let name = input
+            .file_stem()
+            .map_or_else(String::new, |s| s.to_string_lossy().to_uppercase());
+        database.detect_obj(&name, &read_obj(input)?)?
+    } else {
+        let (code, address) = read_code(input, address)?;
+        database.detect_code(&code, address)
+    };
+    write!(write, "{report}")?;
+    Ok(())
+}
+
 pub fn split(lib_path: &Path) -> Result<()> {
     let lib = read_lib(lib_path)?;
     println!("psyk version {}\n", crate_version!());
@@ -129,6 +413,122 @@ pub fn update(lib_path: &Path, obj_paths: Vec<PathBuf>) -> Result<()> {
     write_lib(&lib, &mut file)
 }
 
+/// The file format written by [link].
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum LinkFormat {
+    /// the memory image without any header
+    #[default]
+    Raw,
+    /// a PlayStation `PS-X EXE`
+    Exe,
+    /// a `psylink` CPE
+    Cpe,
+}
+
+/// The region marker written to a `PS-X EXE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Region {
+    Japan,
+    #[default]
+    NorthAmerica,
+    Europe,
+}
+
+impl Region {
+    fn marker(self) -> &'static str {
+        match self {
+            Self::Japan => exe::region::JAPAN,
+            Self::NorthAmerica => exe::region::NORTH_AMERICA,
+            Self::Europe => exe::region::EUROPE,
+        }
+    }
+}
+
+/// Links the OBJs and LIBs referenced by a linker script and writes the result to `output`
+/// in the requested `format`. If `map` is provided, a symbol map is written as well.
+pub fn link(script: &Path, output: &Path, format: LinkFormat, map: Option<&Path>) -> Result<()> {
+    let image = Linker::from_script(script)?.link()?;
+    match format {
+        LinkFormat::Raw => std::fs::write(output, image.to_bytes())?,
+        LinkFormat::Exe => write_exe(&EXE::from_image(&image)?, &mut File::create(output)?)?,
+        LinkFormat::Cpe => write_cpe(&CPE::from_image(&image)?, &mut File::create(output)?)?,
+    }
+    if let Some(map) = map {
+        std::fs::write(map, image.to_string())?;
+    }
+    Ok(())
+}
+
+/// Converts a [CPE] into a `PS-X EXE` or a `PS-X EXE` into a [CPE]. If `output` is not
+/// provided, the input path is used with an `EXE` or `CPE` extension.
+pub fn cpe2x(input: &Path, output: Option<&Path>, region: Region) -> Result<()> {
+    match read(input)? {
+        Type::CPE(cpe) => {
+            let output = output.map_or_else(|| input.with_extension("EXE"), Path::to_path_buf);
+            write_exe(&cpe.to_exe(region.marker())?, &mut File::create(output)?)
+        }
+        Type::EXE(exe) => {
+            let output = output.map_or_else(|| input.with_extension("CPE"), Path::to_path_buf);
+            write_cpe(&CPE::from_exe(&exe), &mut File::create(output)?)
+        }
+        _ => bail!("{} is not a CPE or PS-X EXE", input.display()),
+    }
+}
+
+/// Converts an [OBJ](super::OBJ), or every module of a [LIB], into ELF relocatable objects.
+/// An OBJ is written to `output` or next to the input with an `o` extension. The modules of a
+/// LIB are written as `<MODULE>.o` to the `output` directory or the current directory.
+pub fn obj2elf(input: &Path, output: Option<&Path>) -> Result<()> {
+    match read(input)? {
+        Type::OBJ(obj) => {
+            let output = output.map_or_else(|| input.with_extension("o"), Path::to_path_buf);
+            write_elf(&ELF::from_obj(&obj)?, &mut File::create(output)?)
+        }
+        Type::LIB(lib) => {
+            let directory = output.unwrap_or(Path::new("."));
+            std::fs::create_dir_all(directory)?;
+            for module in lib.modules() {
+                let elf = ELF::from_obj(module.object())
+                    .map_err(|e| anyhow!("{}: {e}", module.name()))?;
+                let path = directory.join(format!("{}.o", module.name()));
+                write_elf(&elf, &mut File::create(path)?)?;
+            }
+            Ok(())
+        }
+        _ => bail!("{} is not an OBJ or LIB", input.display()),
+    }
+}
+
+/// Converts an ELF relocatable object into an [OBJ](super::OBJ).
+pub fn elf2obj(input: &Path, output: Option<&Path>) -> Result<()> {
+    let obj = read_elf(input)?.to_obj()?;
+    let output = output.map_or_else(|| input.with_extension("obj"), Path::to_path_buf);
+    write_obj(&obj, &mut File::create(output)?)
+}
+
+/// Writes GNU as source for an [OBJ](super::OBJ), or for each module of a [LIB] into
+/// `output` as a directory.
+pub fn asm(input: &Path, output: Option<&Path>) -> Result<()> {
+    match read(input)? {
+        Type::OBJ(obj) => {
+            let output = output.map_or_else(|| input.with_extension("s"), Path::to_path_buf);
+            std::fs::write(output, super::asm::gnu_as(&obj)?)?;
+            Ok(())
+        }
+        Type::LIB(lib) => {
+            let directory = output.unwrap_or(Path::new("."));
+            std::fs::create_dir_all(directory)?;
+            for module in lib.modules() {
+                let source = super::asm::gnu_as(module.object())
+                    .map_err(|e| anyhow!("{}: {e}", module.name()))?;
+                std::fs::write(directory.join(format!("{}.s", module.name())), source)?;
+            }
+            Ok(())
+        }
+        _ => bail!("{} is not an OBJ or LIB", input.display()),
+    }
+}
+
 fn stem_or_psyk(path: Option<String>) -> String {
     path.and_then(|path| {
         Path::new(&path)
@@ -0,0 +1,452 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! `psylink` CPE executables.
+//!
+//! A CPE is a sequence of chunks which load data into memory and set the initial value of
+//! registers. It is the native output of `psylink` and is converted into a [PS-X EXE](EXE)
+//! by `cpe2x`.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use psyk::io;
+//! use psyk::exe::region;
+//! use anyhow::Result;
+//!
+//! fn main() -> Result<()> {
+//!     let cpe = io::read_cpe(Path::new("MAIN.CPE"))?;
+//!     let exe = cpe.to_exe(region::EUROPE)?;
+//!     io::write_exe(&exe, &mut std::fs::File::create("MAIN.EXE")?)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use binrw::binrw;
+use binrw::helpers::until;
+
+use crate::display::{self, DisplayWithOptions};
+use crate::exe::EXE;
+use crate::link::Image;
+
+/// The register number of the program counter.
+pub const PC: u16 = 0x90;
+
+/// MIPS general purpose register names, indexed by register number.
+const REGISTER_NAMES: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+    "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp",
+    "ra",
+];
+
+/// Returns the CPE register number for a register name such as `pc`, `sp`, or `r29`.
+pub fn register_number(name: &str) -> Option<u16> {
+    let name = name.to_lowercase();
+    if name == "pc" {
+        return Some(PC);
+    }
+    if let Some(n) = REGISTER_NAMES.iter().position(|&r| r == name) {
+        return Some(n as u16);
+    }
+    name.strip_prefix('r')
+        .and_then(|n| n.parse::<u16>().ok())
+        .filter(|&n| n < 32)
+}
+
+/// Returns the name of a CPE register number.
+pub fn register_name(number: u16) -> String {
+    match number {
+        PC => "pc".into(),
+        n if (n as usize) < REGISTER_NAMES.len() => REGISTER_NAMES[n as usize].into(),
+        n => format!("${n:x}"),
+    }
+}
+
+/// Data loaded into memory.
+///
+/// # Structure on Disk
+///
+/// | Offset | Type   | Description                       |
+/// |--------|--------|-----------------------------------|
+/// | 0      | `u32`  | The address to load the data at.  |
+/// | 4      | `u32`  | The size of the data.             |
+/// | 8      | `[u8]` | The data.                         |
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Load {
+    address: u32,
+    size: u32,
+    #[br(count = size)]
+    data: Vec<u8>,
+}
+
+impl Load {
+    pub fn new(address: u32, data: Vec<u8>) -> Self {
+        Self {
+            address,
+            size: data.len() as u32,
+            data,
+        }
+    }
+
+    /// Returns the address the data is loaded at.
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// A CPE chunk.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Chunk {
+    /// End of file marker.
+    ///
+    /// | Offset | Type | Description |
+    /// |--------|------|-------------|
+    /// | 0      | `u8` | Magic: 0x0  |
+    #[brw(magic(0u8))]
+    End,
+
+    /// Load data into memory.
+    ///
+    /// | Offset | Type   | Description          |
+    /// |--------|--------|----------------------|
+    /// | 0      | `u8`   | Magic: 0x1           |
+    /// | 1      | `Load` | A [Load] structure.  |
+    #[brw(magic(1u8))]
+    Load(Load),
+
+    /// Run address.
+    ///
+    /// | Offset | Type  | Description  |
+    /// |--------|-------|--------------|
+    /// | 0      | `u8`  | Magic: 0x2   |
+    /// | 1      | `u32` | The address. |
+    #[brw(magic(2u8))]
+    RunAddress(u32),
+
+    /// Set a register to a 32-bit value.
+    ///
+    /// | Offset | Type  | Description          |
+    /// |--------|-------|----------------------|
+    /// | 0      | `u8`  | Magic: 0x3           |
+    /// | 1      | `u16` | The register number. |
+    /// | 3      | `u32` | The value.           |
+    #[brw(magic(3u8))]
+    SetRegister(u16, u32),
+
+    /// Set a register to a 16-bit value.
+    ///
+    /// | Offset | Type  | Description          |
+    /// |--------|-------|----------------------|
+    /// | 0      | `u8`  | Magic: 0x4           |
+    /// | 1      | `u16` | The register number. |
+    /// | 3      | `u16` | The value.           |
+    #[brw(magic(4u8))]
+    SetRegisterWord(u16, u16),
+
+    /// Set a register to an 8-bit value.
+    ///
+    /// | Offset | Type  | Description          |
+    /// |--------|-------|----------------------|
+    /// | 0      | `u8`  | Magic: 0x5           |
+    /// | 1      | `u16` | The register number. |
+    /// | 3      | `u8`  | The value.           |
+    #[brw(magic(5u8))]
+    SetRegisterByte(u16, u8),
+
+    /// Set a register to a 24-bit value.
+    ///
+    /// | Offset | Type      | Description                  |
+    /// |--------|-----------|------------------------------|
+    /// | 0      | `u8`      | Magic: 0x6                   |
+    /// | 1      | `u16`     | The register number.         |
+    /// | 3      | `[u8; 3]` | The value, little-endian.    |
+    #[brw(magic(6u8))]
+    SetRegister3Byte(u16, [u8; 3]),
+
+    /// Select the workspace.
+    ///
+    /// | Offset | Type  | Description            |
+    /// |--------|-------|------------------------|
+    /// | 0      | `u8`  | Magic: 0x7             |
+    /// | 1      | `u32` | The workspace address. |
+    #[brw(magic(7u8))]
+    Workspace(u32),
+
+    /// Select the target unit.
+    ///
+    /// | Offset | Type | Description      |
+    /// |--------|------|------------------|
+    /// | 0      | `u8` | Magic: 0x8       |
+    /// | 1      | `u8` | The unit number. |
+    #[brw(magic(8u8))]
+    Unit(u8),
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::End => write!(f, "0 : End of file"),
+            Self::Load(load) => write!(
+                f,
+                "1 : Load {} bytes at ${:08x}",
+                load.data.len(),
+                load.address
+            ),
+            Self::RunAddress(address) => write!(f, "2 : Run address ${address:08x}"),
+            Self::SetRegister(register, value) => write!(
+                f,
+                "3 : Set register {} to ${value:08x}",
+                register_name(*register)
+            ),
+            Self::SetRegisterWord(register, value) => write!(
+                f,
+                "4 : Set register {} to ${value:04x}",
+                register_name(*register)
+            ),
+            Self::SetRegisterByte(register, value) => write!(
+                f,
+                "5 : Set register {} to ${value:02x}",
+                register_name(*register)
+            ),
+            Self::SetRegister3Byte(register, value) => write!(
+                f,
+                "6 : Set register {} to ${:06x}",
+                register_name(*register),
+                u32::from_le_bytes([value[0], value[1], value[2], 0])
+            ),
+            Self::Workspace(address) => write!(f, "7 : Select workspace ${address:08x}"),
+            Self::Unit(unit) => write!(f, "8 : Select unit {unit}"),
+        }
+    }
+}
+
+/// A `psylink` executable.
+///
+/// # Structure on Disk
+///
+/// | Offset | Type      | Description                                       |
+/// |--------|-----------|---------------------------------------------------|
+/// | 0      | `[u8; 4]` | Magic: "CPE" followed by the version (1).         |
+/// | 4      | `[Chunk]` | [Chunk]s terminated by [End](Chunk::End).         |
+#[binrw]
+#[brw(little, magic = b"CPE\x01")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CPE {
+    #[br(parse_with = until(|chunk: &Chunk| matches!(chunk, Chunk::End)))]
+    chunks: Vec<Chunk>,
+}
+
+impl CPE {
+    /// Creates a CPE from chunks. An [End](Chunk::End) chunk is appended if missing.
+    pub fn new(mut chunks: Vec<Chunk>) -> Self {
+        if !matches!(chunks.last(), Some(Chunk::End)) {
+            chunks.push(Chunk::End);
+        }
+        Self { chunks }
+    }
+
+    /// Creates a CPE from a linked [Image]. Each group that is not an overlay is written as a
+    /// separate load chunk followed by any registers set with `regs`.
+    pub fn from_image(image: &Image) -> Result<Self> {
+        let mut chunks = vec![Chunk::Unit(0)];
+        for group in image
+            .groups()
+            .iter()
+            .filter(|g| g.overlay().is_none() && !g.data().is_empty())
+        {
+            chunks.push(Chunk::Load(Load::new(
+                group.address(),
+                group.data().to_vec(),
+            )));
+        }
+        for (name, &value) in image.registers() {
+            let Some(register) = register_number(name) else {
+                bail!("Unknown register {name}");
+            };
+            chunks.push(Chunk::SetRegister(register, value));
+        }
+        Ok(Self::new(chunks))
+    }
+
+    /// Creates a CPE which loads the text of an [EXE] and sets the initial PC, GP, and SP.
+    pub fn from_exe(exe: &EXE) -> Self {
+        let mut chunks = vec![
+            Chunk::Unit(0),
+            Chunk::Load(Load::new(exe.text_address(), exe.text().to_vec())),
+            Chunk::SetRegister(PC, exe.pc()),
+        ];
+        if exe.gp() != 0 {
+            chunks.push(Chunk::SetRegister(28, exe.gp()));
+        }
+        let (stack, offset) = exe.stack();
+        if stack != 0 {
+            chunks.push(Chunk::SetRegister(29, stack.wrapping_add(offset)));
+        }
+        Self::new(chunks)
+    }
+
+    pub fn chunks(&self) -> &Vec<Chunk> {
+        &self.chunks
+    }
+
+    /// Returns the value of a register after all chunks have been applied.
+    pub fn register(&self, register: u16) -> Option<u32> {
+        self.chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                Chunk::SetRegister(r, value) if *r == register => Some(*value),
+                Chunk::SetRegisterWord(r, value) if *r == register => Some(u32::from(*value)),
+                Chunk::SetRegisterByte(r, value) if *r == register => Some(u32::from(*value)),
+                Chunk::SetRegister3Byte(r, value) if *r == register => {
+                    Some(u32::from_le_bytes([value[0], value[1], value[2], 0]))
+                }
+                _ => None,
+            })
+            .next_back()
+    }
+
+    /// Converts the CPE into an [EXE] with the provided region marker.
+    ///
+    /// All load chunks are combined into a single text segment and the initial PC must be set.
+    pub fn to_exe(&self, region: &str) -> Result<EXE> {
+        let loads = self
+            .chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                Chunk::Load(load) if !load.data.is_empty() => Some(load),
+                _ => None,
+            })
+            .collect::<Vec<&Load>>();
+        let Some(start) = loads.iter().map(|load| load.address).min() else {
+            bail!("CPE does not contain any data to load");
+        };
+        let mut text = Vec::new();
+        for load in loads {
+            let offset = (load.address - start) as usize;
+            if text.len() < offset + load.data.len() {
+                text.resize(offset + load.data.len(), 0);
+            }
+            text[offset..offset + load.data.len()].copy_from_slice(&load.data);
+        }
+
+        let Some(pc) = self.register(PC) else {
+            bail!("CPE does not set the initial PC");
+        };
+        let mut exe = EXE::new(pc, self.register(28).unwrap_or(0), start, text);
+        if let Some(sp) = self.register(29) {
+            exe.set_stack(sp, 0);
+        }
+        exe.set_region(region);
+        Ok(exe)
+    }
+}
+
+impl fmt::Display for CPE {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_options(f, &display::Options::default())
+    }
+}
+
+impl display::DisplayWithOptions for CPE {
+    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
+        options.write_indent(f)?;
+        writeln!(f, "Header : CPE version 1")?;
+        for chunk in &self.chunks {
+            options.write_indent(f)?;
+            writeln!(f, "{chunk}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use binrw::io::Cursor;
+    use binrw::{BinRead, BinWrite};
+
+    use crate::exe::region;
+
+    #[test]
+    fn test_cpe() {
+        let bytes: Vec<u8> = vec![
+            b'C', b'P', b'E', 1, // magic
+            8, 0, // unit 0
+            1, 0x00, 0x00, 0x01, 0x80, 0x04, 0x00, 0x00, 0x00, 1, 2, 3, 4, // load
+            3, 0x90, 0x00, 0x00, 0x00, 0x01, 0x80, // pc
+            5, 0x1D, 0x00, 0x7F, // sp
+            0,    // end
+        ];
+        let cpe = CPE::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(5, cpe.chunks().len());
+        assert_eq!(Some(0x80010000), cpe.register(PC));
+        assert_eq!(Some(0x7F), cpe.register(29));
+        assert_eq!(
+            "\
+            Header : CPE version 1\n\
+            8 : Select unit 0\n\
+            1 : Load 4 bytes at $80010000\n\
+            3 : Set register pc to $80010000\n\
+            5 : Set register sp to $7f\n\
+            0 : End of file\n",
+            cpe.to_string()
+        );
+
+        let mut writer = Cursor::new(Vec::new());
+        cpe.write(&mut writer).unwrap();
+        assert_eq!(bytes, writer.into_inner());
+
+        // unknown chunks are errors
+        let bytes: Vec<u8> = vec![b'C', b'P', b'E', 1, 9, 0];
+        assert!(CPE::read(&mut Cursor::new(&bytes)).is_err());
+    }
+
+    #[test]
+    fn test_cpe2x() {
+        let cpe = CPE::new(vec![
+            Chunk::Load(Load::new(0x80010010, vec![5, 6])),
+            Chunk::Load(Load::new(0x80010000, vec![1, 2, 3, 4])),
+            Chunk::SetRegister(PC, 0x80010000),
+        ]);
+        let exe = cpe.to_exe(region::EUROPE).unwrap();
+        assert_eq!(0x80010000, exe.pc());
+        assert_eq!(0x80010000, exe.text_address());
+        assert_eq!([1, 2, 3, 4], exe.text()[0..4]);
+        assert_eq!([5, 6], exe.text()[0x10..0x12]);
+        assert_eq!(region::EUROPE, exe.region());
+
+        let cpe = CPE::from_exe(&exe);
+        assert_eq!(
+            vec![
+                Chunk::Unit(0),
+                Chunk::Load(Load::new(0x80010000, exe.text().to_vec())),
+                Chunk::SetRegister(PC, 0x80010000),
+                Chunk::SetRegister(29, crate::exe::DEFAULT_STACK),
+                Chunk::End,
+            ],
+            *cpe.chunks()
+        );
+
+        let error = CPE::new(vec![]).to_exe(region::JAPAN).unwrap_err();
+        assert_eq!("CPE does not contain any data to load", error.to_string());
+    }
+
+    #[test]
+    fn test_register_number() {
+        assert_eq!(Some(PC), register_number("PC"));
+        assert_eq!(Some(29), register_number("sp"));
+        assert_eq!(Some(31), register_number("r31"));
+        assert_eq!(None, register_number("r32"));
+        assert_eq!("gp", register_name(28));
+    }
+}
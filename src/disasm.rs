@@ -0,0 +1,732 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Disassembly of [Code] annotated with the [Patch]es that relocate it.
+//!
+//! A [Listing] follows the sections of an [OBJ] to know the processor and the offset of each
+//! `Code` section in the section it belongs to, and resolves the numbers used in patch
+//! expressions to the names of symbols and sections. MIPS code is decoded with `rabbitizer`
+//! and [gte] for the PlayStation's GTE coprocessor, SH-2 code with [sh2], 680x0 code with
+//! [m68k], Z80 code with [z80], and 65816 code with [w65816]. The 65816 register widths start
+//! with the [SetMXInfo](crate::SetMXInfo) in effect for the code and follow the `rep` and `sep`
+//! instructions in it.
+//!
+//! ```
+//! use psyk::disasm::Listing;
+//! use psyk::{Expression, LNKHeader, Patch, Section, OBJ, XREF};
+//!
+//! let obj = OBJ::new(vec![
+//!     Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+//!     Section::XREF(XREF::new(2, "InitGeom".into())),
+//!     Section::NOP,
+//! ]);
+//! let listing = Listing::new(&obj);
+//! let patch = Patch::new(74, 0, Expression::SymbolAddressIndex(2));
+//! assert_eq!("jal         InitGeom", listing.mips(0x0C000000, 0, Some(&patch)));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use rabbitizer::{InstrCategory, Instruction};
+
+use crate::display::Options;
+use crate::model;
+use crate::patch::{mips, Target};
+use crate::{Code, Expression, Patch, Section, OBJ};
+
+pub mod gte;
+pub mod m68k;
+pub mod sh2;
+pub mod w65816;
+pub mod z80;
+
+/// Names and positions used to disassemble the [Code] of an [OBJ].
+#[derive(Clone, Debug, Default)]
+pub struct Listing {
+    names: HashMap<u16, String>,
+    target: Target,
+    section: u16,
+    offsets: HashMap<u16, u32>,
+    mx: HashMap<u16, BTreeMap<u32, u8>>,
+}
+
+impl Listing {
+    /// Creates a listing for `obj` positioned before its first section.
+    pub fn new(obj: &OBJ) -> Self {
+        let mut names = HashMap::new();
+        let mut mx: HashMap<u16, BTreeMap<u32, u8>> = HashMap::new();
+        let mut current = 0;
+        for section in obj.sections() {
+            match section {
+                Section::SectionSwitch(number) | Section::RunAtOffset(number, _) => {
+                    current = *number
+                }
+                Section::SetMXInfo(info) => {
+                    mx.entry(current)
+                        .or_default()
+                        .insert(u32::from(info.offset()), info.value());
+                }
+                _ => (),
+            }
+            let (number, name) = match section {
+                Section::LNKHeader(header) => (header.section(), header.type_name()),
+                Section::XDEF(xdef) => (xdef.number(), xdef.symbol_name()),
+                Section::XREF(xref) => (xref.number(), xref.symbol_name()),
+                Section::XBSS(xbss) => (xbss.number(), xbss.name()),
+                Section::GroupSymbol(group) => (group.number(), group.name()),
+                _ => continue,
+            };
+            names.insert(number, name);
+        }
+        Self {
+            names,
+            mx,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the names of the symbols, sections, and groups by number.
+    pub fn names(&self) -> &HashMap<u16, String> {
+        &self.names
+    }
+
+    /// Returns the name of the symbol, section, or group numbered `number`.
+    pub fn name(&self, number: u16) -> Option<&str> {
+        self.names.get(&number).map(String::as_str)
+    }
+
+    /// Returns the offset in the current section where the next [Code] will be placed.
+    pub fn offset(&self) -> u32 {
+        self.offsets.get(&self.section).copied().unwrap_or_default()
+    }
+
+    /// Returns the 65816 flags in effect at `offset` in the current section, as set by the
+    /// last [SetMXInfo](crate::SetMXInfo) at or before it.
+    pub fn flags(&self, offset: u32) -> w65816::Flags {
+        self.mx
+            .get(&self.section)
+            .and_then(|mx| mx.range(..=offset).next_back())
+            .map_or_else(w65816::Flags::default, |(_, value)| {
+                w65816::Flags::from_mx(*value)
+            })
+    }
+
+    /// Returns the processor and patch sizes of the code.
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Moves past `section`.
+    pub fn advance(&mut self, section: &Section) {
+        self.target.update(section);
+        match section {
+            Section::SectionSwitch(number) => self.section = *number,
+            Section::RunAtOffset(number, offset) => {
+                self.section = *number;
+                self.offsets.insert(*number, u32::from(*offset));
+            }
+            Section::Code(code) => {
+                *self.offsets.entry(self.section).or_default() += code.code().len() as u32
+            }
+            Section::BSS(size) => *self.offsets.entry(self.section).or_default() += size,
+            _ => (),
+        }
+    }
+
+    /// Formats `expression` using the names of the symbols and sections it refers to,
+    /// e.g. `sym+0x10`.
+    pub fn symbolic(&self, expression: &Expression) -> String {
+        match model::address(expression) {
+            Some((address, addend)) => self.address(address, addend),
+            None => expression.to_string(),
+        }
+    }
+
+    /// Formats a symbol or section `address` and the constant added to it.
+    fn address(&self, address: &Expression, addend: i64) -> String {
+        let name = match address {
+            Expression::SymbolAddressIndex(number) | Expression::SectionAddressIndex(number) => {
+                self.name(*number)
+            }
+            _ => None,
+        };
+        model::format_address(
+            &name.map_or_else(|| address.to_string(), str::to_string),
+            addend,
+        )
+    }
+
+    /// Disassembles the MIPS instruction `word` at `offset` in the current section. The
+    /// operand relocated by `patch` is replaced with the symbol it refers to.
+    ///
+    /// GTE commands and registers are shown with the names used by [gte].
+    pub fn mips(&self, word: u32, offset: u32, patch: Option<&Patch>) -> String {
+        match gte::command(word) {
+            Some(command) => command,
+            None => gte::name_registers(word, self.mips_operands(word, offset, patch)),
+        }
+    }
+
+    fn mips_operands(&self, word: u32, offset: u32, patch: Option<&Patch>) -> String {
+        let instruction = Instruction::new(word, offset, InstrCategory::R3000GTE);
+        let Some(patch) = patch else {
+            return instruction.disassemble(None, 0);
+        };
+        let expression = patch.expression();
+        let operand = match (patch.tag(), model::patch_address(&self.target, patch)) {
+            (mips::WORD, _) => return format!("{:<12}{}", ".word", self.symbolic(expression)),
+            (mips::JUMP, _) => self.symbolic(expression),
+            (mips::HI16, _) => format!("%hi({})", self.symbolic(expression)),
+            (mips::LO16, _) => format!("%lo({})", self.symbolic(expression)),
+            (mips::GP_RELATIVE, Some((address, addend))) => {
+                format!("%gp_rel({})", self.address(address, addend))
+            }
+            (tag, _) => {
+                return format!(
+                    "{}   ; patch type {tag} with {}",
+                    instruction.disassemble(None, 0),
+                    self.symbolic(expression)
+                )
+            }
+        };
+        instruction.disassemble(Some(&operand), 0)
+    }
+
+    /// Writes the disassembly of `code`, which is followed by `patches`, at the current
+    /// offset with the [Architecture](crate::arch::Architecture) of the current processor.
+    /// Code for unknown processors is disassembled as MIPS.
+    pub fn write_code(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        match self.target.architecture() {
+            Some(architecture) => architecture.write_code(self, f, options, code, patches),
+            None => self.write_mips(f, options, code, patches),
+        }
+    }
+
+    /// Writes the disassembly of MIPS `code`, which is followed by `patches`, at the current
+    /// offset.
+    pub fn write_mips(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        let start = self.offset();
+        for (i, instruction) in code.code().chunks(4).enumerate() {
+            if instruction.len() == 4 {
+                let ins = u32::from_le_bytes(instruction.try_into().unwrap());
+                let offset = i * 4;
+                let patch = patches
+                    .iter()
+                    .find(|p| usize::from(p.offset()) == offset)
+                    .copied();
+                let asm = self.mips(ins, start + offset as u32, patch);
+                options.write_indent(f)?;
+                writeln!(f, "    /* {ins:08x} */   {asm}")?;
+            } else {
+                write!(f, "    /* ")?;
+                for byte in instruction {
+                    write!(f, "{byte:02x}")?;
+                }
+                writeln!(f, " */ ; invalid")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the disassembly of SH-2 `code`, which is followed by `patches`, at the current
+    /// offset.
+    ///
+    /// Literals loaded by PC relative instructions are shown as data, and instructions in
+    /// delay slots are indented by a space.
+    pub fn write_sh2(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        let start = self.offset();
+        let bytes = code.code().as_slice();
+        let in_code = |address: u32| address.checked_sub(start).map(|offset| offset as usize);
+        let patched = |offset: usize, size: usize| {
+            patches
+                .iter()
+                .find(|p| (offset..offset + size).contains(&usize::from(p.offset())))
+        };
+        // the symbol or value stored at `offset`
+        let value = |offset: usize, size: usize| {
+            let data = bytes.get(offset..offset + size)?;
+            Some(match patched(offset, size) {
+                Some(patch) => self.symbolic(patch.expression()),
+                None => format!(
+                    "{:#x}",
+                    data.iter().fold(0u32, |v, b| (v << 8) | u32::from(*b))
+                ),
+            })
+        };
+
+        let mut literals = HashMap::new();
+        for (i, word) in bytes.chunks_exact(2).enumerate() {
+            let word = u16::from_be_bytes([word[0], word[1]]);
+            let instruction = sh2::Instruction::new(word, start + i as u32 * 2);
+            if let Some((offset, size)) = instruction
+                .literal()
+                .and_then(|(address, size)| Some((in_code(address)?, size)))
+            {
+                literals.insert(offset, size);
+            }
+        }
+
+        let mut offset = 0;
+        let mut delay_slot = false;
+        while offset < bytes.len() {
+            let long = patched(offset, 1)
+                .is_some_and(|p| self.target.size(p.tag()) == Some(4) && offset % 2 == 0);
+            let size = if long {
+                4
+            } else {
+                literals.get(&offset).copied().unwrap_or(2)
+            };
+            let Some(data) = bytes.get(offset..offset + size) else {
+                write!(f, "    /* ")?;
+                for byte in &bytes[offset..] {
+                    write!(f, "{byte:02x}")?;
+                }
+                writeln!(f, " */ ; invalid")?;
+                break;
+            };
+
+            options.write_indent(f)?;
+            if long || literals.contains_key(&offset) {
+                let directive = if size == 4 { ".long" } else { ".word" };
+                let operand = value(offset, size).unwrap_or_default();
+                write!(f, "    /* ")?;
+                for byte in data {
+                    write!(f, "{byte:02x}")?;
+                }
+                writeln!(f, " */   {directive:<12}{operand}")?;
+                offset += size;
+                delay_slot = false;
+                continue;
+            }
+
+            let word = u16::from_be_bytes([data[0], data[1]]);
+            let instruction = sh2::Instruction::new(word, start + offset as u32);
+            let mut asm = instruction.disassemble();
+            if delay_slot {
+                asm.insert(0, ' ');
+            }
+            if let Some(patch) = patched(offset, 2) {
+                asm = format!("{asm}   ; {}", self.symbolic(patch.expression()));
+            } else if let Some((literal, size)) = instruction.literal() {
+                // show the value loaded from the literal pool, `mova` only loads its address
+                if let Some(value) = in_code(literal)
+                    .filter(|_| word >> 12 != 0xC)
+                    .and_then(|offset| value(offset, size))
+                {
+                    asm = format!("{asm}   ; {value}");
+                }
+            }
+            writeln!(f, "    /* {word:04x} */   {asm}")?;
+            delay_slot = instruction.has_delay_slot();
+            offset += 2;
+        }
+        Ok(())
+    }
+
+    /// Writes the disassembly of 680x0 `code`, which is followed by `patches`, at the current
+    /// offset.
+    pub fn write_m68k(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+        model: m68k::Model,
+    ) -> fmt::Result {
+        self.write_variable(
+            f,
+            options,
+            code,
+            patches,
+            &mut |bytes, offset, address, symbol| {
+                let instruction =
+                    m68k::Instruction::decode_with(bytes, offset, address, model, symbol);
+                (instruction.text().to_string(), instruction.size())
+            },
+        )
+    }
+
+    /// Writes the disassembly of Z80 `code`, which is followed by `patches`, at the current
+    /// offset.
+    pub fn write_z80(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        self.write_variable(
+            f,
+            options,
+            code,
+            patches,
+            &mut |bytes, offset, address, symbol| {
+                let instruction = z80::Instruction::decode_with(bytes, offset, address, symbol);
+                (instruction.text().to_string(), instruction.size())
+            },
+        )
+    }
+
+    /// Writes the disassembly of 65816 `code`, which is followed by `patches`, at the current
+    /// offset.
+    ///
+    /// The register widths are set by the [SetMXInfo](crate::SetMXInfo) for the section and
+    /// by `rep` and `sep`.
+    pub fn write_w65816(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+    ) -> fmt::Result {
+        let start = self.offset();
+        let mx = self.mx.get(&self.section);
+        let mut flags = self.flags(start);
+        self.write_variable(
+            f,
+            options,
+            code,
+            patches,
+            &mut |bytes, offset, address, symbol| {
+                if let Some(value) = mx.and_then(|mx| mx.get(&(start + offset as u32))) {
+                    flags = w65816::Flags::from_mx(*value);
+                }
+                let instruction =
+                    w65816::Instruction::decode_with(bytes, offset, address, flags, symbol);
+                flags = instruction.flags();
+                (instruction.text().to_string(), instruction.size())
+            },
+        )
+    }
+
+    /// Writes the disassembly of `code` with variable length instructions. `decode` is
+    /// called with the code, the offset and address of each instruction, and a function that
+    /// returns the symbol relocating an offset, and returns the assembly and size of the
+    /// instruction.
+    fn write_variable(
+        &self,
+        f: &mut fmt::Formatter,
+        options: &Options,
+        code: &Code,
+        patches: &[&Patch],
+        decode: &mut Decode,
+    ) -> fmt::Result {
+        let start = self.offset();
+        let bytes = code.code().as_slice();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let mut used = Vec::new();
+            let (mut asm, size) = decode(bytes, offset, start + offset as u32, &mut |at| {
+                let patch = patches.iter().find(|p| usize::from(p.offset()) == at)?;
+                used.push(at);
+                Some(self.symbolic(patch.expression()))
+            });
+            let end = offset + size;
+            if end > bytes.len() {
+                write!(f, "    /* ")?;
+                for byte in &bytes[offset..] {
+                    write!(f, "{byte:02x}")?;
+                }
+                writeln!(f, " */ ; invalid")?;
+                break;
+            }
+            // patches that do not relocate an operand
+            for patch in patches {
+                let at = usize::from(patch.offset());
+                if (offset..end).contains(&at) && !used.contains(&at) {
+                    asm = format!("{asm}   ; {}", self.symbolic(patch.expression()));
+                }
+            }
+
+            options.write_indent(f)?;
+            write!(f, "    /* ")?;
+            for byte in &bytes[offset..end] {
+                write!(f, "{byte:02x}")?;
+            }
+            writeln!(f, " */   {asm}")?;
+            offset = end;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the instruction at an offset in code, see [Listing::write_variable].
+type Decode<'a> =
+    dyn FnMut(&[u8], usize, u32, &mut dyn FnMut(usize) -> Option<String>) -> (String, usize) + 'a;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cputype, LNKHeader, XDEF, XREF};
+
+    fn listing() -> Listing {
+        Listing::new(&OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".sdata".into())),
+            Section::XDEF(XDEF::new(3, 1, 0, "main".into())),
+            Section::XREF(XREF::new(4, "sym".into())),
+            Section::NOP,
+        ]))
+    }
+
+    #[test]
+    fn test_symbolic() {
+        use Expression as E;
+        let listing = listing();
+        assert_eq!("sym", listing.symbolic(&E::SymbolAddressIndex(4)));
+        assert_eq!(".sdata", listing.symbolic(&E::SectionAddressIndex(2)));
+        assert_eq!(
+            "sym+0x10",
+            listing.symbolic(&E::Add(
+                Box::new(E::SymbolAddressIndex(4)),
+                Box::new(E::Constant(0x10))
+            ))
+        );
+        assert_eq!(
+            "main-0x4",
+            listing.symbolic(&E::Subtract(
+                Box::new(E::Constant(4)),
+                Box::new(E::SymbolAddressIndex(3))
+            ))
+        );
+        assert_eq!("[9]", listing.symbolic(&E::SymbolAddressIndex(9)));
+        assert_eq!("sectend(1)", listing.symbolic(&E::SectionEnd(1)));
+    }
+
+    #[test]
+    fn test_mips() {
+        use Expression as E;
+        let listing = listing();
+        let sym = || {
+            E::Add(
+                Box::new(E::SymbolAddressIndex(4)),
+                Box::new(E::Constant(0x10)),
+            )
+        };
+        assert_eq!(
+            "jal         main",
+            listing.mips(
+                0x0C000000,
+                0,
+                Some(&Patch::new(mips::JUMP, 0, E::SymbolAddressIndex(3)))
+            )
+        );
+        assert_eq!(
+            "lui         $a0, %hi(sym+0x10)",
+            listing.mips(0x3C040000, 0, Some(&Patch::new(mips::HI16, 0, sym())))
+        );
+        assert_eq!(
+            "addiu       $a0, $a0, %lo(sym+0x10)",
+            listing.mips(0x24840000, 0, Some(&Patch::new(mips::LO16, 0, sym())))
+        );
+        assert_eq!(
+            "lw          $v1, %gp_rel(sym)($gp)",
+            listing.mips(
+                0x8F830000,
+                0,
+                Some(&Patch::new(
+                    mips::GP_RELATIVE,
+                    0,
+                    E::Subtract(
+                        Box::new(E::SectionStart(2)),
+                        Box::new(E::SymbolAddressIndex(4))
+                    )
+                ))
+            )
+        );
+        assert_eq!(
+            ".word       main",
+            listing.mips(
+                0,
+                0,
+                Some(&Patch::new(mips::WORD, 0, E::SymbolAddressIndex(3)))
+            )
+        );
+        // GTE commands and registers
+        assert_eq!("rtps        sf=1,lm=0", listing.mips(0x4A180001, 0, None));
+        assert_eq!("mfc2        $t0, $sxy2", listing.mips(0x48087000, 0, None));
+        assert_eq!(
+            "lwc2        $vxy0, %lo(sym+0x10)($a0)",
+            listing.mips(0xC8800000, 0, Some(&Patch::new(mips::LO16, 0, sym())))
+        );
+        // jumps without a patch are relative to the section
+        assert_eq!("j           func_000010", listing.mips(0x08000004, 8, None));
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut listing = Listing::default();
+        listing.advance(&Section::SectionSwitch(1));
+        listing.advance(&Section::Code(Code::new(vec![0; 8]).unwrap()));
+        assert_eq!(8, listing.offset());
+        listing.advance(&Section::SectionSwitch(2));
+        listing.advance(&Section::BSS(4));
+        assert_eq!(4, listing.offset());
+        listing.advance(&Section::SectionSwitch(1));
+        assert_eq!(8, listing.offset());
+        listing.advance(&Section::RunAtOffset(2, 0x20));
+        assert_eq!(0x20, listing.offset());
+    }
+
+    /// Asserts that the listing of `sections` in a `text` section of a `cpu` object, which
+    /// may refer to `_func` as symbol 2, contains `expected`.
+    fn assert_disassembly(cpu: u8, sections: Vec<Section>, expected: &str) {
+        use crate::display::{CodeFormat, PsyXDisplayable};
+
+        let obj = OBJ::new(
+            [
+                Section::CPU(cpu),
+                Section::LNKHeader(LNKHeader::new(1, 0, 1, "text".into())),
+                Section::SectionSwitch(1),
+            ]
+            .into_iter()
+            .chain(sections)
+            .chain([Section::XREF(XREF::new(2, "_func".into())), Section::NOP])
+            .collect(),
+        );
+        let options = Options {
+            code_format: CodeFormat::Disassembly,
+            ..Default::default()
+        };
+        let listing = PsyXDisplayable::wrap(&obj, options).to_string();
+        assert!(listing.contains(expected), "{listing}");
+    }
+
+    #[test]
+    fn test_sh2() {
+        assert_disassembly(
+            cputype::HITACHI_SH2,
+            vec![
+                Section::Code(
+                    Code::new(vec![
+                        0x4F, 0x22, 0xD1, 0x03, 0x41, 0x0B, 0x00, 0x09, //
+                        0x4F, 0x26, 0x00, 0x0B, 0x00, 0x09, 0x00, 0x09, //
+                        0x00, 0x00, 0x00, 0x00,
+                    ])
+                    .unwrap(),
+                ),
+                Section::Patch(Patch::new(8, 0x10, Expression::SymbolAddressIndex(2))),
+            ],
+            "\
+            2 : Code 20 bytes\n\
+            \n    \
+            /* 4f22 */   sts.l       pr,@-r15\n    \
+            /* d103 */   mov.l       0x10,r1   ; _func\n    \
+            /* 410b */   jsr         @r1\n    \
+            /* 0009 */    nop\n    \
+            /* 4f26 */   lds.l       @r15+,pr\n    \
+            /* 000b */   rts\n    \
+            /* 0009 */    nop\n    \
+            /* 0009 */   nop\n    \
+            /* 00000000 */   .long       _func\n\
+            \n\
+            10 : Patch type 8 at offset 10 with [2]\n",
+        );
+    }
+
+    #[test]
+    fn test_m68k() {
+        // jsr _func; addq.l #4,sp; rts
+        assert_disassembly(
+            cputype::MOTOROLA_68000,
+            vec![
+                Section::Code(
+                    Code::new(vec![
+                        0x4E, 0xB9, 0x00, 0x00, 0x00, 0x00, 0x58, 0x8F, 0x4E, 0x75,
+                    ])
+                    .unwrap(),
+                ),
+                Section::Patch(Patch::new(8, 2, Expression::SymbolAddressIndex(2))),
+            ],
+            "\
+            2 : Code 10 bytes\n\
+            \n    \
+            /* 4eb900000000 */   jsr         _func\n    \
+            /* 588f */   addq.l      #4,sp\n    \
+            /* 4e75 */   rts\n",
+        );
+    }
+
+    #[test]
+    fn test_z80() {
+        // call _func; ret
+        assert_disassembly(
+            cputype::ZILOG_Z80,
+            vec![
+                Section::Code(Code::new(vec![0xCD, 0x00, 0x00, 0xC9]).unwrap()),
+                Section::Patch(Patch::new(16, 1, Expression::SymbolAddressIndex(2))),
+            ],
+            "\
+            2 : Code 4 bytes\n\
+            \n    \
+            /* cd0000 */   call        _func\n    \
+            /* c9 */   ret\n",
+        );
+    }
+
+    #[test]
+    fn test_w65816() {
+        use crate::SetMXInfo;
+
+        // lda #$1234; sep #$20; lda #$12; jsl _func
+        assert_disassembly(
+            cputype::WDC_65816,
+            vec![
+                Section::SetMXInfo(SetMXInfo::new(0, 0)),
+                Section::Code(
+                    Code::new(vec![
+                        0xA9, 0x34, 0x12, 0xE2, 0x20, 0xA9, 0x12, 0x22, 0x00, 0x00, 0x00,
+                    ])
+                    .unwrap(),
+                ),
+                Section::Patch(Patch::new(12, 8, Expression::SymbolAddressIndex(2))),
+            ],
+            "\
+            2 : Code 11 bytes\n\
+            \n    \
+            /* a93412 */   lda         #$1234\n    \
+            /* e220 */   sep         #$20\n    \
+            /* a912 */   lda         #$12\n    \
+            /* 22000000 */   jsl         _func\n",
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        use crate::SetMXInfo;
+
+        let listing = Listing::new(&OBJ::new(vec![
+            Section::SectionSwitch(1),
+            Section::SetMXInfo(SetMXInfo::new(0x10, 0)),
+            Section::SectionSwitch(2),
+            Section::SetMXInfo(SetMXInfo::new(0, 1)),
+            Section::NOP,
+        ]));
+        assert_eq!(w65816::Flags::default(), listing.flags(0x10));
+        let mut listing = listing;
+        listing.advance(&Section::SectionSwitch(1));
+        assert_eq!(w65816::Flags::default(), listing.flags(0));
+        assert_eq!(w65816::Flags::new(0), listing.flags(0x20));
+        listing.advance(&Section::SectionSwitch(2));
+        assert_eq!(w65816::Flags::new(w65816::Flags::X), listing.flags(0));
+    }
+}
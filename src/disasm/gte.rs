@@ -0,0 +1,165 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! PlayStation GTE (Geometry Transformation Engine) instructions.
+//!
+//! The GTE is coprocessor 2 of the PlayStation's R3000. Its commands are `cop2` instructions
+//! whose low 25 bits select the operation and its fields: `sf` shifts results right by 12
+//! bits, `lm` limits them to positive values, and `mvmva` multiplies the vector `v` by the
+//! matrix `mx` and adds the translation vector `cv`. Its registers are moved with
+//! `mfc2`/`mtc2`, `cfc2`/`ctc2`, and `lwc2`/`swc2`.
+//!
+//! ```
+//! use psyk::disasm::gte;
+//!
+//! assert_eq!(Some("rtps        sf=1,lm=0".into()), gte::command(0x4A180001));
+//! assert_eq!("sxy0", gte::data_register(12));
+//! assert_eq!("flag", gte::control_register(31));
+//! ```
+
+/// Names of the data registers used by `mfc2`, `mtc2`, `lwc2`, and `swc2`.
+const DATA: [&str; 32] = [
+    "vxy0", "vz0", "vxy1", "vz1", "vxy2", "vz2", "rgbc", "otz", //
+    "ir0", "ir1", "ir2", "ir3", "sxy0", "sxy1", "sxy2", "sxyp", //
+    "sz0", "sz1", "sz2", "sz3", "rgb0", "rgb1", "rgb2", "res1", //
+    "mac0", "mac1", "mac2", "mac3", "irgb", "orgb", "lzcs", "lzcr",
+];
+
+/// Names of the control registers used by `cfc2` and `ctc2`.
+const CONTROL: [&str; 32] = [
+    "r11r12", "r13r21", "r22r23", "r31r32", "r33", "trx", "try", "trz", //
+    "l11l12", "l13l21", "l22l23", "l31l32", "l33", "rbk", "gbk", "bbk", //
+    "lr1lr2", "lr3lg1", "lg2lg3", "lb1lb2", "lb3", "rfc", "gfc", "bfc", //
+    "ofx", "ofy", "h", "dqa", "dqb", "zsf3", "zsf4", "flag",
+];
+
+const COP2: u32 = 0x12;
+const LWC2: u32 = 0x32;
+const SWC2: u32 = 0x3A;
+
+/// Returns the name of data register `register`.
+pub fn data_register(register: u32) -> &'static str {
+    DATA[(register & 31) as usize]
+}
+
+/// Returns the name of control register `register`.
+pub fn control_register(register: u32) -> &'static str {
+    CONTROL[(register & 31) as usize]
+}
+
+/// Returns the assembly for the GTE command `word`, or `None` if `word` is not a `cop2`
+/// command.
+pub fn command(word: u32) -> Option<String> {
+    if word >> 26 != COP2 || word & (1 << 25) == 0 {
+        return None;
+    }
+    let sf = (word >> 19) & 1;
+    let lm = (word >> 10) & 1;
+    let mnemonic = match word & 0x3F {
+        0x01 => "rtps",
+        0x06 => return Some("nclip".into()),
+        0x0C => "op",
+        0x10 => "dpcs",
+        0x11 => "intpl",
+        0x12 => {
+            let mx = ["rt", "llm", "lcm", "3"][((word >> 17) & 3) as usize];
+            let v = ["v0", "v1", "v2", "ir"][((word >> 15) & 3) as usize];
+            let cv = ["tr", "bk", "fc", "none"][((word >> 13) & 3) as usize];
+            return Some(format!(
+                "{:<12}sf={sf},mx={mx},v={v},cv={cv},lm={lm}",
+                "mvmva"
+            ));
+        }
+        0x13 => "ncds",
+        0x14 => "cdp",
+        0x16 => "ncdt",
+        0x1B => "nccs",
+        0x1C => "cc",
+        0x1E => "ncs",
+        0x20 => "nct",
+        0x28 => "sqr",
+        0x29 => "dcpl",
+        0x2A => "dpct",
+        0x2D => return Some("avsz3".into()),
+        0x2E => return Some("avsz4".into()),
+        0x30 => "rtpt",
+        0x3D => "gpf",
+        0x3E => "gpl",
+        0x3F => "ncct",
+        _ => return Some(format!("{:<12}{:#x}", "cop2", word & 0x01FF_FFFF)),
+    };
+    Some(format!("{mnemonic:<12}sf={sf},lm={lm}"))
+}
+
+/// Replaces the GTE register number in the `asm` of a register move, load, or store `word`
+/// with its name, e.g. `mfc2 $t0, $12` becomes `mfc2 $t0, $sxy0`. Other instructions are
+/// returned unchanged.
+pub fn name_registers(word: u32, asm: String) -> String {
+    let rt = (word >> 16) & 31;
+    let rd = (word >> 11) & 31;
+    match (word >> 26, (word >> 21) & 31) {
+        // mfc2, mtc2, cfc2, ctc2
+        (COP2, 0 | 4) => replace_last(asm, data_register(rd)),
+        (COP2, 2 | 6) => replace_last(asm, control_register(rd)),
+        (LWC2 | SWC2, _) => match asm.find(',') {
+            Some(comma) if asm.len() > 12 => {
+                format!("{}${}{}", &asm[..12], data_register(rt), &asm[comma..])
+            }
+            _ => asm,
+        },
+        _ => asm,
+    }
+}
+
+fn replace_last(asm: String, name: &str) -> String {
+    match asm.rsplit_once(", ") {
+        Some((head, _)) => format!("{head}, ${name}"),
+        None => asm,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        assert_eq!(Some("nclip".into()), command(0x4B400006));
+        assert_eq!(Some("rtpt        sf=1,lm=0".into()), command(0x4A280030));
+        assert_eq!(Some("ncds        sf=1,lm=1".into()), command(0x4AE80413));
+        assert_eq!(Some("sqr         sf=0,lm=1".into()), command(0x4AA00428));
+        assert_eq!(
+            Some("mvmva       sf=1,mx=rt,v=v0,cv=none,lm=0".into()),
+            command(0x4A486012)
+        );
+        assert_eq!(
+            Some("mvmva       sf=1,mx=llm,v=ir,cv=bk,lm=1".into()),
+            command(0x4A000012 | 1 << 19 | 1 << 17 | 3 << 15 | 1 << 13 | 1 << 10)
+        );
+        assert_eq!(Some("cop2        0x3b".into()), command(0x4A00003B));
+        // register moves are not commands
+        assert_eq!(None, command(0x48080000));
+        assert_eq!(None, command(0x00000000));
+    }
+
+    #[test]
+    fn test_name_registers() {
+        assert_eq!(
+            "mfc2        $t0, $sxy2",
+            name_registers(0x48087000, "mfc2        $t0, $14".into())
+        );
+        assert_eq!(
+            "ctc2        $t0, $flag",
+            name_registers(0x48C8F800, "ctc2        $t0, $31".into())
+        );
+        assert_eq!(
+            "lwc2        $vxy0, 0x0($a0)",
+            name_registers(0xC8800000, "lwc2        $0, 0x0($a0)".into())
+        );
+        assert_eq!(
+            "swc2        $rgbc, 0x4($a1)",
+            name_registers(0xE8A60004, "swc2        $6, 0x4($a1)".into())
+        );
+        assert_eq!("nop", name_registers(0, "nop".into()));
+    }
+}
@@ -0,0 +1,1341 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Motorola 680x0 instructions.
+//!
+//! 680x0 instructions are one to eleven big-endian 16-bit words: an operation word followed
+//! by the extension words of its source and destination effective addresses. The 68010 adds
+//! a few privileged instructions, the 68020 adds long branches and multiplies, bit fields,
+//! and scaled and memory indirect addressing, and the 68030 and 68040 add memory management
+//! and cache instructions. Floating point and other coprocessor instructions are shown as
+//! data.
+//!
+//! Instructions are shown in Motorola syntax with hexadecimal values prefixed by `$`.
+
+use crate::cputype;
+
+/// A processor of the 680x0 family.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Model {
+    M68000,
+    M68010,
+    M68020,
+    M68030,
+    M68040,
+}
+
+impl Model {
+    /// Returns the model for a [cputype], or `None` if it is not a 680x0.
+    pub fn new(cpu: u8) -> Option<Self> {
+        Some(match cpu {
+            cputype::MOTOROLA_68000 => Self::M68000,
+            cputype::MOTOROLA_68010 => Self::M68010,
+            cputype::MOTOROLA_68020 => Self::M68020,
+            cputype::MOTOROLA_68030 => Self::M68030,
+            cputype::MOTOROLA_68040 => Self::M68040,
+            _ => return None,
+        })
+    }
+}
+
+/// A decoded 680x0 instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    text: String,
+    size: usize,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `offset` in `code`, which is located at `address`.
+    ///
+    /// Words that are not instructions for `model`, or instructions that extend past the end
+    /// of `code`, are decoded as a single `dc.w`.
+    pub fn decode(code: &[u8], offset: usize, address: u32, model: Model) -> Self {
+        Self::decode_with(code, offset, address, model, &mut |_| None)
+    }
+
+    /// Decodes the instruction at `offset` in `code` like [decode](Self::decode). `symbol`
+    /// is called with the offset in `code` of each address, displacement, or immediate value
+    /// and returns the name to show in place of the value, if any.
+    pub fn decode_with(
+        code: &[u8],
+        offset: usize,
+        address: u32,
+        model: Model,
+        symbol: &mut dyn FnMut(usize) -> Option<String>,
+    ) -> Self {
+        let mut decoder = Decoder {
+            code,
+            start: offset,
+            position: offset,
+            address,
+            model,
+            symbol,
+        };
+        match decoder.instruction() {
+            Some(text) => Self {
+                text,
+                size: decoder.position - offset,
+            },
+            None => {
+                let word = code
+                    .get(offset..offset + 2)
+                    .map_or(0, |w| u16::from_be_bytes([w[0], w[1]]));
+                Self {
+                    text: format!("{:<12}${word:04x}", "dc.w"),
+                    size: 2,
+                }
+            }
+        }
+    }
+
+    /// Returns the size of the instruction in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the assembly for the instruction.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// The size of an operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Size {
+    Byte,
+    Word,
+    Long,
+}
+
+impl Size {
+    /// The size encoded in bits 7-6 of most operation words.
+    fn new(bits: u16) -> Option<Self> {
+        match bits & 3 {
+            0 => Some(Self::Byte),
+            1 => Some(Self::Word),
+            2 => Some(Self::Long),
+            _ => None,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::Byte => ".b",
+            Self::Word => ".w",
+            Self::Long => ".l",
+        }
+    }
+}
+
+const CONDITIONS: [&str; 16] = [
+    "t", "f", "hi", "ls", "cc", "cs", "ne", "eq", "vc", "vs", "pl", "mi", "ge", "lt", "gt", "le",
+];
+
+fn op(mnemonic: &str, operands: &str) -> String {
+    if operands.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{mnemonic:<12}{operands}")
+    }
+}
+
+fn data_register(register: u16) -> String {
+    format!("d{}", register & 7)
+}
+
+fn address_register(register: u16) -> String {
+    match register & 7 {
+        7 => "sp".to_string(),
+        register => format!("a{register}"),
+    }
+}
+
+/// Formats a `movem` register mask. Predecrement masks are stored with `a7` in bit 0.
+fn register_list(mask: u16, predecrement: bool) -> String {
+    let mask = if predecrement {
+        mask.reverse_bits()
+    } else {
+        mask
+    };
+    let name = |bit: u16| {
+        if bit < 8 {
+            data_register(bit)
+        } else {
+            format!("a{}", bit - 8)
+        }
+    };
+    let mut ranges: Vec<String> = Vec::new();
+    let mut bit = 0;
+    while bit < 16 {
+        if mask & (1 << bit) == 0 {
+            bit += 1;
+            continue;
+        }
+        // ranges do not cross from data to address registers
+        let mut end = bit;
+        while end + 1 < 16 && (end + 1) % 8 != 0 && mask & (1 << (end + 1)) != 0 {
+            end += 1;
+        }
+        ranges.push(if end == bit {
+            name(bit)
+        } else {
+            format!("{}-{}", name(bit), name(end))
+        });
+        bit = end + 1;
+    }
+    ranges.join("/")
+}
+
+fn control_register(register: u16) -> String {
+    match register {
+        0x000 => "sfc",
+        0x001 => "dfc",
+        0x002 => "cacr",
+        0x003 => "tc",
+        0x004 => "itt0",
+        0x005 => "itt1",
+        0x006 => "dtt0",
+        0x007 => "dtt1",
+        0x800 => "usp",
+        0x801 => "vbr",
+        0x802 => "caar",
+        0x803 => "msp",
+        0x804 => "isp",
+        0x805 => "mmusr",
+        0x806 => "urp",
+        0x807 => "srp",
+        _ => return format!("${register:03x}"),
+    }
+    .to_string()
+}
+
+fn signed(value: i32) -> String {
+    if value < 0 {
+        format!("-${:x}", value.unsigned_abs())
+    } else {
+        format!("${value:x}")
+    }
+}
+
+struct Decoder<'a> {
+    code: &'a [u8],
+    start: usize,
+    position: usize,
+    address: u32,
+    model: Model,
+    symbol: &'a mut dyn FnMut(usize) -> Option<String>,
+}
+
+impl Decoder<'_> {
+    /// The address of the next word.
+    fn pc(&self) -> u32 {
+        self.address
+            .wrapping_add((self.position - self.start) as u32)
+    }
+
+    fn word(&mut self) -> Option<u16> {
+        let bytes = self.code.get(self.position..self.position + 2)?;
+        self.position += 2;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn long(&mut self) -> Option<u32> {
+        let high = self.word()?;
+        let low = self.word()?;
+        Some((u32::from(high) << 16) | u32::from(low))
+    }
+
+    /// Reads a word or long extension and the name of the symbol relocating it.
+    fn extension(&mut self, size: Size) -> Option<(u32, Option<String>)> {
+        let offset = self.position;
+        let value = match size {
+            Size::Byte | Size::Word => u32::from(self.word()?),
+            Size::Long => self.long()?,
+        };
+        // byte immediates are stored in the low byte of a word
+        let symbol = (self.symbol)(offset).or_else(|| match size {
+            Size::Byte => (self.symbol)(offset + 1),
+            _ => None,
+        });
+        Some((value, symbol))
+    }
+
+    fn immediate(&mut self, size: Size) -> Option<String> {
+        let (value, symbol) = self.extension(size)?;
+        Some(match (symbol, size) {
+            (Some(symbol), _) => format!("#{symbol}"),
+            (None, Size::Byte) => format!("#${:x}", value & 0xFF),
+            (None, _) => format!("#${value:x}"),
+        })
+    }
+
+    /// A signed 16-bit displacement, or the symbol relocating it.
+    fn displacement(&mut self) -> Option<(i32, Option<String>)> {
+        let (value, symbol) = self.extension(Size::Word)?;
+        Some((i32::from(value as u16 as i16), symbol))
+    }
+
+    /// Decodes the effective address with `mode` and `register`.
+    fn ea(&mut self, mode: u16, register: u16, size: Size) -> Option<String> {
+        let an = address_register(register);
+        Some(match mode & 7 {
+            0 => data_register(register),
+            1 => an,
+            2 => format!("({an})"),
+            3 => format!("({an})+"),
+            4 => format!("-({an})"),
+            5 => {
+                let (displacement, symbol) = self.displacement()?;
+                format!(
+                    "{}({an})",
+                    symbol.unwrap_or_else(|| displacement.to_string())
+                )
+            }
+            6 => self.indexed(&an, None)?,
+            _ => match register & 7 {
+                0 => {
+                    let (value, symbol) = self.extension(Size::Word)?;
+                    symbol.unwrap_or_else(|| format!("${value:04x}.w"))
+                }
+                1 => {
+                    let (value, symbol) = self.extension(Size::Long)?;
+                    symbol.unwrap_or_else(|| format!("${value:x}"))
+                }
+                2 => {
+                    let pc = self.pc();
+                    let (displacement, symbol) = self.displacement()?;
+                    let target = pc.wrapping_add_signed(displacement);
+                    format!("{}(pc)", symbol.unwrap_or_else(|| format!("${target:x}")))
+                }
+                3 => {
+                    let pc = self.pc();
+                    self.indexed("pc", Some(pc))?
+                }
+                4 => self.immediate(size)?,
+                _ => return None,
+            },
+        })
+    }
+
+    /// Decodes an indexed address. `pc` is the address of the extension word of a PC
+    /// relative address.
+    fn indexed(&mut self, base: &str, pc: Option<u32>) -> Option<String> {
+        let extension = self.word()?;
+        let index_register = if extension & 0x8000 != 0 {
+            address_register(extension >> 12)
+        } else {
+            data_register(extension >> 12)
+        };
+        let index_size = if extension & 0x0800 != 0 { ".l" } else { ".w" };
+        let scale = 1 << ((extension >> 9) & 3);
+        let scaled = self.model >= Model::M68020 && scale > 1;
+        let index = if scaled {
+            format!("{index_register}{index_size}*{scale}")
+        } else {
+            format!("{index_register}{index_size}")
+        };
+
+        if self.model < Model::M68020 || extension & 0x0100 == 0 {
+            let displacement = i32::from(extension as u8 as i8);
+            let displacement = match pc {
+                Some(pc) => format!("${:x}", pc.wrapping_add_signed(displacement)),
+                None => displacement.to_string(),
+            };
+            return Some(format!("{displacement}({base},{index})"));
+        }
+
+        // the 68020 full extension word
+        let base = if extension & 0x80 != 0 {
+            String::new()
+        } else {
+            base.to_string()
+        };
+        let index = if extension & 0x40 != 0 {
+            String::new()
+        } else {
+            index
+        };
+        let base_displacement = match (extension >> 4) & 3 {
+            1 => String::new(),
+            2 => {
+                let (displacement, symbol) = self.displacement()?;
+                symbol.unwrap_or_else(|| signed(displacement))
+            }
+            3 => {
+                let (value, symbol) = self.extension(Size::Long)?;
+                symbol.unwrap_or_else(|| format!("${value:x}"))
+            }
+            _ => return None,
+        };
+        let outer = match extension & 3 {
+            2 => {
+                let (displacement, symbol) = self.displacement()?;
+                symbol.unwrap_or_else(|| signed(displacement))
+            }
+            3 => {
+                let (value, symbol) = self.extension(Size::Long)?;
+                symbol.unwrap_or_else(|| format!("${value:x}"))
+            }
+            _ => String::new(),
+        };
+        let join = |parts: &[&str]| {
+            parts
+                .iter()
+                .filter(|p| !p.is_empty())
+                .copied()
+                .collect::<Vec<&str>>()
+                .join(",")
+        };
+        Some(match extension & 7 {
+            0 => format!("({})", join(&[&base_displacement, &base, &index])),
+            1..=3 => format!(
+                "([{}],{})",
+                join(&[&base_displacement, &base, &index]),
+                outer
+            )
+            .replace(",)", ")"),
+            5..=7 if extension & 0x40 == 0 => format!(
+                "([{}],{})",
+                join(&[&base_displacement, &base]),
+                join(&[&index, &outer])
+            )
+            .replace(",)", ")"),
+            _ => return None,
+        })
+    }
+
+    /// A branch target with a displacement relative to `pc`, or the symbol relocating it.
+    fn branch(&self, pc: u32, displacement: i32, symbol: Option<String>) -> String {
+        symbol.unwrap_or_else(|| format!("${:x}", pc.wrapping_add_signed(displacement)))
+    }
+
+    fn at_least(&self, model: Model) -> Option<()> {
+        (self.model >= model).then_some(())
+    }
+
+    fn instruction(&mut self) -> Option<String> {
+        let word = self.word()?;
+        let mode = (word >> 3) & 7;
+        let register = word & 7;
+        let upper = (word >> 9) & 7;
+        match word >> 12 {
+            0x0 => self.line0(word, mode, register, upper),
+            0x1..=0x3 => {
+                let size = match word >> 12 {
+                    0x1 => Size::Byte,
+                    0x2 => Size::Long,
+                    _ => Size::Word,
+                };
+                let source = self.ea(mode, register, size)?;
+                let destination_mode = (word >> 6) & 7;
+                if destination_mode == 1 {
+                    if size == Size::Byte {
+                        return None;
+                    }
+                    let mnemonic = format!("movea{}", size.suffix());
+                    return Some(op(
+                        &mnemonic,
+                        &format!("{source},{}", address_register(upper)),
+                    ));
+                }
+                let destination = self.ea(destination_mode, upper, size)?;
+                Some(op(
+                    &format!("move{}", size.suffix()),
+                    &format!("{source},{destination}"),
+                ))
+            }
+            0x4 => self.line4(word, mode, register, upper),
+            0x5 => self.line5(word, mode, register),
+            0x6 => {
+                let pc = self.pc();
+                let mnemonic = match (word >> 8) & 0xF {
+                    0 => "bra".to_string(),
+                    1 => "bsr".to_string(),
+                    condition => format!("b{}", CONDITIONS[condition as usize]),
+                };
+                let (suffix, target) = match word & 0xFF {
+                    0x00 => {
+                        let (displacement, symbol) = self.displacement()?;
+                        (".w", self.branch(pc, displacement, symbol))
+                    }
+                    0xFF => {
+                        self.at_least(Model::M68020)?;
+                        let (displacement, symbol) = self.extension(Size::Long)?;
+                        (".l", self.branch(pc, displacement as i32, symbol))
+                    }
+                    displacement => (".s", self.branch(pc, i32::from(displacement as i8), None)),
+                };
+                Some(op(&format!("{mnemonic}{suffix}"), &target))
+            }
+            0x7 => {
+                if word & 0x100 != 0 {
+                    return None;
+                }
+                Some(op(
+                    "moveq",
+                    &format!(
+                        "#{},{}",
+                        signed(i32::from(word as u8 as i8)),
+                        data_register(upper)
+                    ),
+                ))
+            }
+            0x8 => self.line8(word, mode, register, upper),
+            0x9 | 0xD => self.arithmetic(word, mode, register, upper),
+            0xB => {
+                let size = Size::new(word >> 6);
+                let dn = data_register(upper);
+                match (word >> 8) & 1 {
+                    _ if size.is_none() => {
+                        let size = if word & 0x100 != 0 {
+                            Size::Long
+                        } else {
+                            Size::Word
+                        };
+                        let source = self.ea(mode, register, size)?;
+                        Some(op(
+                            &format!("cmpa{}", size.suffix()),
+                            &format!("{source},{}", address_register(upper)),
+                        ))
+                    }
+                    0 => {
+                        let size = size?;
+                        let source = self.ea(mode, register, size)?;
+                        Some(op(
+                            &format!("cmp{}", size.suffix()),
+                            &format!("{source},{dn}"),
+                        ))
+                    }
+                    _ if mode == 1 => {
+                        let size = size?;
+                        Some(op(
+                            &format!("cmpm{}", size.suffix()),
+                            &format!(
+                                "({})+,({})+",
+                                address_register(register),
+                                address_register(upper)
+                            ),
+                        ))
+                    }
+                    _ => {
+                        let size = size?;
+                        let destination = self.ea(mode, register, size)?;
+                        Some(op(
+                            &format!("eor{}", size.suffix()),
+                            &format!("{dn},{destination}"),
+                        ))
+                    }
+                }
+            }
+            0xC => self.line_c(word, mode, register, upper),
+            0xE => self.line_e(word, mode, register, upper),
+            0xF => self.line_f(word, mode, register),
+            _ => None,
+        }
+    }
+
+    /// Bit manipulation, `movep`, and immediate instructions.
+    fn line0(&mut self, word: u16, mode: u16, register: u16, upper: u16) -> Option<String> {
+        const BIT: [&str; 4] = ["btst", "bchg", "bclr", "bset"];
+        let operation = (word >> 6) & 3;
+        if word & 0x100 != 0 {
+            if mode == 1 {
+                let size = if word & 0x40 != 0 { ".l" } else { ".w" };
+                let (displacement, symbol) = self.displacement()?;
+                let memory = format!(
+                    "{}({})",
+                    symbol.unwrap_or_else(|| displacement.to_string()),
+                    address_register(register)
+                );
+                let dn = data_register(upper);
+                let operands = if word & 0x80 != 0 {
+                    format!("{dn},{memory}")
+                } else {
+                    format!("{memory},{dn}")
+                };
+                return Some(op(&format!("movep{size}"), &operands));
+            }
+            let destination = self.ea(mode, register, Size::Byte)?;
+            return Some(op(
+                BIT[operation as usize],
+                &format!("{},{destination}", data_register(upper)),
+            ));
+        }
+
+        if upper == 4 {
+            // static bit operations
+            let number = self.immediate(Size::Byte)?;
+            let destination = self.ea(mode, register, Size::Byte)?;
+            return Some(op(
+                BIT[operation as usize],
+                &format!("{number},{destination}"),
+            ));
+        }
+
+        if operation == 3 {
+            return match upper {
+                0..=2 if word & 0x800 == 0 => {
+                    // cmp2 and chk2
+                    self.at_least(Model::M68020)?;
+                    let size = Size::new(upper)?;
+                    let extension = self.word()?;
+                    let source = self.ea(mode, register, size)?;
+                    let rn = if extension & 0x8000 != 0 {
+                        address_register(extension >> 12)
+                    } else {
+                        data_register(extension >> 12)
+                    };
+                    let mnemonic = if extension & 0x800 != 0 {
+                        "chk2"
+                    } else {
+                        "cmp2"
+                    };
+                    Some(op(
+                        &format!("{mnemonic}{}", size.suffix()),
+                        &format!("{source},{rn}"),
+                    ))
+                }
+                3 if self.model == Model::M68020 => {
+                    // callm and rtm
+                    if mode <= 1 {
+                        let rn = if mode == 1 {
+                            address_register(register)
+                        } else {
+                            data_register(register)
+                        };
+                        return Some(op("rtm", &rn));
+                    }
+                    let count = self.immediate(Size::Byte)?;
+                    let destination = self.ea(mode, register, Size::Long)?;
+                    Some(op("callm", &format!("{count},{destination}")))
+                }
+                5..=7 => {
+                    // cas and cas2
+                    self.at_least(Model::M68020)?;
+                    let size = Size::new(upper - 5)?;
+                    if word & 0x3F == 0x3C {
+                        let first = self.word()?;
+                        let second = self.word()?;
+                        let register = |extension: u16| {
+                            if extension & 0x8000 != 0 {
+                                address_register(extension >> 12)
+                            } else {
+                                data_register(extension >> 12)
+                            }
+                        };
+                        return Some(op(
+                            &format!("cas2{}", size.suffix()),
+                            &format!(
+                                "{}:{},{}:{},({}):({})",
+                                data_register(first),
+                                data_register(second),
+                                data_register(first >> 6),
+                                data_register(second >> 6),
+                                register(first),
+                                register(second)
+                            ),
+                        ));
+                    }
+                    let extension = self.word()?;
+                    let destination = self.ea(mode, register, size)?;
+                    Some(op(
+                        &format!("cas{}", size.suffix()),
+                        &format!(
+                            "{},{},{destination}",
+                            data_register(extension),
+                            data_register(extension >> 6)
+                        ),
+                    ))
+                }
+                _ => None,
+            };
+        }
+
+        let size = Size::new(operation)?;
+        if upper == 7 {
+            // moves
+            self.at_least(Model::M68010)?;
+            let extension = self.word()?;
+            let rn = if extension & 0x8000 != 0 {
+                address_register(extension >> 12)
+            } else {
+                data_register(extension >> 12)
+            };
+            let address = self.ea(mode, register, size)?;
+            let operands = if extension & 0x800 != 0 {
+                format!("{rn},{address}")
+            } else {
+                format!("{address},{rn}")
+            };
+            return Some(op(&format!("moves{}", size.suffix()), &operands));
+        }
+
+        let mnemonic = match upper {
+            0 => "ori",
+            1 => "andi",
+            2 => "subi",
+            3 => "addi",
+            5 => "eori",
+            6 => "cmpi",
+            _ => return None,
+        };
+        // to the condition codes or status register
+        if mode == 7 && register == 4 && matches!(upper, 0 | 1 | 5) {
+            return match size {
+                Size::Byte => {
+                    let value = self.immediate(Size::Byte)?;
+                    Some(op(mnemonic, &format!("{value},ccr")))
+                }
+                Size::Word => {
+                    let value = self.immediate(Size::Word)?;
+                    Some(op(mnemonic, &format!("{value},sr")))
+                }
+                Size::Long => None,
+            };
+        }
+        let value = self.immediate(size)?;
+        let destination = self.ea(mode, register, size)?;
+        Some(op(
+            &format!("{mnemonic}{}", size.suffix()),
+            &format!("{value},{destination}"),
+        ))
+    }
+
+    /// Miscellaneous instructions.
+    fn line4(&mut self, word: u16, mode: u16, register: u16, upper: u16) -> Option<String> {
+        match word {
+            0x4AFC => return Some("illegal".into()),
+            0x4E70 => return Some("reset".into()),
+            0x4E71 => return Some("nop".into()),
+            0x4E72 => {
+                let value = self.immediate(Size::Word)?;
+                return Some(op("stop", &value));
+            }
+            0x4E73 => return Some("rte".into()),
+            0x4E74 => {
+                self.at_least(Model::M68010)?;
+                let value = self.immediate(Size::Word)?;
+                return Some(op("rtd", &value));
+            }
+            0x4E75 => return Some("rts".into()),
+            0x4E76 => return Some("trapv".into()),
+            0x4E77 => return Some("rtr".into()),
+            0x4E7A | 0x4E7B => {
+                self.at_least(Model::M68010)?;
+                let extension = self.word()?;
+                let rn = if extension & 0x8000 != 0 {
+                    address_register(extension >> 12)
+                } else {
+                    data_register(extension >> 12)
+                };
+                let control = control_register(extension & 0xFFF);
+                let operands = if word & 1 != 0 {
+                    format!("{rn},{control}")
+                } else {
+                    format!("{control},{rn}")
+                };
+                return Some(op("movec", &operands));
+            }
+            _ => (),
+        }
+
+        let an = address_register(register);
+        match word & 0xFFF8 {
+            0x4840 => return Some(op("swap", &data_register(register))),
+            0x4848 => {
+                self.at_least(Model::M68010)?;
+                return Some(op("bkpt", &format!("#{register}")));
+            }
+            0x4880 => return Some(op("ext.w", &data_register(register))),
+            0x48C0 => return Some(op("ext.l", &data_register(register))),
+            0x49C0 => {
+                self.at_least(Model::M68020)?;
+                return Some(op("extb.l", &data_register(register)));
+            }
+            0x4808 => {
+                self.at_least(Model::M68020)?;
+                let (displacement, symbol) = self.extension(Size::Long)?;
+                let displacement =
+                    symbol.unwrap_or_else(|| format!("#{}", signed(displacement as i32)));
+                return Some(op("link.l", &format!("{an},{displacement}")));
+            }
+            0x4E50 => {
+                let (displacement, symbol) = self.displacement()?;
+                let displacement = symbol.unwrap_or_else(|| format!("#{}", signed(displacement)));
+                return Some(op("link.w", &format!("{an},{displacement}")));
+            }
+            0x4E58 => return Some(op("unlk", &an)),
+            0x4E60 => return Some(op("move.l", &format!("{an},usp"))),
+            0x4E68 => return Some(op("move.l", &format!("usp,{an}"))),
+            _ => (),
+        }
+        if word & 0xFFF0 == 0x4E40 {
+            return Some(op("trap", &format!("#{}", word & 0xF)));
+        }
+
+        if word & 0x100 != 0 {
+            return match (word >> 6) & 7 {
+                7 => {
+                    let source = self.ea(mode, register, Size::Long)?;
+                    Some(op("lea", &format!("{source},{}", address_register(upper))))
+                }
+                6 | 4 => {
+                    let size = if word & 0x80 != 0 {
+                        Size::Word
+                    } else {
+                        self.at_least(Model::M68020)?;
+                        Size::Long
+                    };
+                    let source = self.ea(mode, register, size)?;
+                    Some(op(
+                        &format!("chk{}", size.suffix()),
+                        &format!("{source},{}", data_register(upper)),
+                    ))
+                }
+                _ => None,
+            };
+        }
+
+        let size = Size::new(word >> 6);
+        match upper {
+            0..=3 if size.is_some() => {
+                let size = size?;
+                let mnemonic = ["negx", "clr", "neg", "not"][upper as usize];
+                let destination = self.ea(mode, register, size)?;
+                Some(op(&format!("{mnemonic}{}", size.suffix()), &destination))
+            }
+            0 => {
+                let destination = self.ea(mode, register, Size::Word)?;
+                Some(op("move.w", &format!("sr,{destination}")))
+            }
+            1 => {
+                self.at_least(Model::M68010)?;
+                let destination = self.ea(mode, register, Size::Word)?;
+                Some(op("move.w", &format!("ccr,{destination}")))
+            }
+            2 => {
+                let source = self.ea(mode, register, Size::Word)?;
+                Some(op("move.w", &format!("{source},ccr")))
+            }
+            3 => {
+                let source = self.ea(mode, register, Size::Word)?;
+                Some(op("move.w", &format!("{source},sr")))
+            }
+            4 => match (word >> 6) & 3 {
+                0 => {
+                    let destination = self.ea(mode, register, Size::Byte)?;
+                    Some(op("nbcd", &destination))
+                }
+                1 => {
+                    let source = self.ea(mode, register, Size::Long)?;
+                    Some(op("pea", &source))
+                }
+                _ => self.movem(word, mode, register),
+            },
+            5 => match size {
+                Some(size) => {
+                    let source = self.ea(mode, register, size)?;
+                    Some(op(&format!("tst{}", size.suffix()), &source))
+                }
+                None => {
+                    let destination = self.ea(mode, register, Size::Byte)?;
+                    Some(op("tas", &destination))
+                }
+            },
+            6 => match (word >> 6) & 3 {
+                0 | 1 => {
+                    self.at_least(Model::M68020)?;
+                    let extension = self.word()?;
+                    let source = self.ea(mode, register, Size::Long)?;
+                    let signed = extension & 0x800 != 0;
+                    let dl = data_register(extension >> 12);
+                    let dh = data_register(extension);
+                    let long = extension & 0x400 != 0;
+                    let (mnemonic, operands) = if word & 0x40 == 0 {
+                        let mnemonic = if signed { "muls.l" } else { "mulu.l" };
+                        let registers = if long { format!("{dh}:{dl}") } else { dl };
+                        (mnemonic, format!("{source},{registers}"))
+                    } else {
+                        let mnemonic =
+                            match (signed, long || (extension & 7) == ((extension >> 12) & 7)) {
+                                (true, true) => "divs.l",
+                                (false, true) => "divu.l",
+                                (true, false) => "divsl.l",
+                                (false, false) => "divul.l",
+                            };
+                        let registers = if (extension & 7) == ((extension >> 12) & 7) {
+                            dl
+                        } else {
+                            format!("{dh}:{dl}")
+                        };
+                        (mnemonic, format!("{source},{registers}"))
+                    };
+                    Some(op(mnemonic, &operands))
+                }
+                _ => self.movem(word, mode, register),
+            },
+            7 => match (word >> 6) & 3 {
+                2 => {
+                    let target = self.ea(mode, register, Size::Long)?;
+                    Some(op("jsr", &target))
+                }
+                3 => {
+                    let target = self.ea(mode, register, Size::Long)?;
+                    Some(op("jmp", &target))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn movem(&mut self, word: u16, mode: u16, register: u16) -> Option<String> {
+        let size = if word & 0x40 != 0 {
+            Size::Long
+        } else {
+            Size::Word
+        };
+        let mask = self.word()?;
+        let address = self.ea(mode, register, size)?;
+        let registers = register_list(mask, mode == 4);
+        let operands = if word & 0x400 != 0 {
+            format!("{address},{registers}")
+        } else {
+            format!("{registers},{address}")
+        };
+        Some(op(&format!("movem{}", size.suffix()), &operands))
+    }
+
+    /// `addq`, `subq`, `Scc`, `DBcc`, and `TRAPcc`.
+    fn line5(&mut self, word: u16, mode: u16, register: u16) -> Option<String> {
+        let condition = CONDITIONS[((word >> 8) & 0xF) as usize];
+        match Size::new(word >> 6) {
+            Some(size) => {
+                let mnemonic = if word & 0x100 != 0 { "subq" } else { "addq" };
+                let value = match (word >> 9) & 7 {
+                    0 => 8,
+                    value => value,
+                };
+                let destination = self.ea(mode, register, size)?;
+                Some(op(
+                    &format!("{mnemonic}{}", size.suffix()),
+                    &format!("#{value},{destination}"),
+                ))
+            }
+            None if mode == 1 => {
+                let pc = self.pc();
+                let (displacement, symbol) = self.displacement()?;
+                let target = self.branch(pc, displacement, symbol);
+                Some(op(
+                    &format!("db{condition}"),
+                    &format!("{},{target}", data_register(register)),
+                ))
+            }
+            None if mode == 7 && (2..=4).contains(&register) => {
+                self.at_least(Model::M68020)?;
+                let mnemonic = format!("trap{condition}");
+                match register {
+                    2 => {
+                        let value = self.immediate(Size::Word)?;
+                        Some(op(&format!("{mnemonic}.w"), &value))
+                    }
+                    3 => {
+                        let value = self.immediate(Size::Long)?;
+                        Some(op(&format!("{mnemonic}.l"), &value))
+                    }
+                    _ => Some(mnemonic),
+                }
+            }
+            None => {
+                let destination = self.ea(mode, register, Size::Byte)?;
+                Some(op(&format!("s{condition}"), &destination))
+            }
+        }
+    }
+
+    /// `or`, `divu`, `divs`, `sbcd`, `pack`, and `unpk`.
+    fn line8(&mut self, word: u16, mode: u16, register: u16, upper: u16) -> Option<String> {
+        let dn = data_register(upper);
+        match ((word >> 6) & 7, mode) {
+            (3 | 7, _) => {
+                let mnemonic = if word & 0x100 != 0 {
+                    "divs.w"
+                } else {
+                    "divu.w"
+                };
+                let source = self.ea(mode, register, Size::Word)?;
+                Some(op(mnemonic, &format!("{source},{dn}")))
+            }
+            (4..=6, 0 | 1) => {
+                let (mnemonic, memory) = match (word >> 6) & 7 {
+                    4 => ("sbcd", mode == 1),
+                    5 => {
+                        self.at_least(Model::M68020)?;
+                        ("pack", mode == 1)
+                    }
+                    _ => {
+                        self.at_least(Model::M68020)?;
+                        ("unpk", mode == 1)
+                    }
+                };
+                let operands = if memory {
+                    format!(
+                        "-({}),-({})",
+                        address_register(register),
+                        address_register(upper)
+                    )
+                } else {
+                    format!("{},{dn}", data_register(register))
+                };
+                if mnemonic == "sbcd" {
+                    return Some(op(mnemonic, &operands));
+                }
+                let adjustment = self.immediate(Size::Word)?;
+                Some(op(mnemonic, &format!("{operands},{adjustment}")))
+            }
+            _ => self.logical("or", word, mode, register, upper),
+        }
+    }
+
+    /// `and`, `or`, `add` and `sub` between a data register and an effective address.
+    fn logical(
+        &mut self,
+        mnemonic: &str,
+        word: u16,
+        mode: u16,
+        register: u16,
+        upper: u16,
+    ) -> Option<String> {
+        let size = Size::new(word >> 6)?;
+        let dn = data_register(upper);
+        let address = self.ea(mode, register, size)?;
+        let operands = if word & 0x100 != 0 {
+            format!("{dn},{address}")
+        } else {
+            format!("{address},{dn}")
+        };
+        Some(op(&format!("{mnemonic}{}", size.suffix()), &operands))
+    }
+
+    /// `add`, `adda`, `addx`, `sub`, `suba`, and `subx`.
+    fn arithmetic(&mut self, word: u16, mode: u16, register: u16, upper: u16) -> Option<String> {
+        let mnemonic = if word >> 12 == 0xD { "add" } else { "sub" };
+        if (word >> 6) & 3 == 3 {
+            let size = if word & 0x100 != 0 {
+                Size::Long
+            } else {
+                Size::Word
+            };
+            let source = self.ea(mode, register, size)?;
+            return Some(op(
+                &format!("{mnemonic}a{}", size.suffix()),
+                &format!("{source},{}", address_register(upper)),
+            ));
+        }
+        if word & 0x130 == 0x100 {
+            let size = Size::new(word >> 6)?;
+            let operands = if mode & 1 != 0 {
+                format!(
+                    "-({}),-({})",
+                    address_register(register),
+                    address_register(upper)
+                )
+            } else {
+                format!("{},{}", data_register(register), data_register(upper))
+            };
+            return Some(op(&format!("{mnemonic}x{}", size.suffix()), &operands));
+        }
+        self.logical(mnemonic, word, mode, register, upper)
+    }
+
+    /// `and`, `mulu`, `muls`, `abcd`, and `exg`.
+    fn line_c(&mut self, word: u16, mode: u16, register: u16, upper: u16) -> Option<String> {
+        let dn = data_register(upper);
+        match ((word >> 6) & 7, mode) {
+            (3 | 7, _) => {
+                let mnemonic = if word & 0x100 != 0 {
+                    "muls.w"
+                } else {
+                    "mulu.w"
+                };
+                let source = self.ea(mode, register, Size::Word)?;
+                Some(op(mnemonic, &format!("{source},{dn}")))
+            }
+            (4, 0) => Some(op("abcd", &format!("{},{dn}", data_register(register)))),
+            (4, 1) => Some(op(
+                "abcd",
+                &format!(
+                    "-({}),-({})",
+                    address_register(register),
+                    address_register(upper)
+                ),
+            )),
+            (5, 0) => Some(op("exg", &format!("{dn},{}", data_register(register)))),
+            (5, 1) => Some(op(
+                "exg",
+                &format!("{},{}", address_register(upper), address_register(register)),
+            )),
+            (6, 1) => Some(op("exg", &format!("{dn},{}", address_register(register)))),
+            _ => self.logical("and", word, mode, register, upper),
+        }
+    }
+
+    /// Shifts, rotates, and bit fields.
+    fn line_e(&mut self, word: u16, mode: u16, register: u16, upper: u16) -> Option<String> {
+        const SHIFTS: [&str; 4] = ["as", "ls", "rox", "ro"];
+        let direction = if word & 0x100 != 0 { "l" } else { "r" };
+        match Size::new(word >> 6) {
+            Some(size) => {
+                let mnemonic = SHIFTS[((word >> 3) & 3) as usize];
+                let count = if word & 0x20 != 0 {
+                    data_register(upper)
+                } else {
+                    format!("#{}", if upper == 0 { 8 } else { upper })
+                };
+                Some(op(
+                    &format!("{mnemonic}{direction}{}", size.suffix()),
+                    &format!("{count},{}", data_register(register)),
+                ))
+            }
+            None if word & 0x800 == 0 => {
+                let mnemonic = SHIFTS[((word >> 9) & 3) as usize];
+                let destination = self.ea(mode, register, Size::Word)?;
+                Some(op(&format!("{mnemonic}{direction}.w"), &destination))
+            }
+            None => {
+                self.at_least(Model::M68020)?;
+                const FIELDS: [&str; 8] = [
+                    "bftst", "bfextu", "bfchg", "bfexts", "bfclr", "bfffo", "bfset", "bfins",
+                ];
+                let operation = (word >> 8) & 7;
+                let extension = self.word()?;
+                let address = self.ea(mode, register, Size::Long)?;
+                let offset = if extension & 0x800 != 0 {
+                    data_register(extension >> 6)
+                } else {
+                    ((extension >> 6) & 0x1F).to_string()
+                };
+                let width = if extension & 0x20 != 0 {
+                    data_register(extension)
+                } else {
+                    match extension & 0x1F {
+                        0 => 32,
+                        width => width,
+                    }
+                    .to_string()
+                };
+                let field = format!("{address}{{{offset}:{width}}}");
+                let dn = data_register(extension >> 12);
+                let operands = match operation {
+                    1 | 3 | 5 => format!("{field},{dn}"),
+                    7 => format!("{dn},{field}"),
+                    _ => field,
+                };
+                Some(op(FIELDS[operation as usize], &operands))
+            }
+        }
+    }
+
+    /// 68030 and 68040 memory management and cache instructions, and `move16`.
+    fn line_f(&mut self, word: u16, mode: u16, register: u16) -> Option<String> {
+        let an = address_register(register);
+        match word & 0xFF00 {
+            // 68030 pmove and pflusha
+            0xF000 if self.model == Model::M68030 => {
+                let extension = self.word()?;
+                if extension == 0x2400 {
+                    return Some("pflusha".into());
+                }
+                let mmu = match extension & 0xFC00 {
+                    0x4000 => "tc",
+                    0x4800 => "srp",
+                    0x4C00 => "crp",
+                    0x6000 => "mmusr",
+                    0x0800 => "tt0",
+                    0x0C00 => "tt1",
+                    _ => return None,
+                };
+                let size = if mmu == "srp" || mmu == "crp" {
+                    Size::Long
+                } else {
+                    Size::Word
+                };
+                let address = self.ea(mode, register, size)?;
+                let operands = if extension & 0x200 != 0 {
+                    format!("{mmu},{address}")
+                } else {
+                    format!("{address},{mmu}")
+                };
+                Some(op("pmove", &operands))
+            }
+            // 68040 cache and translation cache instructions
+            0xF400 if self.model == Model::M68040 => {
+                const CACHES: [&str; 4] = ["nc", "dc", "ic", "bc"];
+                let cache = CACHES[((word >> 6) & 3) as usize];
+                let mnemonic = if word & 0x20 != 0 { "cpush" } else { "cinv" };
+                match (word >> 3) & 3 {
+                    1 => Some(op(&format!("{mnemonic}l"), &format!("{cache},({an})"))),
+                    2 => Some(op(&format!("{mnemonic}p"), &format!("{cache},({an})"))),
+                    3 => Some(op(&format!("{mnemonic}a"), cache)),
+                    _ => None,
+                }
+            }
+            0xF500 if self.model == Model::M68040 => match word & 0xF8 {
+                0x00 => Some(op("pflushn", &format!("({an})"))),
+                0x08 => Some(op("pflush", &format!("({an})"))),
+                0x10 => Some("pflushan".into()),
+                0x18 => Some("pflusha".into()),
+                _ => None,
+            },
+            0xF600 if self.model == Model::M68040 => {
+                if word & 0xF8 == 0x20 {
+                    let extension = self.word()?;
+                    return Some(op(
+                        "move16",
+                        &format!("({an})+,({})+", address_register(extension >> 12)),
+                    ));
+                }
+                let (address, symbol) = self.extension(Size::Long)?;
+                let absolute = symbol.unwrap_or_else(|| format!("${address:x}"));
+                let operands = match (word >> 3) & 3 {
+                    0 => format!("({an})+,{absolute}"),
+                    1 => format!("{absolute},({an})+"),
+                    2 => format!("({an}),{absolute}"),
+                    _ => format!("{absolute},({an})"),
+                };
+                Some(op("move16", &operands))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn asm(words: &[u16], model: Model) -> (String, usize) {
+        let code = words
+            .iter()
+            .flat_map(|w| w.to_be_bytes())
+            .collect::<Vec<u8>>();
+        let instruction = Instruction::decode(&code, 0, 0x100, model);
+        (instruction.text().to_string(), instruction.size())
+    }
+
+    fn m68000(words: &[u16]) -> String {
+        let (text, size) = asm(words, Model::M68000);
+        assert_eq!(words.len() * 2, size, "{text}");
+        text
+    }
+
+    #[test]
+    fn test_moves() {
+        assert_eq!("move.l      d0,-(sp)", m68000(&[0x2F00]));
+        assert_eq!("move.w      (a0)+,d1", m68000(&[0x3218]));
+        assert_eq!("move.b      #$12,4(a1)", m68000(&[0x137C, 0x0012, 0x0004]));
+        assert_eq!("movea.l     $ff0000,a0", m68000(&[0x2079, 0x00FF, 0x0000]));
+        assert_eq!("moveq       #-$1,d0", m68000(&[0x70FF]));
+        assert_eq!("move.w      $c000.w,d0", m68000(&[0x3038, 0xC000]));
+        assert_eq!("movem.l     d2-d7/a2-a6,-(sp)", m68000(&[0x48E7, 0x3F3E]));
+        assert_eq!("movem.l     (sp)+,d2-d7/a2-a6", m68000(&[0x4CDF, 0x7CFC]));
+        assert_eq!("lea         -8(a6,d0.w),a0", m68000(&[0x41F6, 0x00F8]));
+        assert_eq!("lea         $10a(pc),a0", m68000(&[0x41FA, 0x0008]));
+    }
+
+    #[test]
+    fn test_operations() {
+        assert_eq!("link.w      a6,#-$8", m68000(&[0x4E56, 0xFFF8]));
+        assert_eq!("unlk        a6", m68000(&[0x4E5E]));
+        assert_eq!("rts", m68000(&[0x4E75]));
+        assert_eq!("jsr         $12345678", m68000(&[0x4EB9, 0x1234, 0x5678]));
+        assert_eq!("addq.l      #8,sp", m68000(&[0x508F]));
+        assert_eq!("subi.w      #$10,d0", m68000(&[0x0440, 0x0010]));
+        assert_eq!("andi        #$f8ff,sr", m68000(&[0x027C, 0xF8FF]));
+        assert_eq!("cmpa.l      a0,a1", m68000(&[0xB3C8]));
+        assert_eq!("eor.w       d1,d2", m68000(&[0xB342]));
+        assert_eq!("lsl.l       #2,d0", m68000(&[0xE588]));
+        assert_eq!("asr.w       d1,d2", m68000(&[0xE262]));
+        assert_eq!("mulu.w      d1,d0", m68000(&[0xC0C1]));
+        assert_eq!("exg         d0,a1", m68000(&[0xC189]));
+        assert_eq!("btst        #$3,d0", m68000(&[0x0800, 0x0003]));
+        assert_eq!("tst.b       (a0)", m68000(&[0x4A10]));
+        assert_eq!("seq         d0", m68000(&[0x57C0]));
+        assert_eq!("trap        #15", m68000(&[0x4E4F]));
+        assert_eq!("addx.l      d1,d0", m68000(&[0xD181]));
+        assert_eq!("add.w       d1,(a0)", m68000(&[0xD350]));
+    }
+
+    #[test]
+    fn test_branches() {
+        assert_eq!("bra.s       $100", m68000(&[0x60FE]));
+        assert_eq!("bne.w       $200", m68000(&[0x6600, 0x00FE]));
+        assert_eq!("dbf         d0,$100", m68000(&[0x51C8, 0xFFFE]));
+        assert_eq!("bsr.s       $112", m68000(&[0x6110]));
+    }
+
+    #[test]
+    fn test_models() {
+        // 68010
+        assert_eq!(
+            ("dc.w        $4e7a".into(), 2),
+            asm(&[0x4E7A, 0x0801], Model::M68000)
+        );
+        assert_eq!(
+            ("movec       vbr,d0".into(), 4),
+            asm(&[0x4E7A, 0x0801], Model::M68010)
+        );
+        assert_eq!(
+            ("rtd         #$4".into(), 4),
+            asm(&[0x4E74, 0x0004], Model::M68010)
+        );
+        // 68020
+        assert_eq!(
+            ("dc.w        $49c0".into(), 2),
+            asm(&[0x49C0], Model::M68010)
+        );
+        assert_eq!(("extb.l      d0".into(), 2), asm(&[0x49C0], Model::M68020));
+        assert_eq!(
+            ("bra.l       $10102".into(), 6),
+            asm(&[0x60FF, 0x0001, 0x0000], Model::M68020)
+        );
+        assert_eq!(
+            ("muls.l      d1,d2:d0".into(), 4),
+            asm(&[0x4C01, 0x0C02], Model::M68020)
+        );
+        assert_eq!(
+            ("move.l      (a0,d1.l*4),d0".into(), 4),
+            asm(&[0x2030, 0x1D00 | 0x0100 | 0x0010], Model::M68020)
+        );
+        assert_eq!(
+            ("bfextu      (a0){4:8},d1".into(), 4),
+            asm(&[0xE9D0, 0x1108], Model::M68020)
+        );
+        // 68040
+        assert_eq!(
+            ("move16      (a0)+,(a1)+".into(), 4),
+            asm(&[0xF620, 0x9000], Model::M68040)
+        );
+        assert_eq!(
+            ("dc.w        $f620".into(), 2),
+            asm(&[0xF620, 0x9000], Model::M68030)
+        );
+    }
+
+    #[test]
+    fn test_symbols() {
+        // jsr _func
+        let code = [0x4E, 0xB9, 0, 0, 0, 0];
+        let instruction = Instruction::decode_with(&code, 0, 0, Model::M68000, &mut |offset| {
+            (offset == 2).then(|| "_func".to_string())
+        });
+        assert_eq!("jsr         _func", instruction.text());
+        assert_eq!(6, instruction.size());
+
+        // truncated instructions are data
+        let instruction = Instruction::decode(&code[..4], 0, 0, Model::M68000);
+        assert_eq!("dc.w        $4eb9", instruction.text());
+        assert_eq!(2, instruction.size());
+    }
+}
@@ -0,0 +1,348 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Hitachi SH-2 instructions.
+//!
+//! SH-2 instructions are 16 bits wide and stored big-endian. Constants that do not fit in
+//! an instruction are loaded from literal pools placed after the code that uses them with
+//! PC relative `mov.w`, `mov.l`, and `mova` instructions.
+
+/// A decoded SH-2 instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instruction {
+    word: u16,
+    address: u32,
+}
+
+impl Instruction {
+    /// Creates an instruction from its encoding and the address it is located at.
+    pub fn new(word: u16, address: u32) -> Self {
+        Self { word, address }
+    }
+
+    /// Returns the encoded instruction.
+    pub fn word(&self) -> u16 {
+        self.word
+    }
+
+    fn n(&self) -> u16 {
+        (self.word >> 8) & 0xF
+    }
+
+    fn m(&self) -> u16 {
+        (self.word >> 4) & 0xF
+    }
+
+    fn imm8(&self) -> u32 {
+        u32::from(self.word & 0xFF)
+    }
+
+    fn simm8(&self) -> i32 {
+        i32::from(self.word as u8 as i8)
+    }
+
+    /// Returns the target of a branch with a signed displacement of `disp` instructions.
+    fn branch(&self, disp: i32) -> u32 {
+        self.address.wrapping_add(4).wrapping_add_signed(disp * 2)
+    }
+
+    /// Returns `true` if the following instruction executes before the branch is taken.
+    pub fn has_delay_slot(&self) -> bool {
+        matches!(self.word, 0x000B | 0x002B)
+            || matches!(self.word & 0xF0FF, 0x0003 | 0x0023 | 0x400B | 0x402B)
+            || matches!(self.word & 0xFF00, 0x8D00 | 0x8F00)
+            || matches!(self.word & 0xF000, 0xA000 | 0xB000)
+    }
+
+    /// Returns the address and size of the literal loaded by a PC relative `mov.w`, `mov.l`,
+    /// or `mova`.
+    pub fn literal(&self) -> Option<(u32, usize)> {
+        let disp = self.imm8();
+        match self.word >> 12 {
+            0x9 => Some((self.address + 4 + disp * 2, 2)),
+            0xD => Some(((self.address & !3) + 4 + disp * 4, 4)),
+            0xC if self.n() == 0x7 => Some(((self.address & !3) + 4 + disp * 4, 4)),
+            _ => None,
+        }
+    }
+
+    /// Returns the assembly for the instruction. Branch targets and PC relative literals are
+    /// shown as addresses.
+    pub fn disassemble(&self) -> String {
+        let (mnemonic, operands) = self.decode();
+        if operands.is_empty() {
+            mnemonic.to_string()
+        } else {
+            format!("{mnemonic:<12}{operands}")
+        }
+    }
+
+    fn decode(&self) -> (&'static str, String) {
+        let w = self.word;
+        let n = self.n();
+        let m = self.m();
+        let rn = format!("r{n}");
+        let rm = format!("r{m}");
+        let none = String::new;
+        match w >> 12 {
+            0x0 => match w {
+                0x0008 => ("clrt", none()),
+                0x0009 => ("nop", none()),
+                0x000B => ("rts", none()),
+                0x0018 => ("sett", none()),
+                0x0019 => ("div0u", none()),
+                0x001B => ("sleep", none()),
+                0x0028 => ("clrmac", none()),
+                0x002B => ("rte", none()),
+                _ => match w & 0xFF {
+                    0x02 => ("stc", format!("sr,{rn}")),
+                    0x03 => ("bsrf", rn),
+                    0x0A => ("sts", format!("mach,{rn}")),
+                    0x12 => ("stc", format!("gbr,{rn}")),
+                    0x1A => ("sts", format!("macl,{rn}")),
+                    0x22 => ("stc", format!("vbr,{rn}")),
+                    0x23 => ("braf", rn),
+                    0x29 => ("movt", rn),
+                    0x2A => ("sts", format!("pr,{rn}")),
+                    _ => match w & 0xF {
+                        0x4 => ("mov.b", format!("{rm},@(r0,{rn})")),
+                        0x5 => ("mov.w", format!("{rm},@(r0,{rn})")),
+                        0x6 => ("mov.l", format!("{rm},@(r0,{rn})")),
+                        0x7 => ("mul.l", format!("{rm},{rn}")),
+                        0xC => ("mov.b", format!("@(r0,{rm}),{rn}")),
+                        0xD => ("mov.w", format!("@(r0,{rm}),{rn}")),
+                        0xE => ("mov.l", format!("@(r0,{rm}),{rn}")),
+                        0xF => ("mac.l", format!("@{rm}+,@{rn}+")),
+                        _ => self.data(),
+                    },
+                },
+            },
+            0x1 => ("mov.l", format!("{rm},@({},{rn})", (w & 0xF) * 4)),
+            0x2 => {
+                let mnemonic = match w & 0xF {
+                    0x0 => return ("mov.b", format!("{rm},@{rn}")),
+                    0x1 => return ("mov.w", format!("{rm},@{rn}")),
+                    0x2 => return ("mov.l", format!("{rm},@{rn}")),
+                    0x4 => return ("mov.b", format!("{rm},@-{rn}")),
+                    0x5 => return ("mov.w", format!("{rm},@-{rn}")),
+                    0x6 => return ("mov.l", format!("{rm},@-{rn}")),
+                    0x7 => "div0s",
+                    0x8 => "tst",
+                    0x9 => "and",
+                    0xA => "xor",
+                    0xB => "or",
+                    0xC => "cmp/str",
+                    0xD => "xtrct",
+                    0xE => "mulu.w",
+                    0xF => "muls.w",
+                    _ => return self.data(),
+                };
+                (mnemonic, format!("{rm},{rn}"))
+            }
+            0x3 => {
+                let mnemonic = match w & 0xF {
+                    0x0 => "cmp/eq",
+                    0x2 => "cmp/hs",
+                    0x3 => "cmp/ge",
+                    0x4 => "div1",
+                    0x5 => "dmulu.l",
+                    0x6 => "cmp/hi",
+                    0x7 => "cmp/gt",
+                    0x8 => "sub",
+                    0xA => "subc",
+                    0xB => "subv",
+                    0xC => "add",
+                    0xD => "dmuls.l",
+                    0xE => "addc",
+                    0xF => "addv",
+                    _ => return self.data(),
+                };
+                (mnemonic, format!("{rm},{rn}"))
+            }
+            0x4 => {
+                if w & 0xF == 0xF {
+                    return ("mac.w", format!("@{rm}+,@{rn}+"));
+                }
+                match w & 0xFF {
+                    0x00 => ("shll", rn),
+                    0x01 => ("shlr", rn),
+                    0x02 => ("sts.l", format!("mach,@-{rn}")),
+                    0x03 => ("stc.l", format!("sr,@-{rn}")),
+                    0x04 => ("rotl", rn),
+                    0x05 => ("rotr", rn),
+                    0x06 => ("lds.l", format!("@{rn}+,mach")),
+                    0x07 => ("ldc.l", format!("@{rn}+,sr")),
+                    0x08 => ("shll2", rn),
+                    0x09 => ("shlr2", rn),
+                    0x0A => ("lds", format!("{rn},mach")),
+                    0x0B => ("jsr", format!("@{rn}")),
+                    0x0E => ("ldc", format!("{rn},sr")),
+                    0x10 => ("dt", rn),
+                    0x11 => ("cmp/pz", rn),
+                    0x12 => ("sts.l", format!("macl,@-{rn}")),
+                    0x13 => ("stc.l", format!("gbr,@-{rn}")),
+                    0x15 => ("cmp/pl", rn),
+                    0x16 => ("lds.l", format!("@{rn}+,macl")),
+                    0x17 => ("ldc.l", format!("@{rn}+,gbr")),
+                    0x18 => ("shll8", rn),
+                    0x19 => ("shlr8", rn),
+                    0x1A => ("lds", format!("{rn},macl")),
+                    0x1B => ("tas.b", format!("@{rn}")),
+                    0x1E => ("ldc", format!("{rn},gbr")),
+                    0x20 => ("shal", rn),
+                    0x21 => ("shar", rn),
+                    0x22 => ("sts.l", format!("pr,@-{rn}")),
+                    0x23 => ("stc.l", format!("vbr,@-{rn}")),
+                    0x24 => ("rotcl", rn),
+                    0x25 => ("rotcr", rn),
+                    0x26 => ("lds.l", format!("@{rn}+,pr")),
+                    0x27 => ("ldc.l", format!("@{rn}+,vbr")),
+                    0x28 => ("shll16", rn),
+                    0x29 => ("shlr16", rn),
+                    0x2A => ("lds", format!("{rn},pr")),
+                    0x2B => ("jmp", format!("@{rn}")),
+                    0x2E => ("ldc", format!("{rn},vbr")),
+                    _ => self.data(),
+                }
+            }
+            0x5 => ("mov.l", format!("@({},{rm}),{rn}", (w & 0xF) * 4)),
+            0x6 => {
+                let mnemonic = match w & 0xF {
+                    0x0 => return ("mov.b", format!("@{rm},{rn}")),
+                    0x1 => return ("mov.w", format!("@{rm},{rn}")),
+                    0x2 => return ("mov.l", format!("@{rm},{rn}")),
+                    0x3 => "mov",
+                    0x4 => return ("mov.b", format!("@{rm}+,{rn}")),
+                    0x5 => return ("mov.w", format!("@{rm}+,{rn}")),
+                    0x6 => return ("mov.l", format!("@{rm}+,{rn}")),
+                    0x7 => "not",
+                    0x8 => "swap.b",
+                    0x9 => "swap.w",
+                    0xA => "negc",
+                    0xB => "neg",
+                    0xC => "extu.b",
+                    0xD => "extu.w",
+                    0xE => "exts.b",
+                    _ => "exts.w",
+                };
+                (mnemonic, format!("{rm},{rn}"))
+            }
+            0x7 => ("add", format!("#{},{rn}", self.simm8())),
+            0x8 => {
+                let disp = w & 0xF;
+                match n {
+                    0x0 => ("mov.b", format!("r0,@({disp},{rm})")),
+                    0x1 => ("mov.w", format!("r0,@({},{rm})", disp * 2)),
+                    0x4 => ("mov.b", format!("@({disp},{rm}),r0")),
+                    0x5 => ("mov.w", format!("@({},{rm}),r0", disp * 2)),
+                    0x8 => ("cmp/eq", format!("#{},r0", self.simm8())),
+                    0x9 => ("bt", format!("{:#x}", self.branch(self.simm8()))),
+                    0xB => ("bf", format!("{:#x}", self.branch(self.simm8()))),
+                    0xD => ("bt/s", format!("{:#x}", self.branch(self.simm8()))),
+                    0xF => ("bf/s", format!("{:#x}", self.branch(self.simm8()))),
+                    _ => self.data(),
+                }
+            }
+            0x9 | 0xD => {
+                let mnemonic = if w >> 12 == 0x9 { "mov.w" } else { "mov.l" };
+                let (address, _) = self.literal().expect("PC relative load");
+                (mnemonic, format!("{address:#x},{rn}"))
+            }
+            0xA | 0xB => {
+                let disp = (i32::from(w & 0xFFF) << 20) >> 20;
+                let mnemonic = if w >> 12 == 0xA { "bra" } else { "bsr" };
+                (mnemonic, format!("{:#x}", self.branch(disp)))
+            }
+            0xC => {
+                let disp = self.imm8();
+                let imm = self.imm8();
+                match n {
+                    0x0 => ("mov.b", format!("r0,@({disp},gbr)")),
+                    0x1 => ("mov.w", format!("r0,@({},gbr)", disp * 2)),
+                    0x2 => ("mov.l", format!("r0,@({},gbr)", disp * 4)),
+                    0x3 => ("trapa", format!("#{imm:#x}")),
+                    0x4 => ("mov.b", format!("@({disp},gbr),r0")),
+                    0x5 => ("mov.w", format!("@({},gbr),r0", disp * 2)),
+                    0x6 => ("mov.l", format!("@({},gbr),r0", disp * 4)),
+                    0x7 => {
+                        let (address, _) = self.literal().expect("PC relative address");
+                        ("mova", format!("{address:#x},r0"))
+                    }
+                    0x8 => ("tst", format!("#{imm:#x},r0")),
+                    0x9 => ("and", format!("#{imm:#x},r0")),
+                    0xA => ("xor", format!("#{imm:#x},r0")),
+                    0xB => ("or", format!("#{imm:#x},r0")),
+                    0xC => ("tst.b", format!("#{imm:#x},@(r0,gbr)")),
+                    0xD => ("and.b", format!("#{imm:#x},@(r0,gbr)")),
+                    0xE => ("xor.b", format!("#{imm:#x},@(r0,gbr)")),
+                    _ => ("or.b", format!("#{imm:#x},@(r0,gbr)")),
+                }
+            }
+            0xE => ("mov", format!("#{},{rn}", self.simm8())),
+            _ => self.data(),
+        }
+    }
+
+    /// A word that is not an SH-2 instruction.
+    fn data(&self) -> (&'static str, String) {
+        (".word", format!("{:#06x}", self.word))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn asm(word: u16, address: u32) -> String {
+        Instruction::new(word, address).disassemble()
+    }
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!("nop", asm(0x0009, 0));
+        assert_eq!("rts", asm(0x000B, 0));
+        assert_eq!("sts.l       pr,@-r15", asm(0x4F22, 0));
+        assert_eq!("lds.l       @r15+,pr", asm(0x4F26, 0));
+        assert_eq!("mov.l       r14,@-r15", asm(0x2FE6, 0));
+        assert_eq!("mov         r15,r14", asm(0x6EF3, 0));
+        assert_eq!("mov         #-1,r0", asm(0xE0FF, 0));
+        assert_eq!("add         #4,r15", asm(0x7F04, 0));
+        assert_eq!("mov.l       @(8,r4),r1", asm(0x5142, 0));
+        assert_eq!("mov.l       r1,@(12,r4)", asm(0x1413, 0));
+        assert_eq!("mov.w       @(2,r4),r0", asm(0x8541, 0));
+        assert_eq!("jsr         @r3", asm(0x430B, 0));
+        assert_eq!("cmp/eq      r2,r1", asm(0x3120, 0));
+        assert_eq!("and         #0xff,r0", asm(0xC9FF, 0));
+        assert_eq!("mov.l       @(r0,r5),r2", asm(0x025E, 0));
+        assert_eq!("mac.w       @r5+,@r4+", asm(0x445F, 0));
+        assert_eq!(".word       0xffff", asm(0xFFFF, 0));
+    }
+
+    #[test]
+    fn test_branches() {
+        // bra with a negative displacement
+        assert_eq!("bra         0x10", asm(0xAFFA, 0x18));
+        assert_eq!("bsr         0x28", asm(0xB00A, 0x10));
+        assert_eq!("bf          0x8", asm(0x8BFE, 0x8));
+        assert_eq!("bt/s        0x20", asm(0x8D06, 0x10));
+        assert!(Instruction::new(0xAFFA, 0).has_delay_slot());
+        assert!(Instruction::new(0x000B, 0).has_delay_slot());
+        assert!(Instruction::new(0x430B, 0).has_delay_slot());
+        assert!(Instruction::new(0x8D06, 0).has_delay_slot());
+        assert!(!Instruction::new(0x8906, 0).has_delay_slot());
+        assert!(!Instruction::new(0x0009, 0).has_delay_slot());
+    }
+
+    #[test]
+    fn test_literals() {
+        // mov.l @(disp,pc) is relative to the aligned address of the instruction
+        let mov = Instruction::new(0xDC00, 0x1E);
+        assert_eq!(Some((0x20, 4)), mov.literal());
+        assert_eq!("mov.l       0x20,r12", mov.disassemble());
+        let mov = Instruction::new(0x9102, 0x12);
+        assert_eq!(Some((0x1A, 2)), mov.literal());
+        assert_eq!("mov.w       0x1a,r1", mov.disassemble());
+        assert_eq!("mova        0x8,r0", asm(0xC701, 0x2));
+        assert_eq!(None, Instruction::new(0x0009, 0).literal());
+    }
+}
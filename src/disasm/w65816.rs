@@ -0,0 +1,426 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! WDC 65816 instructions.
+//!
+//! 65816 instructions are an opcode followed by zero to three little-endian operand bytes.
+//! The size of immediate operands depends on the M and X flags of the processor status
+//! register, which select 8 or 16-bit accumulator and index registers. The flags are not
+//! part of the code, so the decoder is given the [Flags] in effect for each instruction and
+//! follows the changes made by `rep` and `sep`.
+//!
+//! Instructions are shown in WDC syntax with hexadecimal values prefixed by `$`.
+
+/// The M and X flags of the processor status register.
+///
+/// A set flag selects an 8-bit accumulator (M) or 8-bit index registers (X). Both are set
+/// when the processor is reset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flags {
+    bits: u8,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            bits: Flags::M | Flags::X,
+        }
+    }
+}
+
+impl Flags {
+    /// The accumulator and memory width flag in the processor status register.
+    pub const M: u8 = 0x20;
+
+    /// The index register width flag in the processor status register.
+    pub const X: u8 = 0x10;
+
+    /// Creates flags from the M and X bits of the processor status register in `status`.
+    pub fn new(status: u8) -> Self {
+        Self {
+            bits: status & (Self::M | Self::X),
+        }
+    }
+
+    /// Creates flags from the value of a [SetMXInfo](crate::SetMXInfo), which stores M in
+    /// bit 1 and X in bit 0.
+    pub fn from_mx(value: u8) -> Self {
+        Self::new(((value & 2) << 4) | ((value & 1) << 4))
+    }
+
+    /// Returns the M and X bits of the processor status register.
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Returns the size in bytes of the accumulator.
+    pub fn accumulator_size(&self) -> usize {
+        if self.bits & Self::M != 0 {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Returns the size in bytes of the index registers.
+    pub fn index_size(&self) -> usize {
+        if self.bits & Self::X != 0 {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// A decoded 65816 instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    text: String,
+    size: usize,
+    flags: Flags,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `offset` in `code`, which is located at `address`, with
+    /// the register widths selected by `flags`.
+    ///
+    /// Instructions that extend past the end of `code` are decoded as a single `db`.
+    pub fn decode(code: &[u8], offset: usize, address: u32, flags: Flags) -> Self {
+        Self::decode_with(code, offset, address, flags, &mut |_| None)
+    }
+
+    /// Decodes the instruction at `offset` in `code` like [decode](Self::decode). `symbol`
+    /// is called with the offset in `code` of each address or immediate value and returns the
+    /// name to show in place of the value, if any.
+    pub fn decode_with(
+        code: &[u8],
+        offset: usize,
+        address: u32,
+        flags: Flags,
+        symbol: &mut dyn FnMut(usize) -> Option<String>,
+    ) -> Self {
+        let (mnemonic, mode) = OPCODES[usize::from(code.get(offset).copied().unwrap_or(0))];
+        let size = 1 + mode.size(flags);
+        let Some(operand) = code.get(offset + 1..offset + size) else {
+            return Self {
+                text: format!(
+                    "{:<12}${:02x}",
+                    "db",
+                    code.get(offset).copied().unwrap_or(0)
+                ),
+                size: 1,
+                flags,
+            };
+        };
+        let value = operand
+            .iter()
+            .rev()
+            .fold(0u32, |value, byte| (value << 8) | u32::from(*byte));
+        let symbol = if size > 1 { symbol(offset + 1) } else { None };
+        let text = match mode.format(value, address, size, symbol) {
+            operands if operands.is_empty() => mnemonic.to_string(),
+            operands => format!("{mnemonic:<12}{operands}"),
+        };
+        let flags = match mnemonic {
+            "rep" => Flags::new(flags.bits() & !(value as u8)),
+            "sep" => Flags::new(flags.bits() | value as u8),
+            _ => flags,
+        };
+        Self { text, size, flags }
+    }
+
+    /// Returns the size of the instruction in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the assembly for the instruction.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the flags in effect after the instruction.
+    pub fn flags(&self) -> Flags {
+        self.flags
+    }
+}
+
+/// Addressing modes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// No operand, e.g. `rts`.
+    Implied,
+    /// `a`
+    Accumulator,
+    /// `#$12` or `#$1234` depending on M.
+    ImmediateM,
+    /// `#$12` or `#$1234` depending on X.
+    ImmediateX,
+    /// `#$12`
+    Immediate,
+    /// `$12` signature byte of `brk`, `cop`, and `wdm`.
+    Signature,
+    /// `$12`
+    Direct,
+    /// `$12,x`
+    DirectX,
+    /// `$12,y`
+    DirectY,
+    /// `($12)`
+    DirectIndirect,
+    /// `($12,x)`
+    DirectIndirectX,
+    /// `($12),y`
+    DirectIndirectY,
+    /// `[$12]`
+    DirectIndirectLong,
+    /// `[$12],y`
+    DirectIndirectLongY,
+    /// `$1234`
+    Absolute,
+    /// `$1234,x`
+    AbsoluteX,
+    /// `$1234,y`
+    AbsoluteY,
+    /// `$123456`
+    Long,
+    /// `$123456,x`
+    LongX,
+    /// `($1234)`
+    AbsoluteIndirect,
+    /// `($1234,x)`
+    AbsoluteIndirectX,
+    /// `[$1234]`
+    AbsoluteIndirectLong,
+    /// `$12,s`
+    Stack,
+    /// `($12,s),y`
+    StackIndirectY,
+    /// 8-bit branch displacement.
+    Relative,
+    /// 16-bit branch displacement.
+    RelativeLong,
+    /// `$12,$34` source and destination banks, stored in reverse order.
+    BlockMove,
+}
+
+impl Mode {
+    /// The size of the operand.
+    fn size(&self, flags: Flags) -> usize {
+        use Mode::*;
+        match self {
+            Implied | Accumulator => 0,
+            ImmediateM => flags.accumulator_size(),
+            ImmediateX => flags.index_size(),
+            Immediate | Signature | Direct | DirectX | DirectY | DirectIndirect
+            | DirectIndirectX | DirectIndirectY | DirectIndirectLong | DirectIndirectLongY
+            | Stack | StackIndirectY | Relative => 1,
+            Absolute | AbsoluteX | AbsoluteY | AbsoluteIndirect | AbsoluteIndirectX
+            | AbsoluteIndirectLong | RelativeLong | BlockMove => 2,
+            Long | LongX => 3,
+        }
+    }
+
+    /// Formats the operand `value` of an instruction of `size` bytes at `address`, or the
+    /// `symbol` relocating it.
+    fn format(&self, value: u32, address: u32, size: usize, symbol: Option<String>) -> String {
+        use Mode::*;
+        let width = 2 * (size - 1);
+        let number = symbol.unwrap_or_else(|| match self {
+            Relative | RelativeLong => {
+                let displacement = if *self == Relative {
+                    i32::from(value as u8 as i8)
+                } else {
+                    i32::from(value as u16 as i16)
+                };
+                let target = address
+                    .wrapping_add(size as u32)
+                    .wrapping_add_signed(displacement);
+                format!("${target:04x}")
+            }
+            BlockMove => format!("${:02x},${:02x}", value >> 8, value & 0xFF),
+            _ => format!("${value:0width$x}"),
+        });
+        match self {
+            Implied => String::new(),
+            Accumulator => "a".to_string(),
+            ImmediateM | ImmediateX | Immediate => format!("#{number}"),
+            DirectX | AbsoluteX | LongX => format!("{number},x"),
+            DirectY | AbsoluteY => format!("{number},y"),
+            DirectIndirect | AbsoluteIndirect => format!("({number})"),
+            DirectIndirectX | AbsoluteIndirectX => format!("({number},x)"),
+            DirectIndirectY => format!("({number}),y"),
+            DirectIndirectLong | AbsoluteIndirectLong => format!("[{number}]"),
+            DirectIndirectLongY => format!("[{number}],y"),
+            Stack => format!("{number},s"),
+            StackIndirectY => format!("({number},s),y"),
+            _ => number,
+        }
+    }
+}
+
+/// Mnemonics and addressing modes of each opcode.
+#[rustfmt::skip]
+const OPCODES: [(&str, Mode); 256] = {
+    use Mode::*;
+    [
+        // 0x00
+        ("brk", Signature), ("ora", DirectIndirectX), ("cop", Signature), ("ora", Stack),
+        ("tsb", Direct), ("ora", Direct), ("asl", Direct), ("ora", DirectIndirectLong),
+        ("php", Implied), ("ora", ImmediateM), ("asl", Accumulator), ("phd", Implied),
+        ("tsb", Absolute), ("ora", Absolute), ("asl", Absolute), ("ora", Long),
+        // 0x10
+        ("bpl", Relative), ("ora", DirectIndirectY), ("ora", DirectIndirect), ("ora", StackIndirectY),
+        ("trb", Direct), ("ora", DirectX), ("asl", DirectX), ("ora", DirectIndirectLongY),
+        ("clc", Implied), ("ora", AbsoluteY), ("inc", Accumulator), ("tcs", Implied),
+        ("trb", Absolute), ("ora", AbsoluteX), ("asl", AbsoluteX), ("ora", LongX),
+        // 0x20
+        ("jsr", Absolute), ("and", DirectIndirectX), ("jsl", Long), ("and", Stack),
+        ("bit", Direct), ("and", Direct), ("rol", Direct), ("and", DirectIndirectLong),
+        ("plp", Implied), ("and", ImmediateM), ("rol", Accumulator), ("pld", Implied),
+        ("bit", Absolute), ("and", Absolute), ("rol", Absolute), ("and", Long),
+        // 0x30
+        ("bmi", Relative), ("and", DirectIndirectY), ("and", DirectIndirect), ("and", StackIndirectY),
+        ("bit", DirectX), ("and", DirectX), ("rol", DirectX), ("and", DirectIndirectLongY),
+        ("sec", Implied), ("and", AbsoluteY), ("dec", Accumulator), ("tsc", Implied),
+        ("bit", AbsoluteX), ("and", AbsoluteX), ("rol", AbsoluteX), ("and", LongX),
+        // 0x40
+        ("rti", Implied), ("eor", DirectIndirectX), ("wdm", Signature), ("eor", Stack),
+        ("mvp", BlockMove), ("eor", Direct), ("lsr", Direct), ("eor", DirectIndirectLong),
+        ("pha", Implied), ("eor", ImmediateM), ("lsr", Accumulator), ("phk", Implied),
+        ("jmp", Absolute), ("eor", Absolute), ("lsr", Absolute), ("eor", Long),
+        // 0x50
+        ("bvc", Relative), ("eor", DirectIndirectY), ("eor", DirectIndirect), ("eor", StackIndirectY),
+        ("mvn", BlockMove), ("eor", DirectX), ("lsr", DirectX), ("eor", DirectIndirectLongY),
+        ("cli", Implied), ("eor", AbsoluteY), ("phy", Implied), ("tcd", Implied),
+        ("jml", Long), ("eor", AbsoluteX), ("lsr", AbsoluteX), ("eor", LongX),
+        // 0x60
+        ("rts", Implied), ("adc", DirectIndirectX), ("per", RelativeLong), ("adc", Stack),
+        ("stz", Direct), ("adc", Direct), ("ror", Direct), ("adc", DirectIndirectLong),
+        ("pla", Implied), ("adc", ImmediateM), ("ror", Accumulator), ("rtl", Implied),
+        ("jmp", AbsoluteIndirect), ("adc", Absolute), ("ror", Absolute), ("adc", Long),
+        // 0x70
+        ("bvs", Relative), ("adc", DirectIndirectY), ("adc", DirectIndirect), ("adc", StackIndirectY),
+        ("stz", DirectX), ("adc", DirectX), ("ror", DirectX), ("adc", DirectIndirectLongY),
+        ("sei", Implied), ("adc", AbsoluteY), ("ply", Implied), ("tdc", Implied),
+        ("jmp", AbsoluteIndirectX), ("adc", AbsoluteX), ("ror", AbsoluteX), ("adc", LongX),
+        // 0x80
+        ("bra", Relative), ("sta", DirectIndirectX), ("brl", RelativeLong), ("sta", Stack),
+        ("sty", Direct), ("sta", Direct), ("stx", Direct), ("sta", DirectIndirectLong),
+        ("dey", Implied), ("bit", ImmediateM), ("txa", Implied), ("phb", Implied),
+        ("sty", Absolute), ("sta", Absolute), ("stx", Absolute), ("sta", Long),
+        // 0x90
+        ("bcc", Relative), ("sta", DirectIndirectY), ("sta", DirectIndirect), ("sta", StackIndirectY),
+        ("sty", DirectX), ("sta", DirectX), ("stx", DirectY), ("sta", DirectIndirectLongY),
+        ("tya", Implied), ("sta", AbsoluteY), ("txs", Implied), ("txy", Implied),
+        ("stz", Absolute), ("sta", AbsoluteX), ("stz", AbsoluteX), ("sta", LongX),
+        // 0xA0
+        ("ldy", ImmediateX), ("lda", DirectIndirectX), ("ldx", ImmediateX), ("lda", Stack),
+        ("ldy", Direct), ("lda", Direct), ("ldx", Direct), ("lda", DirectIndirectLong),
+        ("tay", Implied), ("lda", ImmediateM), ("tax", Implied), ("plb", Implied),
+        ("ldy", Absolute), ("lda", Absolute), ("ldx", Absolute), ("lda", Long),
+        // 0xB0
+        ("bcs", Relative), ("lda", DirectIndirectY), ("lda", DirectIndirect), ("lda", StackIndirectY),
+        ("ldy", DirectX), ("lda", DirectX), ("ldx", DirectY), ("lda", DirectIndirectLongY),
+        ("clv", Implied), ("lda", AbsoluteY), ("tsx", Implied), ("tyx", Implied),
+        ("ldy", AbsoluteX), ("lda", AbsoluteX), ("ldx", AbsoluteY), ("lda", LongX),
+        // 0xC0
+        ("cpy", ImmediateX), ("cmp", DirectIndirectX), ("rep", Immediate), ("cmp", Stack),
+        ("cpy", Direct), ("cmp", Direct), ("dec", Direct), ("cmp", DirectIndirectLong),
+        ("iny", Implied), ("cmp", ImmediateM), ("dex", Implied), ("wai", Implied),
+        ("cpy", Absolute), ("cmp", Absolute), ("dec", Absolute), ("cmp", Long),
+        // 0xD0
+        ("bne", Relative), ("cmp", DirectIndirectY), ("cmp", DirectIndirect), ("cmp", StackIndirectY),
+        ("pei", DirectIndirect), ("cmp", DirectX), ("dec", DirectX), ("cmp", DirectIndirectLongY),
+        ("cld", Implied), ("cmp", AbsoluteY), ("phx", Implied), ("stp", Implied),
+        ("jml", AbsoluteIndirectLong), ("cmp", AbsoluteX), ("dec", AbsoluteX), ("cmp", LongX),
+        // 0xE0
+        ("cpx", ImmediateX), ("sbc", DirectIndirectX), ("sep", Immediate), ("sbc", Stack),
+        ("cpx", Direct), ("sbc", Direct), ("inc", Direct), ("sbc", DirectIndirectLong),
+        ("inx", Implied), ("sbc", ImmediateM), ("nop", Implied), ("xba", Implied),
+        ("cpx", Absolute), ("sbc", Absolute), ("inc", Absolute), ("sbc", Long),
+        // 0xF0
+        ("beq", Relative), ("sbc", DirectIndirectY), ("sbc", DirectIndirect), ("sbc", StackIndirectY),
+        ("pea", Absolute), ("sbc", DirectX), ("inc", DirectX), ("sbc", DirectIndirectLongY),
+        ("sed", Implied), ("sbc", AbsoluteY), ("plx", Implied), ("xce", Implied),
+        ("jsr", AbsoluteIndirectX), ("sbc", AbsoluteX), ("inc", AbsoluteX), ("sbc", LongX),
+    ]
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn asm(code: &[u8], flags: Flags) -> String {
+        let instruction = Instruction::decode(code, 0, 0x8000, flags);
+        assert_eq!(code.len(), instruction.size(), "{}", instruction.text());
+        instruction.text().to_string()
+    }
+
+    #[test]
+    fn test_modes() {
+        let flags = Flags::default();
+        assert_eq!("rts", asm(&[0x60], flags));
+        assert_eq!("asl         a", asm(&[0x0A], flags));
+        assert_eq!("lda         $12", asm(&[0xA5, 0x12], flags));
+        assert_eq!("sta         $1234,x", asm(&[0x9D, 0x34, 0x12], flags));
+        assert_eq!(
+            "lda         $7e1234,x",
+            asm(&[0xBF, 0x34, 0x12, 0x7E], flags)
+        );
+        assert_eq!("jsl         $008000", asm(&[0x22, 0x00, 0x80, 0x00], flags));
+        assert_eq!("lda         [$10],y", asm(&[0xB7, 0x10], flags));
+        assert_eq!("lda         ($03,s),y", asm(&[0xB3, 0x03], flags));
+        assert_eq!("ldx         $12,y", asm(&[0xB6, 0x12], flags));
+        assert_eq!("jmp         ($1234,x)", asm(&[0x7C, 0x34, 0x12], flags));
+        assert_eq!("jml         [$0100]", asm(&[0xDC, 0x00, 0x01], flags));
+        assert_eq!("mvn         $7e,$7f", asm(&[0x54, 0x7F, 0x7E], flags));
+        assert_eq!("brk         $00", asm(&[0x00, 0x00], flags));
+    }
+
+    #[test]
+    fn test_branches() {
+        let flags = Flags::default();
+        assert_eq!("bra         $8000", asm(&[0x80, 0xFE], flags));
+        assert_eq!("bne         $8012", asm(&[0xD0, 0x10], flags));
+        assert_eq!("brl         $7003", asm(&[0x82, 0x00, 0xF0], flags));
+    }
+
+    #[test]
+    fn test_flags() {
+        let short = Flags::default();
+        let long = Flags::new(0);
+        assert_eq!(1, short.accumulator_size());
+        assert_eq!(2, long.index_size());
+        assert_eq!("lda         #$12", asm(&[0xA9, 0x12], short));
+        assert_eq!("lda         #$1234", asm(&[0xA9, 0x34, 0x12], long));
+        assert_eq!(
+            "ldx         #$1234",
+            asm(&[0xA2, 0x34, 0x12], Flags::new(Flags::M))
+        );
+        assert_eq!("ldx         #$12", asm(&[0xA2, 0x12], Flags::new(Flags::X)));
+        assert_eq!(Flags::new(Flags::M), Flags::from_mx(2));
+        assert_eq!(Flags::new(Flags::X), Flags::from_mx(1));
+
+        let rep = Instruction::decode(&[0xC2, 0x30], 0, 0, short);
+        assert_eq!("rep         #$30", rep.text());
+        assert_eq!(long, rep.flags());
+        let sep = Instruction::decode(&[0xE2, 0x20], 0, 0, long);
+        assert_eq!(Flags::new(Flags::M), sep.flags());
+    }
+
+    #[test]
+    fn test_symbols() {
+        // jsl _func
+        let code = [0x22, 0, 0, 0];
+        let instruction = Instruction::decode_with(&code, 0, 0, Flags::default(), &mut |offset| {
+            (offset == 1).then(|| "_func".to_string())
+        });
+        assert_eq!("jsl         _func", instruction.text());
+
+        // truncated instructions are data
+        let instruction = Instruction::decode(&code[..2], 0, 0, Flags::default());
+        assert_eq!("db          $22", instruction.text());
+        assert_eq!(1, instruction.size());
+    }
+}
@@ -0,0 +1,488 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Zilog Z80 instructions.
+//!
+//! Z80 instructions are one to four bytes: an optional `CB`, `DD`, `ED`, or `FD` prefix, an
+//! opcode, and little-endian operands. `DD` and `FD` replace `hl` with `ix` and `iy` in the
+//! instruction that follows them. Undocumented instructions using the halves of the index
+//! registers are decoded, other undefined opcodes are shown as data.
+//!
+//! Instructions are shown in Zilog syntax with hexadecimal values prefixed by `$`.
+
+/// A decoded Z80 instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    text: String,
+    size: usize,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `offset` in `code`, which is located at `address`.
+    ///
+    /// Undefined opcodes, prefixes that do not modify the following instruction, and
+    /// instructions that extend past the end of `code` are decoded as a single `db`.
+    pub fn decode(code: &[u8], offset: usize, address: u32) -> Self {
+        Self::decode_with(code, offset, address, &mut |_| None)
+    }
+
+    /// Decodes the instruction at `offset` in `code` like [decode](Self::decode). `symbol`
+    /// is called with the offset in `code` of each address, displacement, or immediate value
+    /// and returns the name to show in place of the value, if any.
+    pub fn decode_with(
+        code: &[u8],
+        offset: usize,
+        address: u32,
+        symbol: &mut dyn FnMut(usize) -> Option<String>,
+    ) -> Self {
+        let mut decoder = Decoder {
+            code,
+            start: offset,
+            position: offset,
+            address,
+            index: None,
+            displacement: None,
+            indexed: false,
+            symbol,
+        };
+        match decoder.instruction() {
+            Some(text) => Self {
+                text,
+                size: decoder.position - offset,
+            },
+            None => Self {
+                text: format!(
+                    "{:<12}${:02x}",
+                    "db",
+                    code.get(offset).copied().unwrap_or(0)
+                ),
+                size: 1,
+            },
+        }
+    }
+
+    /// Returns the size of the instruction in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the assembly for the instruction.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+const CONDITIONS: [&str; 8] = ["nz", "z", "nc", "c", "po", "pe", "p", "m"];
+
+const ALU: [&str; 8] = ["add", "adc", "sub", "sbc", "and", "xor", "or", "cp"];
+
+const ROTATIONS: [&str; 8] = ["rlc", "rrc", "rl", "rr", "sla", "sra", "sll", "srl"];
+
+const ACCUMULATOR: [&str; 8] = ["rlca", "rrca", "rla", "rra", "daa", "cpl", "scf", "ccf"];
+
+/// Block transfer, search, and I/O instructions indexed by the repeat and direction bits and
+/// the operation.
+const BLOCK: [[&str; 4]; 4] = [
+    ["ldi", "cpi", "ini", "outi"],
+    ["ldd", "cpd", "ind", "outd"],
+    ["ldir", "cpir", "inir", "otir"],
+    ["lddr", "cpdr", "indr", "otdr"],
+];
+
+fn op(mnemonic: &str, operands: &str) -> String {
+    if operands.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{mnemonic:<12}{operands}")
+    }
+}
+
+/// The operands of an arithmetic or logic operation with the accumulator. Only `add`, `adc`,
+/// and `sbc` name the accumulator.
+fn alu(operation: u8, operand: &str) -> String {
+    let mnemonic = ALU[usize::from(operation)];
+    match operation {
+        0 | 1 | 3 => op(mnemonic, &format!("a,{operand}")),
+        _ => op(mnemonic, operand),
+    }
+}
+
+struct Decoder<'a> {
+    code: &'a [u8],
+    start: usize,
+    position: usize,
+    address: u32,
+    /// `ix` or `iy` when the instruction has a `DD` or `FD` prefix.
+    index: Option<&'static str>,
+    /// The displacement of an indexed `CB` instruction, which precedes its opcode.
+    displacement: Option<String>,
+    /// Whether the index register replaced `hl` in the instruction.
+    indexed: bool,
+    symbol: &'a mut dyn FnMut(usize) -> Option<String>,
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.code.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn word(&mut self) -> Option<u16> {
+        let low = self.byte()?;
+        let high = self.byte()?;
+        Some(u16::from_le_bytes([low, high]))
+    }
+
+    /// An 8-bit immediate value.
+    fn n(&mut self) -> Option<String> {
+        let offset = self.position;
+        let value = self.byte()?;
+        Some((self.symbol)(offset).unwrap_or_else(|| format!("${value:02x}")))
+    }
+
+    /// A 16-bit immediate value or address.
+    fn nn(&mut self) -> Option<String> {
+        let offset = self.position;
+        let value = self.word()?;
+        Some((self.symbol)(offset).unwrap_or_else(|| format!("${value:04x}")))
+    }
+
+    /// The target of a relative jump.
+    fn relative(&mut self) -> Option<String> {
+        let offset = self.position;
+        let displacement = self.byte()? as i8;
+        let pc = self
+            .address
+            .wrapping_add((self.position - self.start) as u32);
+        Some(
+            (self.symbol)(offset).unwrap_or_else(|| {
+                format!("${:04x}", pc.wrapping_add_signed(i32::from(displacement)))
+            }),
+        )
+    }
+
+    /// A signed 8-bit displacement, e.g. `+$05`.
+    fn d(&mut self) -> Option<String> {
+        let offset = self.position;
+        let value = self.byte()? as i8;
+        Some(match (self.symbol)(offset) {
+            Some(symbol) => format!("+{symbol}"),
+            None if value < 0 => format!("-${:02x}", value.unsigned_abs()),
+            None => format!("+${value:02x}"),
+        })
+    }
+
+    /// `(ix+d)` or `(iy+d)`.
+    fn index_displacement(&mut self, index: &str) -> Option<String> {
+        let displacement = match self.displacement.take() {
+            Some(displacement) => displacement,
+            None => self.d()?,
+        };
+        Some(format!("({index}{displacement})"))
+    }
+
+    /// The 8-bit register numbered `register`. `(hl)` becomes `(ix+d)` and, when `halves` is
+    /// set, `h` and `l` become `ixh` and `ixl`.
+    fn r(&mut self, register: u8, halves: bool) -> Option<String> {
+        Some(match (register, self.index) {
+            (6, Some(index)) => {
+                self.indexed = true;
+                self.index_displacement(index)?
+            }
+            (4 | 5, Some(index)) if halves => {
+                self.indexed = true;
+                format!("{index}{}", if register == 4 { "h" } else { "l" })
+            }
+            _ => ["b", "c", "d", "e", "h", "l", "(hl)", "a"][usize::from(register)].to_string(),
+        })
+    }
+
+    fn hl(&mut self) -> &'static str {
+        match self.index {
+            Some(index) => {
+                self.indexed = true;
+                index
+            }
+            None => "hl",
+        }
+    }
+
+    /// The register pair numbered `pair`, with `sp` or `af` as the last pair.
+    fn rp(&mut self, pair: u8, af: bool) -> &'static str {
+        match pair {
+            0 => "bc",
+            1 => "de",
+            2 => self.hl(),
+            _ if af => "af",
+            _ => "sp",
+        }
+    }
+
+    fn instruction(&mut self) -> Option<String> {
+        let opcode = self.byte()?;
+        Some(match opcode {
+            0xCB => self.cb()?,
+            0xED => self.ed()?,
+            0xDD | 0xFD => {
+                self.index = Some(if opcode == 0xDD { "ix" } else { "iy" });
+                let text = match self.byte()? {
+                    0xCB => {
+                        // the displacement precedes the opcode
+                        self.displacement = Some(self.d()?);
+                        self.cb()?
+                    }
+                    0xDD | 0xED | 0xFD => return None,
+                    opcode => self.unprefixed(opcode)?,
+                };
+                if !self.indexed {
+                    return None;
+                }
+                text
+            }
+            opcode => self.unprefixed(opcode)?,
+        })
+    }
+
+    fn unprefixed(&mut self, opcode: u8) -> Option<String> {
+        let x = opcode >> 6;
+        let y = (opcode >> 3) & 7;
+        let z = opcode & 7;
+        let p = y >> 1;
+        let q = y & 1 != 0;
+        Some(match (x, z) {
+            (0, 0) => match y {
+                0 => op("nop", ""),
+                1 => op("ex", "af,af'"),
+                2 => op("djnz", &self.relative()?),
+                3 => op("jr", &self.relative()?),
+                _ => op(
+                    "jr",
+                    &format!("{},{}", CONDITIONS[usize::from(y - 4)], self.relative()?),
+                ),
+            },
+            (0, 1) if q => {
+                let hl = self.hl();
+                op("add", &format!("{hl},{}", self.rp(p, false)))
+            }
+            (0, 1) => {
+                let rp = self.rp(p, false);
+                op("ld", &format!("{rp},{}", self.nn()?))
+            }
+            (0, 2) => {
+                let (memory, register) = match p {
+                    0 => ("(bc)".to_string(), "a"),
+                    1 => ("(de)".to_string(), "a"),
+                    2 => {
+                        let hl = self.hl();
+                        (format!("({})", self.nn()?), hl)
+                    }
+                    _ => (format!("({})", self.nn()?), "a"),
+                };
+                if q {
+                    op("ld", &format!("{register},{memory}"))
+                } else {
+                    op("ld", &format!("{memory},{register}"))
+                }
+            }
+            (0, 3) => op(if q { "dec" } else { "inc" }, self.rp(p, false)),
+            (0, 4) => op("inc", &self.r(y, true)?),
+            (0, 5) => op("dec", &self.r(y, true)?),
+            (0, 6) => {
+                let r = self.r(y, true)?;
+                op("ld", &format!("{r},{}", self.n()?))
+            }
+            (0, _) => op(ACCUMULATOR[usize::from(y)], ""),
+            (1, 6) if y == 6 => op("halt", ""),
+            (1, _) => {
+                // `h` and `l` are not replaced when the other operand is `(ix+d)`
+                let halves = y != 6 && z != 6;
+                let destination = self.r(y, halves)?;
+                op("ld", &format!("{destination},{}", self.r(z, halves)?))
+            }
+            (2, _) => alu(y, &self.r(z, true)?),
+            (_, 0) => op("ret", CONDITIONS[usize::from(y)]),
+            (_, 1) if !q => op("pop", self.rp(p, true)),
+            (_, 1) => match p {
+                0 => op("ret", ""),
+                1 => op("exx", ""),
+                2 => op("jp", &format!("({})", self.hl())),
+                _ => op("ld", &format!("sp,{}", self.hl())),
+            },
+            (_, 2) => op(
+                "jp",
+                &format!("{},{}", CONDITIONS[usize::from(y)], self.nn()?),
+            ),
+            (_, 3) => match y {
+                0 => op("jp", &self.nn()?),
+                2 => op("out", &format!("({}),a", self.n()?)),
+                3 => op("in", &format!("a,({})", self.n()?)),
+                4 => op("ex", &format!("(sp),{}", self.hl())),
+                5 => op("ex", "de,hl"),
+                6 => op("di", ""),
+                7 => op("ei", ""),
+                // prefixes
+                _ => return None,
+            },
+            (_, 4) => op(
+                "call",
+                &format!("{},{}", CONDITIONS[usize::from(y)], self.nn()?),
+            ),
+            (_, 5) if !q => op("push", self.rp(p, true)),
+            (_, 5) if p == 0 => op("call", &self.nn()?),
+            (_, 5) => return None,
+            (_, 6) => alu(y, &self.n()?),
+            _ => op("rst", &format!("${:02x}", y * 8)),
+        })
+    }
+
+    fn cb(&mut self) -> Option<String> {
+        let opcode = self.byte()?;
+        let x = opcode >> 6;
+        let y = opcode >> 3 & 7;
+        let z = opcode & 7;
+        let operand = match self.index {
+            Some(_) => {
+                let operand = self.r(6, false)?;
+                // undocumented forms also copy the result to a register
+                match (x, z) {
+                    (1, _) | (_, 6) => operand,
+                    _ => {
+                        self.index = None;
+                        format!("{operand},{}", self.r(z, false)?)
+                    }
+                }
+            }
+            None => self.r(z, false)?,
+        };
+        Some(match x {
+            0 => op(ROTATIONS[usize::from(y)], &operand),
+            1 => op("bit", &format!("{y},{operand}")),
+            2 => op("res", &format!("{y},{operand}")),
+            _ => op("set", &format!("{y},{operand}")),
+        })
+    }
+
+    fn ed(&mut self) -> Option<String> {
+        let opcode = self.byte()?;
+        let x = opcode >> 6;
+        let y = opcode >> 3 & 7;
+        let z = opcode & 7;
+        let p = y >> 1;
+        let q = y & 1 != 0;
+        Some(match (x, z) {
+            (1, 0) if y == 6 => op("in", "(c)"),
+            (1, 0) => op("in", &format!("{},(c)", self.r(y, false)?)),
+            (1, 1) if y == 6 => op("out", "(c),0"),
+            (1, 1) => op("out", &format!("(c),{}", self.r(y, false)?)),
+            (1, 2) => op(
+                if q { "adc" } else { "sbc" },
+                &format!("hl,{}", self.rp(p, false)),
+            ),
+            (1, 3) => {
+                let rp = self.rp(p, false);
+                let address = self.nn()?;
+                if q {
+                    op("ld", &format!("{rp},({address})"))
+                } else {
+                    op("ld", &format!("({address}),{rp}"))
+                }
+            }
+            (1, 4) => op("neg", ""),
+            (1, 5) => op(if y == 1 { "reti" } else { "retn" }, ""),
+            (1, 6) => op("im", ["0", "0", "1", "2"][usize::from(y & 3)]),
+            (1, 7) => match y {
+                0 => op("ld", "i,a"),
+                1 => op("ld", "r,a"),
+                2 => op("ld", "a,i"),
+                3 => op("ld", "a,r"),
+                4 => op("rrd", ""),
+                5 => op("rld", ""),
+                _ => return None,
+            },
+            (2, 0..=3) if y >= 4 => op(BLOCK[usize::from(y - 4)][usize::from(z)], ""),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn asm(code: &[u8]) -> String {
+        let instruction = Instruction::decode(code, 0, 0x100);
+        assert_eq!(code.len(), instruction.size(), "{}", instruction.text());
+        instruction.text().to_string()
+    }
+
+    #[test]
+    fn test_unprefixed() {
+        assert_eq!("nop", asm(&[0x00]));
+        assert_eq!("ex          af,af'", asm(&[0x08]));
+        assert_eq!("ld          hl,$1234", asm(&[0x21, 0x34, 0x12]));
+        assert_eq!("ld          ($4000),a", asm(&[0x32, 0x00, 0x40]));
+        assert_eq!("ld          hl,($4000)", asm(&[0x2A, 0x00, 0x40]));
+        assert_eq!("ld          b,(hl)", asm(&[0x46]));
+        assert_eq!("ld          a,$ff", asm(&[0x3E, 0xFF]));
+        assert_eq!("add         hl,de", asm(&[0x19]));
+        assert_eq!("add         a,b", asm(&[0x80]));
+        assert_eq!("sub         $10", asm(&[0xD6, 0x10]));
+        assert_eq!("xor         a", asm(&[0xAF]));
+        assert_eq!("halt", asm(&[0x76]));
+        assert_eq!("push        af", asm(&[0xF5]));
+        assert_eq!("jp          (hl)", asm(&[0xE9]));
+        assert_eq!("out         ($7f),a", asm(&[0xD3, 0x7F]));
+        assert_eq!("rst         $38", asm(&[0xFF]));
+        assert_eq!("call        nz,$0038", asm(&[0xC4, 0x38, 0x00]));
+    }
+
+    #[test]
+    fn test_relative() {
+        assert_eq!("jr          $0100", asm(&[0x18, 0xFE]));
+        assert_eq!("jr          nz,$0112", asm(&[0x20, 0x10]));
+        assert_eq!("djnz        $00f2", asm(&[0x10, 0xF0]));
+    }
+
+    #[test]
+    fn test_prefixed() {
+        assert_eq!("bit         7,a", asm(&[0xCB, 0x7F]));
+        assert_eq!("srl         (hl)", asm(&[0xCB, 0x3E]));
+        assert_eq!("ldir", asm(&[0xED, 0xB0]));
+        assert_eq!("im          1", asm(&[0xED, 0x56]));
+        assert_eq!("ld          ($c000),sp", asm(&[0xED, 0x73, 0x00, 0xC0]));
+        assert_eq!("sbc         hl,de", asm(&[0xED, 0x52]));
+        assert_eq!("ld          ix,$1234", asm(&[0xDD, 0x21, 0x34, 0x12]));
+        assert_eq!("ld          a,(ix+$05)", asm(&[0xDD, 0x7E, 0x05]));
+        assert_eq!("ld          (iy-$02),$11", asm(&[0xFD, 0x36, 0xFE, 0x11]));
+        assert_eq!("ld          h,(ix+$01)", asm(&[0xDD, 0x66, 0x01]));
+        assert_eq!("inc         ixh", asm(&[0xDD, 0x24]));
+        assert_eq!("bit         3,(ix+$04)", asm(&[0xDD, 0xCB, 0x04, 0x5E]));
+        assert_eq!("set         0,(iy-$01)", asm(&[0xFD, 0xCB, 0xFF, 0xC6]));
+        assert_eq!("rlc         (ix+$02),b", asm(&[0xDD, 0xCB, 0x02, 0x00]));
+        assert_eq!("ex          (sp),iy", asm(&[0xFD, 0xE3]));
+    }
+
+    #[test]
+    fn test_data() {
+        // prefixes without an effect, undefined opcodes, and truncated instructions
+        let data = |code: &[u8]| {
+            let instruction = Instruction::decode(code, 0, 0);
+            (instruction.text().to_string(), instruction.size())
+        };
+        assert_eq!(("db          $dd".into(), 1), data(&[0xDD, 0x00]));
+        assert_eq!(("db          $ed".into(), 1), data(&[0xED, 0x00]));
+        assert_eq!(("db          $21".into(), 1), data(&[0x21, 0x00]));
+    }
+
+    #[test]
+    fn test_symbols() {
+        // call _func
+        let code = [0xCD, 0, 0];
+        let instruction = Instruction::decode_with(&code, 0, 0, &mut |offset| {
+            (offset == 1).then(|| "_func".to_string())
+        });
+        assert_eq!("call        _func", instruction.text());
+        assert_eq!(3, instruction.size());
+    }
+}
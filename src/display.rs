@@ -22,6 +22,10 @@ pub struct Options {
     /// Whether or not to recurse into each module of a [LIB](super::LIB)
     pub recursive: bool,
 
+    /// Whether or not to name the symbols, sections, and groups in patch expressions, e.g.
+    /// `[14 InitGeom]` instead of `[14]`
+    pub symbolic: bool,
+
     /// Level to indent
     pub indent_level: u8,
 }
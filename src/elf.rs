@@ -0,0 +1,687 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! 32-bit little-endian ELF relocatable objects.
+//!
+//! [ELF] is a minimal model of an ELF object file: a list of sections with their headers and
+//! contents. It is enough to convert between [OBJ](crate::OBJ)s and the objects produced and
+//! consumed by GNU and LLVM tools for the PlayStation's MIPS R3000.
+//!
+//! ```
+//! use binrw::{BinRead, BinWrite};
+//! use binrw::io::Cursor;
+//! use psyk::elf::{self, ELF, ElfSection};
+//!
+//! let mut elf = ELF::new(elf::EM_MIPS, elf::EF_MIPS_ABI_O32);
+//! elf.add_section(ElfSection::new(
+//!     ".text",
+//!     elf::SHT_PROGBITS,
+//!     elf::SHF_ALLOC | elf::SHF_EXECINSTR,
+//!     4,
+//!     vec![0x08, 0x00, 0xE0, 0x03, 0x00, 0x00, 0x00, 0x00],
+//! ));
+//!
+//! let mut writer = Cursor::new(Vec::new());
+//! elf.write(&mut writer).unwrap();
+//! let elf = ELF::read(&mut Cursor::new(writer.into_inner())).unwrap();
+//! assert_eq!(8, elf.section_by_name(".text").unwrap().data().len());
+//! ```
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use anyhow::{anyhow, bail, Result};
+use binrw::meta::{EndianKind, ReadEndian, WriteEndian};
+use binrw::{binrw, BinRead, BinResult, BinWrite, Endian};
+
+mod dwarf;
+mod obj;
+
+/// Relocatable file.
+pub const ET_REL: u16 = 1;
+
+/// MIPS machine type.
+pub const EM_MIPS: u16 = 8;
+
+/// MIPS o32 ABI flag.
+pub const EF_MIPS_ABI_O32: u32 = 0x1000;
+
+/// Inactive section header.
+pub const SHT_NULL: u32 = 0;
+/// Program defined contents.
+pub const SHT_PROGBITS: u32 = 1;
+/// Symbol table.
+pub const SHT_SYMTAB: u32 = 2;
+/// String table.
+pub const SHT_STRTAB: u32 = 3;
+/// Relocations with explicit addends.
+pub const SHT_RELA: u32 = 4;
+/// Uninitialized contents.
+pub const SHT_NOBITS: u32 = 8;
+/// Relocations with addends stored in the section contents.
+pub const SHT_REL: u32 = 9;
+
+/// Writable section.
+pub const SHF_WRITE: u32 = 0x1;
+/// Section occupies memory at run time.
+pub const SHF_ALLOC: u32 = 0x2;
+/// Section contains instructions.
+pub const SHF_EXECINSTR: u32 = 0x4;
+/// Section is addressed relative to `$gp`.
+pub const SHF_MIPS_GPREL: u32 = 0x1000_0000;
+
+/// Undefined section index.
+pub const SHN_UNDEF: u16 = 0;
+/// Absolute symbol.
+pub const SHN_ABS: u16 = 0xFFF1;
+/// Common symbol.
+pub const SHN_COMMON: u16 = 0xFFF2;
+/// Small common symbol, allocated in `.sbss`.
+pub const SHN_MIPS_SCOMMON: u16 = 0xFF03;
+
+/// Local symbol binding.
+pub const STB_LOCAL: u8 = 0;
+/// Global symbol binding.
+pub const STB_GLOBAL: u8 = 1;
+/// Weak symbol binding.
+pub const STB_WEAK: u8 = 2;
+
+/// Symbol without a type.
+pub const STT_NOTYPE: u8 = 0;
+/// Data symbol.
+pub const STT_OBJECT: u8 = 1;
+/// Function symbol.
+pub const STT_FUNC: u8 = 2;
+/// Section symbol.
+pub const STT_SECTION: u8 = 3;
+/// Source file symbol.
+pub const STT_FILE: u8 = 4;
+
+/// No relocation.
+pub const R_MIPS_NONE: u8 = 0;
+/// 32-bit value.
+pub const R_MIPS_32: u8 = 2;
+/// 26-bit jump target.
+pub const R_MIPS_26: u8 = 4;
+/// High 16 bits of a value, paired with an [R_MIPS_LO16].
+pub const R_MIPS_HI16: u8 = 5;
+/// Low 16 bits of a value.
+pub const R_MIPS_LO16: u8 = 6;
+/// Signed 16-bit offset from `$gp`.
+pub const R_MIPS_GPREL16: u8 = 7;
+
+/// The ELF file header.
+#[binrw]
+#[brw(little, magic = b"\x7FELF")]
+#[derive(Clone, Debug, PartialEq)]
+struct FileHeader {
+    #[br(assert(class == 1, "only 32-bit ELF files are supported"))]
+    class: u8,
+    #[br(assert(data == 1, "only little-endian ELF files are supported"))]
+    data: u8,
+    ident_version: u8,
+    os_abi: u8,
+    abi_version: u8,
+    padding: [u8; 7],
+    file_type: u16,
+    machine: u16,
+    version: u32,
+    entry: u32,
+    phoff: u32,
+    shoff: u32,
+    flags: u32,
+    ehsize: u16,
+    phentsize: u16,
+    phnum: u16,
+    shentsize: u16,
+    shnum: u16,
+    shstrndx: u16,
+}
+
+impl FileHeader {
+    const SIZE: u32 = 52;
+}
+
+/// An ELF section header.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SectionHeader {
+    name: u32,
+    section_type: u32,
+    flags: u32,
+    addr: u32,
+    offset: u32,
+    size: u32,
+    link: u32,
+    info: u32,
+    addralign: u32,
+    entsize: u32,
+}
+
+impl SectionHeader {
+    const SIZE: u32 = 40;
+}
+
+/// An entry in a [SHT_SYMTAB] section.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Symbol {
+    name: u32,
+    value: u32,
+    size: u32,
+    info: u8,
+    other: u8,
+    section: u16,
+}
+
+impl Symbol {
+    /// Size of a symbol table entry.
+    pub const SIZE: u32 = 16;
+
+    /// Creates a symbol whose name is at offset `name` in the symbol string table.
+    pub fn new(name: u32, value: u32, size: u32, bind: u8, symbol_type: u8, section: u16) -> Self {
+        Self {
+            name,
+            value,
+            size,
+            info: (bind << 4) | (symbol_type & 0xF),
+            other: 0,
+            section,
+        }
+    }
+
+    /// Returns the offset of the symbol's name in the symbol string table.
+    pub fn name(&self) -> u32 {
+        self.name
+    }
+
+    /// Returns the value of the symbol. For defined symbols in relocatable objects this is the
+    /// offset from the start of its section, for common symbols it is the alignment.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the size of the symbol.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the symbol's binding, e.g. [STB_GLOBAL].
+    pub fn bind(&self) -> u8 {
+        self.info >> 4
+    }
+
+    /// Returns the symbol's type, e.g. [STT_FUNC].
+    pub fn symbol_type(&self) -> u8 {
+        self.info & 0xF
+    }
+
+    /// Returns the index of the section the symbol is defined in, or one of the special indexes
+    /// [SHN_UNDEF], [SHN_ABS], or [SHN_COMMON].
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+}
+
+/// An entry in a [SHT_REL] section.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rel {
+    offset: u32,
+    info: u32,
+}
+
+impl Rel {
+    /// Size of a relocation entry.
+    pub const SIZE: u32 = 8;
+
+    /// Creates a relocation of `rel_type` at `offset` against the symbol at index `symbol`.
+    pub fn new(offset: u32, symbol: u32, rel_type: u8) -> Self {
+        Self {
+            offset,
+            info: (symbol << 8) | u32::from(rel_type),
+        }
+    }
+
+    /// Returns the offset of the relocation in the section it applies to.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the index of the symbol the relocation refers to.
+    pub fn symbol(&self) -> u32 {
+        self.info >> 8
+    }
+
+    /// Returns the type of relocation, e.g. [R_MIPS_26].
+    pub fn rel_type(&self) -> u8 {
+        self.info as u8
+    }
+}
+
+/// A section of an [ELF] file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElfSection {
+    name: String,
+    header: SectionHeader,
+    data: Vec<u8>,
+}
+
+impl ElfSection {
+    /// Creates a section. The size of the section is the length of `data`.
+    pub fn new(name: &str, section_type: u32, flags: u32, align: u32, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            header: SectionHeader {
+                section_type,
+                flags,
+                addralign: align,
+                size: data.len() as u32,
+                ..Default::default()
+            },
+            data,
+        }
+    }
+
+    /// Creates a [SHT_NOBITS] section of `size` bytes.
+    pub fn uninitialized(name: &str, flags: u32, align: u32, size: u32) -> Self {
+        let mut section = Self::new(name, SHT_NOBITS, flags, align, Vec::new());
+        section.header.size = size;
+        section
+    }
+
+    /// Sets the section's `sh_link` and `sh_info` fields.
+    pub fn with_link(mut self, link: u32, info: u32) -> Self {
+        self.header.link = link;
+        self.header.info = info;
+        self
+    }
+
+    /// Sets the size of each entry of a table section.
+    pub fn with_entry_size(mut self, entsize: u32) -> Self {
+        self.header.entsize = entsize;
+        self
+    }
+
+    /// Returns the name of the section.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the section type, e.g. [SHT_PROGBITS].
+    pub fn section_type(&self) -> u32 {
+        self.header.section_type
+    }
+
+    /// Returns the section flags, e.g. [SHF_ALLOC].
+    pub fn flags(&self) -> u32 {
+        self.header.flags
+    }
+
+    /// Returns the required alignment of the section.
+    pub fn align(&self) -> u32 {
+        self.header.addralign
+    }
+
+    /// Returns the section's `sh_link` field.
+    pub fn link(&self) -> u32 {
+        self.header.link
+    }
+
+    /// Returns the section's `sh_info` field.
+    pub fn info(&self) -> u32 {
+        self.header.info
+    }
+
+    /// Returns the size of the section. Uninitialized sections have a size but no data.
+    pub fn size(&self) -> u32 {
+        self.header.size
+    }
+
+    /// Returns the contents of the section.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the symbols in a [SHT_SYMTAB] section.
+    pub fn symbols(&self) -> Result<Vec<Symbol>> {
+        if self.section_type() != SHT_SYMTAB {
+            bail!("{} is not a symbol table", self.name);
+        }
+        Ok(Vec::<Symbol>::read_le_args(
+            &mut binrw::io::Cursor::new(&self.data),
+            binrw::VecArgs::builder()
+                .count(self.data.len() / Symbol::SIZE as usize)
+                .finalize(),
+        )?)
+    }
+
+    /// Returns the relocations in a [SHT_REL] section.
+    pub fn relocations(&self) -> Result<Vec<Rel>> {
+        if self.section_type() != SHT_REL {
+            bail!("{} is not a relocation section", self.name);
+        }
+        Ok(Vec::<Rel>::read_le_args(
+            &mut binrw::io::Cursor::new(&self.data),
+            binrw::VecArgs::builder()
+                .count(self.data.len() / Rel::SIZE as usize)
+                .finalize(),
+        )?)
+    }
+
+    /// Returns the string starting at `offset` in a [SHT_STRTAB] section.
+    pub fn string(&self, offset: u32) -> Result<String> {
+        let bytes = self
+            .data
+            .get(offset as usize..)
+            .ok_or_else(|| anyhow!("string offset {offset:x} is outside of {}", self.name))?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// A 32-bit little-endian ELF relocatable object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ELF {
+    machine: u16,
+    flags: u32,
+    sections: Vec<ElfSection>,
+}
+
+impl ELF {
+    pub const MAGIC: [u8; 4] = *b"\x7FELF";
+
+    /// Creates an object with only the null section.
+    pub fn new(machine: u16, flags: u32) -> Self {
+        Self {
+            machine,
+            flags,
+            sections: vec![ElfSection::new("", SHT_NULL, 0, 0, Vec::new())],
+        }
+    }
+
+    /// Returns the machine type, e.g. [EM_MIPS].
+    pub fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    /// Returns the processor specific flags.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns the sections, indexed by their section number. The first section is always the
+    /// null section.
+    pub fn sections(&self) -> &[ElfSection] {
+        &self.sections
+    }
+
+    /// Returns the first section named `name`.
+    pub fn section_by_name(&self, name: &str) -> Option<&ElfSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    /// Appends a section and returns its index.
+    pub fn add_section(&mut self, section: ElfSection) -> u16 {
+        self.sections.push(section);
+        (self.sections.len() - 1) as u16
+    }
+
+    /// Returns the symbols of the object with their names.
+    pub fn symbols(&self) -> Result<Vec<(String, Symbol)>> {
+        let Some(symtab) = self
+            .sections
+            .iter()
+            .find(|s| s.section_type() == SHT_SYMTAB)
+        else {
+            return Ok(Vec::new());
+        };
+        let strtab = self
+            .sections
+            .get(symtab.link() as usize)
+            .ok_or_else(|| anyhow!("symbol table has no string table"))?;
+        symtab
+            .symbols()?
+            .into_iter()
+            .map(|symbol| Ok((strtab.string(symbol.name)?, symbol)))
+            .collect()
+    }
+}
+
+impl ReadEndian for ELF {
+    const ENDIAN: EndianKind = EndianKind::Endian(Endian::Little);
+}
+
+impl WriteEndian for ELF {
+    const ENDIAN: EndianKind = EndianKind::Endian(Endian::Little);
+}
+
+impl BinRead for ELF {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let start = reader.stream_position()?;
+        let header = FileHeader::read(reader)?;
+        if header.file_type != ET_REL {
+            return Err(binrw::Error::AssertFail {
+                pos: start + 16,
+                message: format!("ELF type {} is not a relocatable object", header.file_type),
+            });
+        }
+
+        reader.seek(SeekFrom::Start(start + u64::from(header.shoff)))?;
+        let mut headers = Vec::new();
+        for _ in 0..header.shnum {
+            headers.push(SectionHeader::read(reader)?);
+        }
+
+        let mut sections = Vec::new();
+        for header in headers {
+            let mut data = Vec::new();
+            if header.section_type != SHT_NOBITS && header.section_type != SHT_NULL {
+                reader.seek(SeekFrom::Start(start + u64::from(header.offset)))?;
+                data.resize(header.size as usize, 0);
+                reader.read_exact(&mut data)?;
+            }
+            sections.push(ElfSection {
+                name: String::new(),
+                header,
+                data,
+            });
+        }
+
+        if let Some(strtab) = sections.get(header.shstrndx as usize).cloned() {
+            for section in sections.iter_mut() {
+                section.name = strtab.string(section.header.name).unwrap_or_default();
+            }
+        }
+
+        Ok(Self {
+            machine: header.machine,
+            flags: header.flags,
+            sections,
+        })
+    }
+}
+
+fn align_to(offset: u32, align: u32) -> u32 {
+    offset.next_multiple_of(align.max(1))
+}
+
+impl BinWrite for ELF {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        let mut sections = self.sections.clone();
+        let shstrndx = match sections
+            .iter()
+            .position(|s| s.name == ".shstrtab" && s.section_type() == SHT_STRTAB)
+        {
+            Some(index) => index,
+            None => {
+                sections.push(ElfSection::new(".shstrtab", SHT_STRTAB, 0, 1, Vec::new()));
+                sections.len() - 1
+            }
+        };
+
+        let mut shstrtab = vec![0u8];
+        for section in sections.iter_mut() {
+            if section.name.is_empty() {
+                section.header.name = 0;
+                continue;
+            }
+            section.header.name = shstrtab.len() as u32;
+            shstrtab.extend_from_slice(section.name.as_bytes());
+            shstrtab.push(0);
+        }
+        sections[shstrndx].header.size = shstrtab.len() as u32;
+        sections[shstrndx].data = shstrtab;
+
+        let mut offset = FileHeader::SIZE;
+        for section in sections.iter_mut() {
+            if section.section_type() == SHT_NULL {
+                continue;
+            }
+            if section.section_type() != SHT_NOBITS {
+                section.header.size = section.data.len() as u32;
+            }
+            offset = align_to(offset, section.align());
+            section.header.offset = offset;
+            if section.section_type() != SHT_NOBITS {
+                offset += section.data.len() as u32;
+            }
+        }
+        let shoff = align_to(offset, 4);
+
+        let header = FileHeader {
+            class: 1,
+            data: 1,
+            ident_version: 1,
+            os_abi: 0,
+            abi_version: 0,
+            padding: [0; 7],
+            file_type: ET_REL,
+            machine: self.machine,
+            version: 1,
+            entry: 0,
+            phoff: 0,
+            shoff,
+            flags: self.flags,
+            ehsize: FileHeader::SIZE as u16,
+            phentsize: 0,
+            phnum: 0,
+            shentsize: SectionHeader::SIZE as u16,
+            shnum: sections.len() as u16,
+            shstrndx: shstrndx as u16,
+        };
+
+        let start = writer.stream_position()?;
+        let pad = |writer: &mut W, to: u32| -> BinResult<()> {
+            let position = (writer.stream_position()? - start) as u32;
+            writer.write_all(&vec![0; (to - position) as usize])?;
+            Ok(())
+        };
+
+        header.write(writer)?;
+        for section in &sections {
+            if section.section_type() == SHT_NULL || section.section_type() == SHT_NOBITS {
+                continue;
+            }
+            pad(writer, section.header.offset)?;
+            writer.write_all(&section.data)?;
+        }
+        pad(writer, shoff)?;
+        for section in &sections {
+            section.header.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use binrw::io::Cursor;
+
+    #[test]
+    fn test_round_trip() {
+        let mut elf = ELF::new(EM_MIPS, EF_MIPS_ABI_O32);
+        elf.add_section(ElfSection::new(
+            ".text",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            4,
+            vec![0; 8],
+        ));
+        elf.add_section(ElfSection::uninitialized(
+            ".bss",
+            SHF_ALLOC | SHF_WRITE,
+            8,
+            0x20,
+        ));
+        elf.add_section(ElfSection::new(
+            ".strtab",
+            SHT_STRTAB,
+            0,
+            1,
+            b"\0main\0".to_vec(),
+        ));
+        let mut symbols = Cursor::new(Vec::new());
+        Symbol::default().write(&mut symbols).unwrap();
+        Symbol::new(1, 0, 8, STB_GLOBAL, STT_FUNC, 1)
+            .write(&mut symbols)
+            .unwrap();
+        elf.add_section(
+            ElfSection::new(".symtab", SHT_SYMTAB, 0, 4, symbols.into_inner())
+                .with_link(3, 1)
+                .with_entry_size(Symbol::SIZE),
+        );
+
+        let mut writer = Cursor::new(Vec::new());
+        elf.write(&mut writer).unwrap();
+        let bytes = writer.into_inner();
+        assert!(bytes.starts_with(&ELF::MAGIC));
+
+        let read = ELF::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(EM_MIPS, read.machine());
+        assert_eq!(6, read.sections().len());
+        assert_eq!(".shstrtab", read.sections()[5].name());
+
+        let bss = read.section_by_name(".bss").unwrap();
+        assert_eq!(SHT_NOBITS, bss.section_type());
+        assert_eq!(0x20, bss.size());
+        assert!(bss.data().is_empty());
+
+        let symbols = read.symbols().unwrap();
+        assert_eq!(2, symbols.len());
+        assert_eq!("main", symbols[1].0);
+        assert_eq!(STB_GLOBAL, symbols[1].1.bind());
+        assert_eq!(STT_FUNC, symbols[1].1.symbol_type());
+        assert_eq!(8, symbols[1].1.size());
+
+        for (written, read) in elf.sections().iter().zip(read.sections()) {
+            assert_eq!(written.name(), read.name());
+            assert_eq!(written.data(), read.data());
+            assert_eq!(written.link(), read.link());
+        }
+    }
+
+    #[test]
+    fn test_rel() {
+        let rel = Rel::new(0x10, 3, R_MIPS_HI16);
+        assert_eq!(0x10, rel.offset());
+        assert_eq!(3, rel.symbol());
+        assert_eq!(R_MIPS_HI16, rel.rel_type());
+    }
+}
@@ -0,0 +1,921 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! DWARF 2 debug information for [OBJ]s converted to ELF.
+//!
+//! Psy-Q debug builds map code to source lines with line number records, describe types,
+//! variables, and functions with [Def](crate::Def) and [Def2](crate::Def2) records, and
+//! describe the stack frame of each function with a [FunctionStart]. These become the
+//! `.debug_line`, `.debug_info`, and `.debug_frame` sections read by GNU and LLVM tools.
+//!
+//! Addresses in the debug sections are relocated against the section they refer to, with the
+//! offset in the section stored in the contents.
+
+use std::collections::HashMap;
+
+use crate::model::{Function, Line, ObjectModel};
+use crate::types::{BaseType, Declarations, Derivation, StorageClass, TagKind, Type, Variable};
+use crate::{FunctionStart, SectionOffsetLine, OBJ};
+
+/// The section an address in a debug section is relative to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Base {
+    /// An OBJ section.
+    Section(u16),
+    /// Another debug section.
+    Debug(&'static str),
+}
+
+/// A debug section and the addresses in it which need relocations.
+pub(super) struct DebugSection {
+    pub(super) name: &'static str,
+    pub(super) data: Vec<u8>,
+    pub(super) relocations: Vec<(u32, Base)>,
+}
+
+const DEBUG_ABBREV: &str = ".debug_abbrev";
+const DEBUG_INFO: &str = ".debug_info";
+const DEBUG_LINE: &str = ".debug_line";
+const DEBUG_FRAME: &str = ".debug_frame";
+
+const DW_TAG_ARRAY_TYPE: u8 = 0x01;
+const DW_TAG_ENUMERATION_TYPE: u8 = 0x04;
+const DW_TAG_FORMAL_PARAMETER: u8 = 0x05;
+const DW_TAG_MEMBER: u8 = 0x0D;
+const DW_TAG_POINTER_TYPE: u8 = 0x0F;
+const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+const DW_TAG_STRUCTURE_TYPE: u8 = 0x13;
+const DW_TAG_SUBROUTINE_TYPE: u8 = 0x15;
+const DW_TAG_TYPEDEF: u8 = 0x16;
+const DW_TAG_UNION_TYPE: u8 = 0x17;
+const DW_TAG_SUBRANGE_TYPE: u8 = 0x21;
+const DW_TAG_BASE_TYPE: u8 = 0x24;
+const DW_TAG_ENUMERATOR: u8 = 0x28;
+const DW_TAG_SUBPROGRAM: u8 = 0x2E;
+const DW_TAG_VARIABLE: u8 = 0x34;
+
+const DW_AT_LOCATION: u8 = 0x02;
+const DW_AT_NAME: u8 = 0x03;
+const DW_AT_BYTE_SIZE: u8 = 0x0B;
+const DW_AT_BIT_OFFSET: u8 = 0x0C;
+const DW_AT_BIT_SIZE: u8 = 0x0D;
+const DW_AT_STMT_LIST: u8 = 0x10;
+const DW_AT_LOW_PC: u8 = 0x11;
+const DW_AT_HIGH_PC: u8 = 0x12;
+const DW_AT_LANGUAGE: u8 = 0x13;
+const DW_AT_CONST_VALUE: u8 = 0x1C;
+const DW_AT_PRODUCER: u8 = 0x25;
+const DW_AT_UPPER_BOUND: u8 = 0x2F;
+const DW_AT_DATA_MEMBER_LOCATION: u8 = 0x38;
+const DW_AT_ENCODING: u8 = 0x3E;
+const DW_AT_EXTERNAL: u8 = 0x3F;
+const DW_AT_FRAME_BASE: u8 = 0x40;
+const DW_AT_TYPE: u8 = 0x49;
+
+const DW_FORM_ADDR: u8 = 0x01;
+const DW_FORM_DATA2: u8 = 0x05;
+const DW_FORM_DATA4: u8 = 0x06;
+const DW_FORM_STRING: u8 = 0x08;
+const DW_FORM_BLOCK1: u8 = 0x0A;
+const DW_FORM_DATA1: u8 = 0x0B;
+const DW_FORM_FLAG: u8 = 0x0C;
+const DW_FORM_SDATA: u8 = 0x0D;
+const DW_FORM_UDATA: u8 = 0x0F;
+const DW_FORM_REF4: u8 = 0x13;
+
+const DW_ATE_FLOAT: u8 = 0x04;
+const DW_ATE_SIGNED: u8 = 0x05;
+const DW_ATE_SIGNED_CHAR: u8 = 0x06;
+const DW_ATE_UNSIGNED: u8 = 0x07;
+const DW_ATE_UNSIGNED_CHAR: u8 = 0x08;
+
+const DW_LANG_C89: u16 = 0x0001;
+
+const DW_OP_ADDR: u8 = 0x03;
+const DW_OP_PLUS_UCONST: u8 = 0x23;
+const DW_OP_REG0: u8 = 0x50;
+const DW_OP_BREG0: u8 = 0x70;
+const DW_OP_FBREG: u8 = 0x91;
+
+const DW_LNS_COPY: u8 = 1;
+const DW_LNS_ADVANCE_PC: u8 = 2;
+const DW_LNS_ADVANCE_LINE: u8 = 3;
+const DW_LNS_SET_FILE: u8 = 4;
+const DW_LNE_END_SEQUENCE: u8 = 1;
+const DW_LNE_SET_ADDRESS: u8 = 2;
+
+const DW_CFA_DEF_CFA: u8 = 0x0C;
+const DW_CFA_OFFSET: u8 = 0x80;
+
+/// The stack pointer, `$sp`.
+const SP: u8 = 29;
+/// The return address register, `$ra`.
+const RA: u8 = 31;
+
+// abbreviation codes, one more than the index in ABBREVIATIONS
+const COMPILE_UNIT: u8 = 1;
+const COMPILE_UNIT_LINES: u8 = 2;
+const BASE: u8 = 3;
+const POINTER: u8 = 4;
+const VOID_POINTER: u8 = 5;
+const SUBROUTINE: u8 = 6;
+const VOID_SUBROUTINE: u8 = 7;
+const ARRAY: u8 = 8;
+const SUBRANGE: u8 = 9;
+const UNBOUNDED_SUBRANGE: u8 = 10;
+const STRUCTURE: u8 = 11;
+const UNION: u8 = 12;
+const ENUMERATION: u8 = 13;
+const MEMBER: u8 = 14;
+const BIT_FIELD: u8 = 15;
+const ENUMERATOR: u8 = 16;
+const TYPEDEF: u8 = 17;
+const VOID_TYPEDEF: u8 = 18;
+const VARIABLE: u8 = 19;
+const SUBPROGRAM: u8 = 20;
+const VOID_SUBPROGRAM: u8 = 21;
+const PARAMETER: u8 = 22;
+
+type Abbreviation = (u8, bool, &'static [(u8, u8)]);
+
+const ABBREVIATIONS: &[Abbreviation] = &[
+    (
+        DW_TAG_COMPILE_UNIT,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_PRODUCER, DW_FORM_STRING),
+            (DW_AT_LANGUAGE, DW_FORM_DATA2),
+        ],
+    ),
+    (
+        DW_TAG_COMPILE_UNIT,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_PRODUCER, DW_FORM_STRING),
+            (DW_AT_LANGUAGE, DW_FORM_DATA2),
+            (DW_AT_STMT_LIST, DW_FORM_DATA4),
+            (DW_AT_LOW_PC, DW_FORM_ADDR),
+            (DW_AT_HIGH_PC, DW_FORM_ADDR),
+        ],
+    ),
+    (
+        DW_TAG_BASE_TYPE,
+        false,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_ENCODING, DW_FORM_DATA1),
+            (DW_AT_BYTE_SIZE, DW_FORM_DATA1),
+        ],
+    ),
+    (
+        DW_TAG_POINTER_TYPE,
+        false,
+        &[(DW_AT_BYTE_SIZE, DW_FORM_DATA1), (DW_AT_TYPE, DW_FORM_REF4)],
+    ),
+    (
+        DW_TAG_POINTER_TYPE,
+        false,
+        &[(DW_AT_BYTE_SIZE, DW_FORM_DATA1)],
+    ),
+    (DW_TAG_SUBROUTINE_TYPE, false, &[(DW_AT_TYPE, DW_FORM_REF4)]),
+    (DW_TAG_SUBROUTINE_TYPE, false, &[]),
+    (DW_TAG_ARRAY_TYPE, true, &[(DW_AT_TYPE, DW_FORM_REF4)]),
+    (
+        DW_TAG_SUBRANGE_TYPE,
+        false,
+        &[(DW_AT_UPPER_BOUND, DW_FORM_UDATA)],
+    ),
+    (DW_TAG_SUBRANGE_TYPE, false, &[]),
+    (
+        DW_TAG_STRUCTURE_TYPE,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_BYTE_SIZE, DW_FORM_UDATA),
+        ],
+    ),
+    (
+        DW_TAG_UNION_TYPE,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_BYTE_SIZE, DW_FORM_UDATA),
+        ],
+    ),
+    (
+        DW_TAG_ENUMERATION_TYPE,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_BYTE_SIZE, DW_FORM_UDATA),
+        ],
+    ),
+    (
+        DW_TAG_MEMBER,
+        false,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_TYPE, DW_FORM_REF4),
+            (DW_AT_DATA_MEMBER_LOCATION, DW_FORM_BLOCK1),
+        ],
+    ),
+    (
+        DW_TAG_MEMBER,
+        false,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_TYPE, DW_FORM_REF4),
+            (DW_AT_BYTE_SIZE, DW_FORM_DATA1),
+            (DW_AT_BIT_SIZE, DW_FORM_DATA1),
+            (DW_AT_BIT_OFFSET, DW_FORM_DATA1),
+            (DW_AT_DATA_MEMBER_LOCATION, DW_FORM_BLOCK1),
+        ],
+    ),
+    (
+        DW_TAG_ENUMERATOR,
+        false,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_CONST_VALUE, DW_FORM_SDATA),
+        ],
+    ),
+    (
+        DW_TAG_TYPEDEF,
+        false,
+        &[(DW_AT_NAME, DW_FORM_STRING), (DW_AT_TYPE, DW_FORM_REF4)],
+    ),
+    (DW_TAG_TYPEDEF, false, &[(DW_AT_NAME, DW_FORM_STRING)]),
+    (
+        DW_TAG_VARIABLE,
+        false,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_TYPE, DW_FORM_REF4),
+            (DW_AT_EXTERNAL, DW_FORM_FLAG),
+            (DW_AT_LOCATION, DW_FORM_BLOCK1),
+        ],
+    ),
+    (
+        DW_TAG_SUBPROGRAM,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_EXTERNAL, DW_FORM_FLAG),
+            (DW_AT_TYPE, DW_FORM_REF4),
+            (DW_AT_LOW_PC, DW_FORM_ADDR),
+            (DW_AT_HIGH_PC, DW_FORM_ADDR),
+            (DW_AT_FRAME_BASE, DW_FORM_BLOCK1),
+        ],
+    ),
+    (
+        DW_TAG_SUBPROGRAM,
+        true,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_EXTERNAL, DW_FORM_FLAG),
+            (DW_AT_LOW_PC, DW_FORM_ADDR),
+            (DW_AT_HIGH_PC, DW_FORM_ADDR),
+            (DW_AT_FRAME_BASE, DW_FORM_BLOCK1),
+        ],
+    ),
+    (
+        DW_TAG_FORMAL_PARAMETER,
+        false,
+        &[
+            (DW_AT_NAME, DW_FORM_STRING),
+            (DW_AT_TYPE, DW_FORM_REF4),
+            (DW_AT_LOCATION, DW_FORM_BLOCK1),
+        ],
+    ),
+];
+
+/// Writes the contents of a debug section.
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+    relocations: Vec<(u32, Base)>,
+    // offset -> index of the type DIE referred to
+    references: Vec<(usize, usize)>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn uleb128(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    fn sleb128(&mut self, mut value: i64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.data.extend_from_slice(value.as_bytes());
+        self.u8(0);
+    }
+
+    /// Writes an address `offset` bytes into `base`.
+    fn address(&mut self, base: Base, offset: u32) {
+        self.relocations.push((self.data.len() as u32, base));
+        self.u32(offset);
+    }
+
+    /// Writes a reference to the type DIE `index`, filled in by [Info::finish].
+    fn reference(&mut self, index: usize) {
+        self.references.push((self.data.len(), index));
+        self.u32(0);
+    }
+
+    /// Writes `block` prefixed with its size.
+    fn block(&mut self, block: Writer) {
+        self.u8(block.data.len() as u8);
+        self.append(block);
+    }
+
+    fn append(&mut self, other: Writer) {
+        let start = self.data.len();
+        self.relocations.extend(
+            other
+                .relocations
+                .into_iter()
+                .map(|(offset, base)| (offset + start as u32, base)),
+        );
+        self.references.extend(
+            other
+                .references
+                .into_iter()
+                .map(|(offset, index)| (offset + start, index)),
+        );
+        self.data.extend(other.data);
+    }
+
+    /// Pads the contents with zeros to a multiple of `align` bytes.
+    fn align(&mut self, align: usize) {
+        while !self.data.len().is_multiple_of(align) {
+            self.u8(0);
+        }
+    }
+
+    fn section(self, name: &'static str) -> DebugSection {
+        DebugSection {
+            name,
+            data: self.data,
+            relocations: self.relocations,
+        }
+    }
+}
+
+/// Returns the DWARF debug sections for the debug records of `obj`, or none if it has none.
+pub(super) fn debug_sections(obj: &OBJ, model: &ObjectModel) -> Vec<DebugSection> {
+    let declarations = Declarations::new(obj);
+    let lines = line_program(model);
+    if lines.is_none() && declarations.is_empty() && model.debug_functions().is_empty() {
+        return Vec::new();
+    }
+
+    let mut abbrev = Writer::default();
+    for (code, (tag, children, attributes)) in ABBREVIATIONS.iter().enumerate() {
+        abbrev.uleb128(code as u64 + 1);
+        abbrev.uleb128(u64::from(*tag));
+        abbrev.u8(u8::from(*children));
+        for (attribute, form) in *attributes {
+            abbrev.uleb128(u64::from(*attribute));
+            abbrev.uleb128(u64::from(*form));
+        }
+        abbrev.u16(0);
+    }
+    abbrev.u8(0);
+
+    let mut info = Info::new(model, &declarations);
+    let mut sections = vec![
+        abbrev.section(DEBUG_ABBREV),
+        info.compile_unit(lines.is_some()).section(DEBUG_INFO),
+    ];
+    sections.extend(lines);
+    if !model.debug_functions().is_empty() {
+        sections.push(frames(model));
+    }
+    sections
+}
+
+/// Builds the `.debug_info` compilation unit.
+struct Info<'a> {
+    model: &'a ObjectModel,
+    declarations: &'a Declarations,
+    functions: Vec<Function>,
+    // type DIEs, written after the other DIEs of the unit
+    types: Vec<Writer>,
+    type_index: HashMap<Type, usize>,
+    tag_index: HashMap<String, usize>,
+}
+
+impl<'a> Info<'a> {
+    fn new(model: &'a ObjectModel, declarations: &'a Declarations) -> Self {
+        Self {
+            model,
+            declarations,
+            functions: model.functions(),
+            types: Vec::new(),
+            type_index: HashMap::new(),
+            tag_index: HashMap::new(),
+        }
+    }
+
+    fn compile_unit(&mut self, lines: bool) -> Writer {
+        let mut unit = Writer::default();
+        let name = self
+            .model
+            .lines()
+            .files()
+            .values()
+            .next()
+            .cloned()
+            .unwrap_or_default();
+        unit.u8(if lines {
+            COMPILE_UNIT_LINES
+        } else {
+            COMPILE_UNIT
+        });
+        unit.string(&name);
+        unit.string(concat!("psyk ", env!("CARGO_PKG_VERSION")));
+        unit.u16(DW_LANG_C89);
+        if lines {
+            unit.address(Base::Debug(DEBUG_LINE), 0);
+            // DWARF 2 units cover a single range, the section with the first line
+            let section = self.model.lines().lines()[0].section();
+            let size = self.model.section(section).map_or(0, |s| s.size());
+            unit.address(Base::Section(section), 0);
+            unit.address(Base::Section(section), size);
+        }
+
+        for tag in self.declarations.tags() {
+            self.tag(tag.name());
+        }
+        for typedef in self.declarations.typedefs() {
+            match self.type_of(typedef.ty()) {
+                Some(index) => {
+                    unit.u8(TYPEDEF);
+                    unit.string(typedef.name());
+                    unit.reference(index);
+                }
+                None => {
+                    unit.u8(VOID_TYPEDEF);
+                    unit.string(typedef.name());
+                }
+            }
+        }
+        for variable in self.declarations.variables() {
+            let external = variable.class() != StorageClass::Static;
+            self.variable(&mut unit, VARIABLE, variable, Some(external), None);
+        }
+        for (start, end) in self.model.debug_functions() {
+            self.subprogram(&mut unit, start, end.as_ref());
+        }
+
+        // the offset of the first type DIE from the start of the unit header
+        let mut offset = 11 + unit.data.len();
+        let mut offsets = Vec::new();
+        for die in &self.types {
+            offsets.push(offset as u32);
+            offset += die.data.len();
+        }
+        for die in std::mem::take(&mut self.types) {
+            unit.append(die);
+        }
+        unit.u8(0);
+        for (at, index) in std::mem::take(&mut unit.references) {
+            unit.data[at..at + 4].copy_from_slice(&offsets[index].to_le_bytes());
+        }
+
+        let mut header = Writer::default();
+        header.u32(7 + unit.data.len() as u32);
+        header.u16(2);
+        header.address(Base::Debug(DEBUG_ABBREV), 0);
+        header.u8(4);
+        header.append(unit);
+        header
+    }
+
+    fn subprogram(
+        &mut self,
+        unit: &mut Writer,
+        start: &FunctionStart,
+        end: Option<&SectionOffsetLine>,
+    ) {
+        let name = start.name();
+        let size = function_size(&self.functions, start, end);
+        let function = self
+            .declarations
+            .functions()
+            .iter()
+            .find(|f| f.name() == name);
+        let return_type = function.and_then(|f| self.type_of(&f.return_type()));
+
+        unit.u8(if return_type.is_some() {
+            SUBPROGRAM
+        } else {
+            VOID_SUBPROGRAM
+        });
+        unit.string(&name);
+        unit.u8(u8::from(
+            function.is_none_or(|f| f.class() != StorageClass::Static),
+        ));
+        if let Some(index) = return_type {
+            unit.reference(index);
+        }
+        let section = Base::Section(start.section());
+        unit.address(section, start.offset());
+        unit.address(section, start.offset() + size);
+        let mut frame_base = Writer::default();
+        if let Ok(register) = u8::try_from(start.frame_register()) {
+            if register < 32 {
+                frame_base.u8(DW_OP_BREG0 + register);
+                frame_base.sleb128(i64::from(start.frame_size()));
+            }
+        }
+        unit.block(frame_base);
+
+        if let Some(function) = function {
+            for parameter in function.parameters() {
+                self.variable(unit, PARAMETER, parameter, None, Some(start));
+            }
+            for local in function.locals() {
+                self.variable(unit, VARIABLE, local, Some(false), Some(start));
+            }
+        }
+        unit.u8(0);
+    }
+
+    /// Writes a variable or parameter DIE. Variables of unknown type are skipped.
+    fn variable(
+        &mut self,
+        unit: &mut Writer,
+        code: u8,
+        variable: &Variable,
+        external: Option<bool>,
+        function: Option<&FunctionStart>,
+    ) {
+        let Some(index) = self.type_of(variable.ty()) else {
+            return;
+        };
+        unit.u8(code);
+        unit.string(variable.name());
+        unit.reference(index);
+        if let Some(external) = external {
+            unit.u8(u8::from(external));
+        }
+
+        let mut location = Writer::default();
+        match variable.class() {
+            StorageClass::Automatic | StorageClass::Argument if function.is_some() => {
+                location.u8(DW_OP_FBREG);
+                location.sleb128(i64::from(variable.value() as i32));
+            }
+            StorageClass::Register | StorageClass::RegisterParameter if variable.value() < 32 => {
+                location.u8(DW_OP_REG0 + variable.value() as u8);
+            }
+            StorageClass::External | StorageClass::Static | StorageClass::ExternalDefinition
+                if self.model.section(variable.section()).is_some() =>
+            {
+                location.u8(DW_OP_ADDR);
+                location.address(Base::Section(variable.section()), variable.value());
+            }
+            _ => (),
+        }
+        unit.block(location);
+    }
+
+    /// Returns the index of the type DIE for `ty`, or `None` for `void`.
+    fn type_of(&mut self, ty: &Type) -> Option<usize> {
+        if ty.derivations().is_empty() {
+            match ty.base() {
+                BaseType::Null | BaseType::Void => return None,
+                BaseType::Struct | BaseType::Union | BaseType::Enum => {
+                    return Some(self.tag(ty.tag().unwrap_or_default()));
+                }
+                _ => (),
+            }
+        }
+        if let Some(index) = self.type_index.get(ty) {
+            return Some(*index);
+        }
+        let index = self.reserve();
+        self.type_index.insert(ty.clone(), index);
+
+        let mut die = Writer::default();
+        let target = ty.target().and_then(|target| self.type_of(&target));
+        match ty.derivations().first() {
+            None => {
+                let (name, encoding, size) = match ty.base() {
+                    BaseType::Char => ("char", DW_ATE_SIGNED_CHAR, 1),
+                    BaseType::Short => ("short", DW_ATE_SIGNED, 2),
+                    BaseType::Long => ("long", DW_ATE_SIGNED, 4),
+                    BaseType::Float => ("float", DW_ATE_FLOAT, 4),
+                    BaseType::Double => ("double", DW_ATE_FLOAT, 8),
+                    BaseType::UnsignedChar => ("unsigned char", DW_ATE_UNSIGNED_CHAR, 1),
+                    BaseType::UnsignedShort => ("unsigned short", DW_ATE_UNSIGNED, 2),
+                    BaseType::UnsignedInt => ("unsigned int", DW_ATE_UNSIGNED, 4),
+                    BaseType::UnsignedLong => ("unsigned long", DW_ATE_UNSIGNED, 4),
+                    _ => ("int", DW_ATE_SIGNED, 4),
+                };
+                die.u8(BASE);
+                die.string(name);
+                die.u8(encoding);
+                die.u8(size);
+            }
+            Some(Derivation::Pointer) => match target {
+                Some(target) => {
+                    die.u8(POINTER);
+                    die.u8(4);
+                    die.reference(target);
+                }
+                None => {
+                    die.u8(VOID_POINTER);
+                    die.u8(4);
+                }
+            },
+            Some(Derivation::Function) => match target {
+                Some(target) => {
+                    die.u8(SUBROUTINE);
+                    die.reference(target);
+                }
+                None => die.u8(VOID_SUBROUTINE),
+            },
+            Some(Derivation::Array(count)) => {
+                die.u8(ARRAY);
+                // arrays of void are not valid C, describe them as bytes
+                let target = target.unwrap_or_else(|| {
+                    self.type_of(&Type::decode(0xC, &[], None))
+                        .expect("unsigned char")
+                });
+                die.reference(target);
+                match count {
+                    0 => die.u8(UNBOUNDED_SUBRANGE),
+                    count => {
+                        die.u8(SUBRANGE);
+                        die.uleb128(u64::from(count - 1));
+                    }
+                }
+                die.u8(0);
+            }
+        }
+        self.types[index] = die;
+        Some(index)
+    }
+
+    /// Returns the index of the type DIE for the structure, union, or enumeration `name`.
+    fn tag(&mut self, name: &str) -> usize {
+        if let Some(index) = self.tag_index.get(name) {
+            return *index;
+        }
+        let index = self.reserve();
+        self.tag_index.insert(name.into(), index);
+
+        let mut die = Writer::default();
+        let Some(tag) = self.declarations.tags().iter().find(|t| t.name() == name) else {
+            // an incomplete type
+            die.u8(STRUCTURE);
+            die.string(name);
+            die.uleb128(0);
+            die.u8(0);
+            self.types[index] = die;
+            return index;
+        };
+        die.u8(match tag.kind() {
+            TagKind::Struct => STRUCTURE,
+            TagKind::Union => UNION,
+            TagKind::Enum => ENUMERATION,
+        });
+        die.string(name);
+        die.uleb128(u64::from(tag.size()));
+        for member in tag.members() {
+            if member.class() == StorageClass::EnumMember {
+                die.u8(ENUMERATOR);
+                die.string(member.name());
+                die.sleb128(i64::from(member.value() as i32));
+                continue;
+            }
+            let Some(ty) = self.type_of(member.ty()) else {
+                continue;
+            };
+            let mut location = Writer::default();
+            location.u8(DW_OP_PLUS_UCONST);
+            if member.class() == StorageClass::BitField {
+                // bit offsets count from the most significant bit of the containing word
+                let bit = member.value() % 32;
+                die.u8(BIT_FIELD);
+                die.string(member.name());
+                die.reference(ty);
+                die.u8(4);
+                die.u8(member.size() as u8);
+                die.u8(32u32.saturating_sub(bit + member.size()) as u8);
+                location.uleb128(u64::from(member.value() / 32 * 4));
+            } else {
+                die.u8(MEMBER);
+                die.string(member.name());
+                die.reference(ty);
+                location.uleb128(u64::from(member.value()));
+            }
+            die.block(location);
+        }
+        die.u8(0);
+        self.types[index] = die;
+        index
+    }
+
+    fn reserve(&mut self) -> usize {
+        self.types.push(Writer::default());
+        self.types.len() - 1
+    }
+}
+
+/// Returns the size of the function starting at `start` and ending at `end`.
+fn function_size(
+    functions: &[Function],
+    start: &FunctionStart,
+    end: Option<&SectionOffsetLine>,
+) -> u32 {
+    functions
+        .iter()
+        .find(|f| f.section() == start.section() && f.offset() == start.offset())
+        .map(|f| f.size())
+        .or_else(|| end.map(|end| end.offset().saturating_sub(start.offset())))
+        .unwrap_or(0)
+}
+
+/// Returns the `.debug_line` line number program for the source lines of the object, or
+/// `None` if it has none.
+fn line_program(model: &ObjectModel) -> Option<DebugSection> {
+    let table = model.lines();
+    if table.lines().is_empty() {
+        return None;
+    }
+
+    // file numbers start at 1
+    let files: HashMap<u16, u64> = table
+        .files()
+        .keys()
+        .enumerate()
+        .map(|(i, number)| (*number, i as u64 + 1))
+        .collect();
+    let mut header = Writer::default();
+    header.u8(1); // minimum instruction length
+    header.u8(1); // default is_stmt
+    header.u8(-5i8 as u8); // line base
+    header.u8(14); // line range
+    header.u8(10); // opcode base
+    header.data.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1]);
+    header.u8(0); // no include directories
+    if table.files().is_empty() {
+        header.string("<unknown>");
+        header.data.extend_from_slice(&[0, 0, 0]);
+    }
+    for name in table.files().values() {
+        header.string(name);
+        header.data.extend_from_slice(&[0, 0, 0]);
+    }
+    header.u8(0);
+
+    let mut program = Writer::default();
+    let mut rows = table.lines().iter().peekable();
+    while let Some(first) = rows.peek() {
+        let section = first.section();
+        let mut sequence: Vec<&Line> = Vec::new();
+        while let Some(row) = rows.next_if(|row| row.section() == section) {
+            // only the last line at an offset applies
+            if sequence
+                .last()
+                .is_some_and(|last| last.offset() == row.offset())
+            {
+                sequence.pop();
+            }
+            sequence.push(row);
+        }
+        let Some(size) = model.section(section).map(|s| s.size()) else {
+            continue;
+        };
+
+        let mut address = sequence[0].offset();
+        let mut file = 1;
+        let mut line = 1;
+        program.u8(0);
+        program.u8(5);
+        program.u8(DW_LNE_SET_ADDRESS);
+        program.address(Base::Section(section), address);
+        for row in sequence {
+            let row_file = row.file().and_then(|f| files.get(&f)).copied().unwrap_or(1);
+            if row_file != file {
+                program.u8(DW_LNS_SET_FILE);
+                program.uleb128(row_file);
+                file = row_file;
+            }
+            if row.offset() > address {
+                program.u8(DW_LNS_ADVANCE_PC);
+                program.uleb128(u64::from(row.offset() - address));
+                address = row.offset();
+            }
+            if i64::from(row.line()) != line {
+                program.u8(DW_LNS_ADVANCE_LINE);
+                program.sleb128(i64::from(row.line()) - line);
+                line = i64::from(row.line());
+            }
+            program.u8(DW_LNS_COPY);
+        }
+        if size > address {
+            program.u8(DW_LNS_ADVANCE_PC);
+            program.uleb128(u64::from(size - address));
+        }
+        program.u8(0);
+        program.u8(1);
+        program.u8(DW_LNE_END_SEQUENCE);
+    }
+
+    let mut section = Writer::default();
+    section.u32((2 + 4 + header.data.len() + program.data.len()) as u32);
+    section.u16(2);
+    section.u32(header.data.len() as u32);
+    section.append(header);
+    section.append(program);
+    Some(section.section(DEBUG_LINE))
+}
+
+/// Returns the `.debug_frame` call frame information for the functions of the object.
+///
+/// The rules describe each function after its prologue: the canonical frame address is the
+/// frame register plus the frame size, and the registers in the mask are saved from the
+/// highest numbered down, starting at the mask offset from the frame address.
+fn frames(model: &ObjectModel) -> DebugSection {
+    let mut frame = Writer::default();
+
+    // common information entry
+    let mut cie = Writer::default();
+    cie.u32(0xFFFF_FFFF); // CIE id
+    cie.u8(1); // version
+    cie.string(""); // augmentation
+    cie.uleb128(1); // code alignment
+    cie.sleb128(-4); // data alignment
+    cie.u8(RA);
+    cie.u8(DW_CFA_DEF_CFA);
+    cie.uleb128(u64::from(SP));
+    cie.uleb128(0);
+    cie.align(4);
+    frame.u32(cie.data.len() as u32);
+    frame.append(cie);
+
+    let functions = model.functions();
+    for (start, end) in model.debug_functions() {
+        let size = function_size(&functions, start, end.as_ref());
+
+        let mut fde = Writer::default();
+        fde.address(Base::Debug(DEBUG_FRAME), 0);
+        fde.address(Base::Section(start.section()), start.offset());
+        fde.u32(size);
+        fde.u8(DW_CFA_DEF_CFA);
+        fde.uleb128(u64::from(start.frame_register()));
+        fde.uleb128(u64::from(start.frame_size()));
+        let mut offset = i64::from(start.mask_offset());
+        for register in (0..32u8).rev() {
+            if start.mask() & (1 << register) == 0 {
+                continue;
+            }
+            // saved registers are below the frame address
+            if offset < 0 && offset % 4 == 0 {
+                fde.u8(DW_CFA_OFFSET | register);
+                fde.uleb128((-offset / 4) as u64);
+            }
+            offset -= 4;
+        }
+        fde.align(4);
+        frame.u32(fde.data.len() as u32);
+        frame.append(fde);
+    }
+    frame.section(DEBUG_FRAME)
+}
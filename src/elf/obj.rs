@@ -0,0 +1,1060 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Conversion between [OBJ]s and [ELF] objects.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use binrw::io::Cursor;
+use binrw::BinWrite;
+
+use super::dwarf::{self, Base};
+use super::*;
+use crate::model::{ObjectModel, ObjectSection, Relocation, Symbol as ModelSymbol};
+use crate::patch::mips;
+use crate::{
+    cputype, Code, Expression, LNKHeader, LocalSymbol, Patch, Section, OBJ, XBSS, XDEF, XREF,
+};
+
+/// Returns the ELF section flags for a PSY-Q section name.
+fn section_flags(name: &str, uninitialized: bool) -> u32 {
+    let gp = if name.starts_with(".sdata") || name.starts_with(".sbss") {
+        SHF_MIPS_GPREL
+    } else {
+        0
+    };
+    if name.starts_with(".text") {
+        SHF_ALLOC | SHF_EXECINSTR
+    } else if name.starts_with(".rdata") || name.starts_with(".rodata") {
+        SHF_ALLOC
+    } else if uninitialized || name.starts_with(".data") || name.starts_with(".sdata") {
+        SHF_ALLOC | SHF_WRITE | gp
+    } else {
+        SHF_ALLOC | SHF_WRITE | SHF_EXECINSTR
+    }
+}
+
+/// Returns the ELF relocation type for a MIPS patch tag.
+fn relocation_type(tag: u8) -> Result<u8> {
+    Ok(match tag {
+        mips::WORD => R_MIPS_32,
+        mips::JUMP => R_MIPS_26,
+        mips::HI16 => R_MIPS_HI16,
+        mips::LO16 => R_MIPS_LO16,
+        mips::GP_RELATIVE => R_MIPS_GPREL16,
+        _ => bail!("patch type {tag} has no ELF relocation"),
+    })
+}
+
+/// Builds a string table.
+#[derive(Default)]
+struct StringTable {
+    data: Vec<u8>,
+}
+
+impl StringTable {
+    fn add(&mut self, name: &str) -> u32 {
+        if self.data.is_empty() {
+            self.data.push(0);
+        }
+        if name.is_empty() {
+            return 0;
+        }
+        let offset = self.data.len() as u32;
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.push(0);
+        offset
+    }
+}
+
+/// Symbol table indexes of the sections and numbered symbols of an object.
+struct SymbolIndex {
+    sections: HashMap<u16, u32>,
+    symbols: HashMap<u16, u32>,
+}
+
+impl SymbolIndex {
+    fn get(&self, number: u16) -> Result<u32> {
+        self.symbols
+            .get(&number)
+            .or_else(|| self.sections.get(&number))
+            .copied()
+            .ok_or_else(|| anyhow!("unknown symbol {number:x}"))
+    }
+
+    fn section(&self, number: u16) -> Result<u32> {
+        self.sections
+            .get(&number)
+            .copied()
+            .ok_or_else(|| anyhow!("unknown section {number:x}"))
+    }
+
+    /// Returns the symbol `relocation` refers to and the addend.
+    fn target(&self, relocation: &Relocation) -> Result<(u32, i64)> {
+        Ok(match relocation.address() {
+            Some((Expression::SymbolAddressIndex(number), addend)) => (self.get(*number)?, addend),
+            Some((Expression::SectionAddressIndex(number), addend)) => {
+                (self.section(*number)?, addend)
+            }
+            _ => bail!(
+                "{} cannot be converted to an ELF relocation",
+                relocation.expression()
+            ),
+        })
+    }
+}
+
+/// Returns the size of each definition, the distance to the next definition in the same
+/// section or to the end of the section.
+fn definition_sizes(model: &ObjectModel) -> HashMap<u16, u32> {
+    let mut sizes = HashMap::new();
+    for symbol in model.symbols().values() {
+        let ModelSymbol::Definition(xdef) = symbol else {
+            continue;
+        };
+        let Some(section) = model.section(xdef.section()) else {
+            continue;
+        };
+        let end = model
+            .symbols()
+            .values()
+            .filter_map(|s| match s {
+                ModelSymbol::Definition(other)
+                    if other.section() == xdef.section() && other.offset() > xdef.offset() =>
+                {
+                    Some(other.offset())
+                }
+                _ => None,
+            })
+            .min()
+            .unwrap_or(section.size());
+        sizes.insert(xdef.number(), end.saturating_sub(xdef.offset()));
+    }
+    sizes
+}
+
+/// Stores a relocation's addend in the section contents.
+fn write_addend(data: &mut [u8], offset: u32, rel_type: u8, addend: i64) -> Result<()> {
+    let offset = offset as usize;
+    let Some(bytes) = data.get_mut(offset..offset + 4) else {
+        bail!("relocation offset {offset:x} is outside of the section");
+    };
+    let word = u32::from_le_bytes(bytes.try_into().expect("4 bytes"));
+    let addend = addend as u32;
+    let word = match rel_type {
+        R_MIPS_32 => addend,
+        R_MIPS_26 => (word & 0xFC00_0000) | ((addend >> 2) & 0x03FF_FFFF),
+        R_MIPS_HI16 => (word & 0xFFFF_0000) | (addend.wrapping_add(0x8000) >> 16),
+        _ => (word & 0xFFFF_0000) | (addend & 0xFFFF),
+    };
+    bytes.copy_from_slice(&word.to_le_bytes());
+    Ok(())
+}
+
+fn to_bytes<T: for<'a> BinWrite<Args<'a> = ()>>(entries: &[T]) -> Result<Vec<u8>> {
+    let mut writer = Cursor::new(Vec::new());
+    for entry in entries {
+        entry.write_le(&mut writer)?;
+    }
+    Ok(writer.into_inner())
+}
+
+impl ELF {
+    /// Converts a MIPS [OBJ] into an ELF relocatable object.
+    ///
+    /// Each section becomes an ELF section with the same name. XDEFs become global symbols,
+    /// XREFs undefined symbols, XBSS common symbols, and local symbols local symbols. Patches
+    /// become `R_MIPS_32`, `R_MIPS_26`, `R_MIPS_HI16`, `R_MIPS_LO16`, and `R_MIPS_GPREL16`
+    /// relocations against a symbol or a section with the addend stored in the section
+    /// contents.
+    ///
+    /// Debug records become DWARF 2 `.debug_info`, `.debug_line`, and `.debug_frame`
+    /// sections describing the types, variables, functions, source lines, and stack frames of
+    /// the code.
+    ///
+    /// An error is returned if the object is not for a MIPS processor, or a patch has no
+    /// equivalent ELF relocation.
+    pub fn from_obj(obj: &OBJ) -> Result<Self> {
+        let model = ObjectModel::new(obj)?;
+        if model.cpu() != cputype::MIPS_R3000 {
+            bail!(
+                "processor type {} objects cannot be converted to ELF",
+                model.cpu()
+            );
+        }
+
+        let mut elf = ELF::new(EM_MIPS, EF_MIPS_ABI_O32);
+        let relocated = model
+            .sections()
+            .iter()
+            .filter(|s| !s.relocations().is_empty())
+            .count();
+        let debug = dwarf::debug_sections(obj, &model);
+        let first_debug = (1 + model.sections().len() + relocated) as u16;
+        let debug_relocated = debug.iter().filter(|s| !s.relocations.is_empty()).count();
+        let symtab_index = (first_debug as usize + debug.len() + debug_relocated) as u32;
+
+        // contents
+        let mut section_index: HashMap<u16, u16> = HashMap::new();
+        for section in model.sections() {
+            let index = elf.add_section(elf_section(section));
+            section_index.insert(section.number(), index);
+        }
+
+        // symbols
+        let mut strtab = StringTable::default();
+        strtab.add("");
+        let mut symbols = vec![Symbol::default()];
+        let mut index = SymbolIndex {
+            sections: HashMap::new(),
+            symbols: HashMap::new(),
+        };
+        for section in model.sections() {
+            index
+                .sections
+                .insert(section.number(), symbols.len() as u32);
+            symbols.push(Symbol::new(
+                0,
+                0,
+                0,
+                STB_LOCAL,
+                STT_SECTION,
+                section_index[&section.number()],
+            ));
+        }
+        let mut debug_symbols: HashMap<&str, u32> = HashMap::new();
+        for (i, section) in debug.iter().enumerate() {
+            debug_symbols.insert(section.name, symbols.len() as u32);
+            symbols.push(Symbol::new(
+                0,
+                0,
+                0,
+                STB_LOCAL,
+                STT_SECTION,
+                first_debug + i as u16,
+            ));
+        }
+        for local in model.locals() {
+            let Some(&section) = section_index.get(&local.section()) else {
+                continue;
+            };
+            symbols.push(Symbol::new(
+                strtab.add(&local.name()),
+                local.offset(),
+                0,
+                STB_LOCAL,
+                STT_NOTYPE,
+                section,
+            ));
+        }
+        let first_global = symbols.len() as u32;
+
+        let sizes = definition_sizes(&model);
+        for symbol in model.symbols().values() {
+            let entry = match symbol {
+                ModelSymbol::Definition(xdef) => {
+                    let Some(&section) = section_index.get(&xdef.section()) else {
+                        bail!(
+                            "{} is defined in unknown section {:x}",
+                            xdef.symbol_name(),
+                            xdef.section()
+                        );
+                    };
+                    let symbol_type = if elf.sections[section as usize].flags() & SHF_EXECINSTR != 0
+                    {
+                        STT_FUNC
+                    } else {
+                        STT_OBJECT
+                    };
+                    Symbol::new(
+                        strtab.add(&xdef.symbol_name()),
+                        xdef.offset(),
+                        sizes[&xdef.number()],
+                        STB_GLOBAL,
+                        symbol_type,
+                        section,
+                    )
+                }
+                ModelSymbol::Reference(xref) => Symbol::new(
+                    strtab.add(&xref.symbol_name()),
+                    0,
+                    0,
+                    STB_GLOBAL,
+                    STT_NOTYPE,
+                    SHN_UNDEF,
+                ),
+                ModelSymbol::Common(xbss) => Symbol::new(
+                    strtab.add(&xbss.name()),
+                    match xbss.size() {
+                        0..=1 => 1,
+                        2..=3 => 2,
+                        _ => 4,
+                    },
+                    xbss.size(),
+                    STB_GLOBAL,
+                    STT_OBJECT,
+                    SHN_COMMON,
+                ),
+                ModelSymbol::Group(_) => continue,
+            };
+            index.symbols.insert(symbol.number(), symbols.len() as u32);
+            symbols.push(entry);
+        }
+
+        // relocations
+        for section in model.sections() {
+            if section.relocations().is_empty() {
+                continue;
+            }
+            let target = section_index[&section.number()];
+            let mut rels = Vec::new();
+            for relocation in section.relocations() {
+                let error = |e: anyhow::Error| {
+                    anyhow!(
+                        "patch at {:x} in section {}: {e}",
+                        relocation.offset(),
+                        section.name()
+                    )
+                };
+                let rel_type = relocation_type(relocation.tag()).map_err(error)?;
+                let (symbol, addend) = index.target(relocation).map_err(error)?;
+                let data = &mut elf.sections[target as usize].data;
+                write_addend(data, relocation.offset(), rel_type, addend).map_err(error)?;
+                rels.push(Rel::new(relocation.offset(), symbol, rel_type));
+            }
+            elf.add_section(
+                ElfSection::new(
+                    &format!(".rel{}", section.name()),
+                    SHT_REL,
+                    0,
+                    4,
+                    to_bytes(&rels)?,
+                )
+                .with_link(symtab_index, u32::from(target))
+                .with_entry_size(Rel::SIZE),
+            );
+        }
+
+        // debug information
+        let mut debug_rels = Vec::new();
+        for section in debug {
+            let target = elf.add_section(ElfSection::new(
+                section.name,
+                SHT_PROGBITS,
+                0,
+                1,
+                section.data,
+            ));
+            if section.relocations.is_empty() {
+                continue;
+            }
+            let mut rels = Vec::new();
+            for (offset, base) in section.relocations {
+                let symbol = match base {
+                    Base::Section(number) => index.section(number)?,
+                    Base::Debug(name) => debug_symbols[name],
+                };
+                rels.push(Rel::new(offset, symbol, R_MIPS_32));
+            }
+            debug_rels.push((section.name, target, rels));
+        }
+        for (name, target, rels) in debug_rels {
+            elf.add_section(
+                ElfSection::new(&format!(".rel{name}"), SHT_REL, 0, 4, to_bytes(&rels)?)
+                    .with_link(symtab_index, u32::from(target))
+                    .with_entry_size(Rel::SIZE),
+            );
+        }
+
+        elf.add_section(
+            ElfSection::new(".symtab", SHT_SYMTAB, 0, 4, to_bytes(&symbols)?)
+                .with_link(symtab_index + 1, first_global)
+                .with_entry_size(Symbol::SIZE),
+        );
+        elf.add_section(ElfSection::new(".strtab", SHT_STRTAB, 0, 1, strtab.data));
+        Ok(elf)
+    }
+
+    /// Converts a MIPS ELF relocatable object into an [OBJ].
+    ///
+    /// Each allocated section becomes an OBJ section with the same name. Global symbols
+    /// become XDEFs, undefined symbols XREFs, and common symbols XBSS in `.bss`, or `.sbss`
+    /// for small common symbols. Named local symbols are kept as local symbols. `R_MIPS_32`,
+    /// `R_MIPS_26`, `R_MIPS_HI16`, `R_MIPS_LO16`, and `R_MIPS_GPREL16` relocations become
+    /// patches with the addend taken from the section contents. `$gp` relative patches are
+    /// relative to the start of `.sdata`.
+    ///
+    /// An error is returned if the object is not for a MIPS processor, or a relocation has no
+    /// equivalent patch.
+    pub fn to_obj(&self) -> Result<OBJ> {
+        if self.machine != EM_MIPS {
+            bail!(
+                "machine type {} objects cannot be converted to OBJ",
+                self.machine
+            );
+        }
+        let symbols = self.symbols()?;
+
+        // sections
+        let mut headers: Vec<LNKHeader> = Vec::new();
+        let mut numbers: HashMap<usize, u16> = HashMap::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            if !matches!(section.section_type(), SHT_PROGBITS | SHT_NOBITS)
+                || section.flags() & SHF_ALLOC == 0
+            {
+                continue;
+            }
+            numbers.insert(index, headers.len() as u16 + 1);
+            headers.push(LNKHeader::new(
+                headers.len() as u16 + 1,
+                0,
+                u8::try_from(section.align()).unwrap_or(u8::MAX),
+                section.name().into(),
+            ));
+        }
+
+        let mut relocations: Vec<(usize, Vec<Rel>)> = Vec::new();
+        for section in &self.sections {
+            let target = section.info() as usize;
+            match section.section_type() {
+                SHT_REL if numbers.contains_key(&target) => {
+                    relocations.push((target, section.relocations()?));
+                }
+                SHT_RELA if numbers.contains_key(&target) => {
+                    bail!("{}: RELA relocations are not supported", section.name())
+                }
+                _ => (),
+            }
+        }
+
+        // sections referred to by common symbols and `$gp` relative patches
+        let mut section_named = |name: &str| match headers.iter().find(|h| h.type_name() == name) {
+            Some(header) => header.section(),
+            None => {
+                let number = headers.len() as u16 + 1;
+                headers.push(LNKHeader::new(number, 0, 8, name.into()));
+                number
+            }
+        };
+        let uses = |section: u16| symbols.iter().any(|(_, s)| s.section() == section);
+        let bss = uses(SHN_COMMON).then(|| section_named(".bss"));
+        let sbss = uses(SHN_MIPS_SCOMMON).then(|| section_named(".sbss"));
+        let sdata = relocations
+            .iter()
+            .any(|(_, rels)| rels.iter().any(|r| r.rel_type() == R_MIPS_GPREL16))
+            .then(|| section_named(".sdata"));
+
+        // symbols
+        let mut next = headers.len() as u16 + 1;
+        let mut definitions = Vec::new();
+        let mut targets: Vec<Option<(Expression, u32)>> = Vec::new();
+        for (name, symbol) in &symbols {
+            let section = numbers.get(&(symbol.section() as usize)).copied();
+            let target = match (symbol.bind(), symbol.symbol_type()) {
+                (_, STT_SECTION) => {
+                    section.map(|number| (Expression::SectionAddressIndex(number), 0))
+                }
+                (STB_LOCAL, STT_FILE) => None,
+                (STB_LOCAL, _) => section.map(|number| {
+                    if !name.is_empty() {
+                        definitions.push(Section::LocalSymbol(LocalSymbol::new(
+                            number,
+                            symbol.value(),
+                            name.clone(),
+                        )));
+                    }
+                    (Expression::SectionAddressIndex(number), symbol.value())
+                }),
+                _ => {
+                    let number = next;
+                    next += 1;
+                    definitions.push(match (symbol.section(), section) {
+                        (SHN_UNDEF, _) => Section::XREF(XREF::new(number, name.clone())),
+                        (SHN_COMMON, _) | (SHN_MIPS_SCOMMON, _) => Section::XBSS(XBSS::new(
+                            number,
+                            if symbol.section() == SHN_COMMON {
+                                bss
+                            } else {
+                                sbss
+                            }
+                            .expect("common section"),
+                            symbol.size(),
+                            name.clone(),
+                        )),
+                        (_, Some(section)) => {
+                            Section::XDEF(XDEF::new(number, section, symbol.value(), name.clone()))
+                        }
+                        (index, None) => {
+                            bail!("{name} is defined in section {index:x} which is not converted")
+                        }
+                    });
+                    Some((Expression::SymbolAddressIndex(number), 0))
+                }
+            };
+            targets.push(target);
+        }
+
+        // contents
+        let mut sections = vec![Section::CPU(cputype::MIPS_R3000)];
+        sections.extend(headers.iter().cloned().map(Section::LNKHeader));
+        for (index, section) in self.sections.iter().enumerate() {
+            let Some(&number) = numbers.get(&index) else {
+                continue;
+            };
+            if section.size() == 0 {
+                continue;
+            }
+            sections.push(Section::SectionSwitch(number));
+            if section.section_type() == SHT_NOBITS {
+                sections.push(Section::BSS(section.size()));
+                continue;
+            }
+
+            let mut data = section.data().to_vec();
+            let mut patches = Vec::new();
+            let rels = relocations
+                .iter()
+                .filter(|(target, _)| *target == index)
+                .flat_map(|(_, rels)| rels.iter())
+                .collect::<Vec<&Rel>>();
+            for (i, rel) in rels.iter().enumerate() {
+                if rel.rel_type() == R_MIPS_NONE {
+                    continue;
+                }
+                let error = |e: anyhow::Error| {
+                    anyhow!(
+                        "relocation at {:x} in section {}: {e}",
+                        rel.offset(),
+                        section.name()
+                    )
+                };
+                let (target, value) = targets
+                    .get(rel.symbol() as usize)
+                    .cloned()
+                    .flatten()
+                    .ok_or_else(|| anyhow!("symbol {} cannot be patched", rel.symbol()))
+                    .map_err(error)?;
+                // the instruction of the `R_MIPS_LO16` paired with a `R_MIPS_HI16`
+                let lo = rels[i..]
+                    .iter()
+                    .filter(|_| rel.rel_type() == R_MIPS_HI16)
+                    .find(|r| r.rel_type() == R_MIPS_LO16 && r.symbol() == rel.symbol())
+                    .map(|r| read_word(&data, r.offset()))
+                    .transpose()
+                    .map_err(error)?;
+                let (tag, addend) =
+                    take_addend(&mut data, rel.offset(), rel.rel_type(), lo).map_err(error)?;
+                let mut expression = offset(target, i64::from(value) + addend);
+                if tag == mips::GP_RELATIVE {
+                    expression = Expression::Subtract(
+                        Box::new(Expression::SectionStart(sdata.expect("gp section"))),
+                        Box::new(expression),
+                    );
+                }
+                patches.push((rel.offset(), tag, expression));
+            }
+
+            // patch offsets are relative to the start of the chunk they follow
+            patches.sort_by_key(|(offset, _, _)| *offset);
+            let mut patches = patches.into_iter().peekable();
+            for (chunk, code) in data.chunks(CHUNK_SIZE).enumerate() {
+                let start = (chunk * CHUNK_SIZE) as u32;
+                sections.push(Section::Code(Code::new(code.to_vec())?));
+                while let Some((offset, tag, expression)) =
+                    patches.next_if(|(offset, _, _)| *offset < start + code.len() as u32)
+                {
+                    sections.push(Section::Patch(Patch::new(
+                        tag,
+                        (offset - start) as u16,
+                        expression,
+                    )));
+                }
+            }
+            if let Some((offset, _, _)) = patches.next() {
+                bail!(
+                    "relocation offset {offset:x} is outside of section {}",
+                    section.name()
+                );
+            }
+        }
+
+        sections.extend(definitions);
+        sections.push(Section::NOP);
+        Ok(OBJ::new(sections))
+    }
+}
+
+/// The largest number of bytes written to a single [Code] section.
+const CHUNK_SIZE: usize = 0x8000;
+
+fn read_word(data: &[u8], offset: u32) -> Result<u32> {
+    let offset = offset as usize;
+    let Some(bytes) = data.get(offset..offset + 4) else {
+        bail!("relocation offset {offset:x} is outside of the section");
+    };
+    Ok(u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+}
+
+/// Removes a relocation's addend from the section contents, returning the patch type and
+/// the addend. `lo` is the instruction of the `R_MIPS_LO16` paired with an `R_MIPS_HI16`.
+fn take_addend(data: &mut [u8], offset: u32, rel_type: u8, lo: Option<u32>) -> Result<(u8, i64)> {
+    let word = read_word(data, offset)?;
+    let low = i64::from((word & 0xFFFF) as i16);
+    let (tag, addend, word) = match rel_type {
+        R_MIPS_32 => (mips::WORD, i64::from(word as i32), 0),
+        R_MIPS_26 => (
+            mips::JUMP,
+            i64::from((word & 0x03FF_FFFF) << 2),
+            word & 0xFC00_0000,
+        ),
+        R_MIPS_HI16 => {
+            let Some(lo) = lo else {
+                bail!("R_MIPS_HI16 has no matching R_MIPS_LO16");
+            };
+            let addend = i64::from(((word & 0xFFFF) << 16) as i32) + i64::from(lo as i16);
+            (mips::HI16, addend, word & 0xFFFF_0000)
+        }
+        R_MIPS_LO16 => (mips::LO16, low, word & 0xFFFF_0000),
+        R_MIPS_GPREL16 => (mips::GP_RELATIVE, low, word & 0xFFFF_0000),
+        _ => bail!("relocation type {rel_type} has no equivalent patch"),
+    };
+    let offset = offset as usize;
+    data[offset..offset + 4].copy_from_slice(&word.to_le_bytes());
+    Ok((tag, addend))
+}
+
+/// Returns `expression` offset by `addend`.
+fn offset(expression: Expression, addend: i64) -> Expression {
+    let expression = Box::new(expression);
+    match addend {
+        0 => *expression,
+        1.. => Expression::Add(expression, Box::new(Expression::Constant(addend as u32))),
+        _ => Expression::Subtract(
+            Box::new(Expression::Constant(addend.unsigned_abs() as u32)),
+            expression,
+        ),
+    }
+}
+
+fn elf_section(section: &ObjectSection) -> ElfSection {
+    let uninitialized = section.data().is_empty() && section.size() > 0;
+    let flags = section_flags(section.name(), uninitialized);
+    let align = u32::from(section.align()).max(1);
+    if uninitialized {
+        return ElfSection::uninitialized(section.name(), flags, align, section.size());
+    }
+    let mut data = section.data().to_vec();
+    data.resize(section.size() as usize, 0);
+    ElfSection::new(section.name(), SHT_PROGBITS, flags, align, data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LNKHeader, LocalSymbol, Patch, Section, XBSS, XDEF, XREF};
+    use binrw::BinRead;
+
+    fn obj() -> OBJ {
+        use Expression as E;
+        OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".sdata".into())),
+            Section::LNKHeader(LNKHeader::new(3, 0, 8, ".bss".into())),
+            Section::SectionSwitch(1),
+            // jal 0; nop; lui v0, 0; lw v0, 0(v0); lw v1, 0(gp)
+            Section::Code(
+                Code::new(vec![
+                    0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x00, //
+                    0x00, 0x00, 0x02, 0x3C, 0x00, 0x00, 0x42, 0x8C, //
+                    0x00, 0x00, 0x83, 0x8F,
+                ])
+                .unwrap(),
+            ),
+            Section::Patch(Patch::new(74, 0, E::SymbolAddressIndex(5))),
+            Section::Patch(Patch::new(
+                82,
+                8,
+                E::Add(
+                    Box::new(E::Constant(0x10)),
+                    Box::new(E::SymbolAddressIndex(6)),
+                ),
+            )),
+            Section::Patch(Patch::new(
+                84,
+                12,
+                E::Add(
+                    Box::new(E::Constant(0x10)),
+                    Box::new(E::SymbolAddressIndex(6)),
+                ),
+            )),
+            Section::Patch(Patch::new(
+                30,
+                16,
+                E::Subtract(
+                    Box::new(E::SectionStart(2)),
+                    Box::new(E::Add(
+                        Box::new(E::SectionAddressIndex(2)),
+                        Box::new(E::Constant(4)),
+                    )),
+                ),
+            )),
+            Section::SectionSwitch(2),
+            Section::Code(Code::new(vec![0; 8]).unwrap()),
+            Section::Patch(Patch::new(16, 4, E::SymbolAddressIndex(4))),
+            Section::SectionSwitch(3),
+            Section::BSS(16),
+            Section::XDEF(XDEF::new(4, 1, 0, "main".into())),
+            Section::XREF(XREF::new(5, "func".into())),
+            Section::XBSS(XBSS::new(6, 3, 32, "buffer".into())),
+            Section::LocalSymbol(LocalSymbol::new(2, 4, "table".into())),
+            Section::NOP,
+        ])
+    }
+
+    #[test]
+    fn test_from_obj() {
+        let elf = ELF::from_obj(&obj()).unwrap();
+
+        let names = elf
+            .sections()
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            vec![
+                "",
+                ".text",
+                ".sdata",
+                ".bss",
+                ".rel.text",
+                ".rel.sdata",
+                ".symtab",
+                ".strtab"
+            ],
+            names
+        );
+        assert_eq!(SHT_NOBITS, elf.sections()[3].section_type());
+        assert_eq!(16, elf.sections()[3].size());
+        assert_ne!(0, elf.sections()[2].flags() & SHF_MIPS_GPREL);
+
+        let symbols = elf.symbols().unwrap();
+        let named = |name: &str| {
+            symbols
+                .iter()
+                .position(|(n, _)| n == name)
+                .map(|i| (i as u32, symbols[i].1.clone()))
+                .unwrap()
+        };
+        let (_, main) = named("main");
+        assert_eq!(STB_GLOBAL, main.bind());
+        assert_eq!(STT_FUNC, main.symbol_type());
+        assert_eq!(20, main.size());
+        let (func, undefined) = named("func");
+        assert_eq!(SHN_UNDEF, undefined.section());
+        let (buffer, common) = named("buffer");
+        assert_eq!(SHN_COMMON, common.section());
+        assert_eq!(32, common.size());
+        let (_, table) = named("table");
+        assert_eq!(STB_LOCAL, table.bind());
+
+        let rels = elf.sections()[4].relocations().unwrap();
+        assert_eq!(
+            vec![
+                Rel::new(0, func, R_MIPS_26),
+                Rel::new(8, buffer, R_MIPS_HI16),
+                Rel::new(12, buffer, R_MIPS_LO16),
+                Rel::new(16, 2, R_MIPS_GPREL16),
+            ],
+            rels
+        );
+        // addends are stored in the instructions
+        let text = elf.sections()[1].data();
+        assert_eq!(
+            [0x00, 0x00, 0x02, 0x3C, 0x10, 0x00, 0x42, 0x8C],
+            text[8..16]
+        );
+        assert_eq!([0x04, 0x00, 0x83, 0x8F], text[16..20]);
+
+        // the converted object can be read back
+        let mut writer = Cursor::new(Vec::new());
+        elf.write(&mut writer).unwrap();
+        let read = ELF::read(&mut Cursor::new(writer.into_inner())).unwrap();
+        assert_eq!(elf.symbols().unwrap(), read.symbols().unwrap());
+    }
+
+    #[test]
+    fn test_from_obj_errors() {
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 4]).unwrap()),
+            Section::Patch(Patch::new(
+                16,
+                0,
+                Expression::Multiply(
+                    Box::new(Expression::Constant(2)),
+                    Box::new(Expression::SectionAddressIndex(1)),
+                ),
+            )),
+            Section::NOP,
+        ]);
+        assert_eq!(
+            "patch at 0 in section .text: ($2*sectbase(1)) cannot be converted to an ELF relocation",
+            ELF::from_obj(&obj).unwrap_err().to_string()
+        );
+
+        let obj = OBJ::new(vec![Section::CPU(cputype::HITACHI_SH2), Section::NOP]);
+        assert_eq!(
+            "processor type 8 objects cannot be converted to ELF",
+            ELF::from_obj(&obj).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_obj_debug() {
+        use crate::{
+            Def, Def2, Dim, Filename, FunctionStart, SectionOffsetLine, SetSLDLineNum,
+            SetSLDLineNumFile,
+        };
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".data".into())),
+            Section::Filename(Filename::new(1, "main.c".into())),
+            Section::SectionSwitch(1),
+            // addiu sp, sp, -24; sw ra, 20(sp); lw ra, 20(sp); jr ra; addiu sp, sp, 24
+            Section::Code(
+                Code::new(vec![
+                    0xE8, 0xFF, 0xBD, 0x27, 0x14, 0x00, 0xBF, 0xAF, //
+                    0x14, 0x00, 0xBF, 0x8F, 0x08, 0x00, 0xE0, 0x03, //
+                    0x18, 0x00, 0xBD, 0x27,
+                ])
+                .unwrap(),
+            ),
+            Section::SetSLDLineNumFile(SetSLDLineNumFile::new(0, 3, 1)),
+            Section::SetSLDLineNum(SetSLDLineNum::new(8, 5)),
+            Section::SectionSwitch(2),
+            Section::Code(Code::new(vec![0; 8]).unwrap()),
+            Section::XDEF(XDEF::new(3, 1, 0, "main".into())),
+            Section::XDEF(XDEF::new(4, 2, 0, "origin".into())),
+            Section::Def(Def::new(0, 0, 10, 8, 8, "point".into())),
+            Section::Def(Def::new(0, 0, 8, 4, 4, "x".into())),
+            Section::Def(Def::new(0, 4, 8, 4, 4, "y".into())),
+            Section::Def2(Def2::new(
+                0,
+                8,
+                102,
+                0,
+                8,
+                Dim::None,
+                "point".into(),
+                ".eos".into(),
+            )),
+            Section::Def2(Def2::new(
+                2,
+                0,
+                2,
+                8,
+                8,
+                Dim::None,
+                "point".into(),
+                "origin".into(),
+            )),
+            Section::Def(Def::new(1, 0, 2, 0x24, 0, "main".into())),
+            Section::FunctionStart(FunctionStart::new(
+                1,
+                0,
+                1,
+                3,
+                29,
+                24,
+                31,
+                0x8000_0000,
+                -4,
+                "main".into(),
+            )),
+            Section::Def(Def::new(0, -4i32 as u32, 1, 0x12, 4, "name".into())),
+            Section::FunctionEnd(SectionOffsetLine::new(1, 20, 6)),
+            Section::NOP,
+        ]);
+        let elf = ELF::from_obj(&obj).unwrap();
+
+        let names = elf
+            .sections()
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            vec![
+                "",
+                ".text",
+                ".data",
+                ".debug_abbrev",
+                ".debug_info",
+                ".debug_line",
+                ".debug_frame",
+                ".rel.debug_info",
+                ".rel.debug_line",
+                ".rel.debug_frame",
+                ".symtab",
+                ".strtab"
+            ],
+            names
+        );
+        assert_eq!(0, elf.sections()[4].flags());
+
+        // section symbols of .text, .data, and the debug sections come first
+        let symbols = elf.symbols().unwrap();
+        assert_eq!(5, symbols[5].1.section());
+        let line_rels = elf.sections()[8].relocations().unwrap();
+        assert_eq!(vec![Rel::new(39, 1, R_MIPS_32)], line_rels);
+        // the frame description refers to the common information entry and main
+        let frame_rels = elf.sections()[9].relocations().unwrap();
+        assert_eq!(
+            vec![Rel::new(20, 6, R_MIPS_32), Rel::new(24, 1, R_MIPS_32)],
+            frame_rels
+        );
+        let frame = elf.sections()[6].data();
+        // range, `DW_CFA_def_cfa sp 24`, and `DW_CFA_offset ra -4`
+        assert_eq!([20, 0, 0, 0, 0x0C, 29, 24, 0x9F, 1], frame[28..37]);
+
+        let info = elf.sections()[4].data();
+        let contains = |name: &str| info.windows(name.len()).any(|w| w == name.as_bytes());
+        for name in ["main.c", "point", "origin", "main", "name", "char", "int"] {
+            assert!(contains(name), "{name}");
+        }
+
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::NOP,
+        ]);
+        let elf = ELF::from_obj(&obj).unwrap();
+        assert!(elf.section_by_name(".debug_info").is_none());
+    }
+
+    #[test]
+    fn test_to_obj() {
+        let elf = ELF::from_obj(&obj()).unwrap();
+        let obj = elf.to_obj().unwrap();
+        let model = ObjectModel::new(&obj).unwrap();
+
+        let names = model
+            .sections()
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<&str>>();
+        assert_eq!(vec![".text", ".sdata", ".bss"], names);
+        assert_eq!(16, model.sections()[2].size());
+
+        let patches = |section: usize| {
+            model.sections()[section]
+                .relocations()
+                .iter()
+                .map(|r| (r.offset(), r.tag(), r.expression().to_string()))
+                .collect::<Vec<(u32, u8, String)>>()
+        };
+        assert_eq!(
+            vec![
+                (0, mips::JUMP, "[5]".to_string()),
+                (8, mips::HI16, "([6]+$10)".to_string()),
+                (12, mips::LO16, "([6]+$10)".to_string()),
+                (
+                    16,
+                    mips::GP_RELATIVE,
+                    "(sectstart(2)-(sectbase(2)+$4))".to_string()
+                ),
+            ],
+            patches(0)
+        );
+        assert_eq!(vec![(4, mips::WORD, "[4]".to_string())], patches(1));
+        // addends are removed from the instructions
+        assert_eq!(
+            [0x00, 0x00, 0x02, 0x3C, 0x00, 0x00, 0x42, 0x8C],
+            model.sections()[0].data()[8..16]
+        );
+
+        assert!(matches!(
+            model.symbol(4),
+            Some(ModelSymbol::Definition(xdef)) if xdef.symbol_name() == "main"
+        ));
+        assert!(matches!(
+            model.symbol(5),
+            Some(ModelSymbol::Reference(xref)) if xref.symbol_name() == "func"
+        ));
+        assert!(matches!(
+            model.symbol(6),
+            Some(ModelSymbol::Common(xbss)) if xbss.section() == 3 && xbss.size() == 32
+        ));
+        assert_eq!("table", model.locals()[0].name());
+    }
+
+    #[test]
+    fn test_to_obj_relocations() {
+        // lui v0, %hi(sym-4); addiu v0, v0, %lo(sym-4); .word sym
+        let mut elf = ELF::new(EM_MIPS, EF_MIPS_ABI_O32);
+        elf.add_section(ElfSection::new(
+            ".text",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            4,
+            vec![
+                0x00, 0x00, 0x02, 0x3C, 0xFC, 0xFF, 0x42, 0x24, //
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        ));
+        let mut strtab = StringTable::default();
+        let symbols = vec![
+            Symbol::default(),
+            Symbol::new(strtab.add("sym"), 0, 0, STB_GLOBAL, STT_NOTYPE, SHN_UNDEF),
+        ];
+        let rels = |rels: &[Rel]| {
+            ElfSection::new(".rel.text", SHT_REL, 0, 4, to_bytes(rels).unwrap())
+                .with_link(3, 1)
+                .with_entry_size(Rel::SIZE)
+        };
+        elf.add_section(rels(&[
+            Rel::new(0, 1, R_MIPS_HI16),
+            Rel::new(4, 1, R_MIPS_LO16),
+            Rel::new(8, 1, R_MIPS_32),
+        ]));
+        elf.add_section(
+            ElfSection::new(".symtab", SHT_SYMTAB, 0, 4, to_bytes(&symbols).unwrap())
+                .with_link(4, 1)
+                .with_entry_size(Symbol::SIZE),
+        );
+        elf.add_section(ElfSection::new(".strtab", SHT_STRTAB, 0, 1, strtab.data));
+
+        let model = ObjectModel::new(&elf.to_obj().unwrap()).unwrap();
+        let patches = model.sections()[0]
+            .relocations()
+            .iter()
+            .map(|r| (r.tag(), r.expression().to_string()))
+            .collect::<Vec<(u8, String)>>();
+        assert_eq!(
+            vec![
+                (mips::HI16, "($4-[2])".to_string()),
+                (mips::LO16, "($4-[2])".to_string()),
+                (mips::WORD, "[2]".to_string()),
+            ],
+            patches
+        );
+
+        // a `%hi` without a `%lo`
+        elf.sections[2] = rels(&[Rel::new(0, 1, R_MIPS_HI16)]);
+        assert_eq!(
+            "relocation at 0 in section .text: R_MIPS_HI16 has no matching R_MIPS_LO16",
+            elf.to_obj().unwrap_err().to_string()
+        );
+        elf.sections[2] = rels(&[Rel::new(0, 1, 37)]);
+        assert_eq!(
+            "relocation at 0 in section .text: relocation type 37 has no equivalent patch",
+            elf.to_obj().unwrap_err().to_string()
+        );
+
+        let elf = ELF::new(0x28, 0);
+        assert_eq!(
+            "machine type 40 objects cannot be converted to OBJ",
+            elf.to_obj().unwrap_err().to_string()
+        );
+    }
+}
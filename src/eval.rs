@@ -0,0 +1,413 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Evaluation of relocation [Expression]s.
+//!
+//! Expressions refer to symbols, sections, and groups by the numbers assigned to them in an
+//! [OBJ](crate::OBJ). A [Context] maps those numbers to addresses, typically once a linker has
+//! decided where everything is placed.
+//!
+//! ## Operand Order
+//!
+//! The operands of binary operators are stored in the reverse of the order they are written
+//! in. The expression displayed as `($4/(sectstart(1)-sectend(1)))` evaluates the size of
+//! section 1 divided by 4 and `($2-arshift_chk-x)` shifts `x` right by 2.
+//!
+//! ```
+//! use anyhow::Result;
+//! use psyk::Expression;
+//! use psyk::eval::Context;
+//!
+//! struct Sections;
+//!
+//! impl Context for Sections {
+//!     fn symbol(&self, number: u16) -> Result<u32> {
+//!         Ok(0x80010000 + number as u32)
+//!     }
+//!     fn section_base(&self, section: u16) -> Result<u32> {
+//!         Ok(0x80020000)
+//!     }
+//!     fn section_start(&self, section: u16) -> Result<u32> {
+//!         Ok(0x80020000)
+//!     }
+//!     fn section_end(&self, section: u16) -> Result<u32> {
+//!         Ok(0x80020100)
+//!     }
+//!     fn group_start(&self, group: u16) -> Result<u32> {
+//!         Ok(0x80000000)
+//!     }
+//! }
+//!
+//! # fn main() -> Result<()> {
+//! let size = Expression::Divide(
+//!     Box::new(Expression::Constant(4)),
+//!     Box::new(Expression::Subtract(
+//!         Box::new(Expression::SectionStart(1)),
+//!         Box::new(Expression::SectionEnd(1)),
+//!     )),
+//! );
+//! assert_eq!(0x40, size.evaluate(&Sections)?);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{bail, Result};
+
+use crate::Expression;
+
+/// Supplies the addresses an [Expression] refers to.
+///
+/// Functions which are only meaningful for some targets, such as `bank()` and `seg()`, default
+/// to returning an error.
+pub trait Context {
+    /// Returns the address of the symbol with the given number, `[n]`.
+    fn symbol(&self, number: u16) -> Result<u32>;
+
+    /// Returns the address of this object's contribution to a section, `sectbase(n)`.
+    fn section_base(&self, section: u16) -> Result<u32>;
+
+    /// Returns the start address of a section after all objects are combined, `sectstart(n)`.
+    fn section_start(&self, section: u16) -> Result<u32>;
+
+    /// Returns the end address of a section after all objects are combined, `sectend(n)`.
+    fn section_end(&self, section: u16) -> Result<u32>;
+
+    /// Returns the start address of a group, `groupstart(n)`.
+    fn group_start(&self, group: u16) -> Result<u32>;
+
+    /// Returns the origin of a group, `grouporg(n)`.
+    fn group_org(&self, group: u16) -> Result<u32> {
+        self.group_start(group)
+    }
+
+    /// Returns the bank of a symbol, `bank(n)`.
+    fn bank(&self, number: u16) -> Result<u32> {
+        bail!("bank({number:x}) is not supported")
+    }
+
+    /// Returns the section of a symbol, `sectof(n)`.
+    fn section_of(&self, number: u16) -> Result<u32> {
+        bail!("sectof({number:x}) is not supported")
+    }
+
+    /// Returns the offset of a symbol from the start of its section, `offs(n)`.
+    fn offset(&self, number: u16) -> Result<u32> {
+        bail!("offs({number:x}) is not supported")
+    }
+
+    /// Returns the group of a symbol, `groupof(n)`.
+    fn group_of(&self, number: u16) -> Result<u32> {
+        bail!("groupof({number:x}) is not supported")
+    }
+
+    /// Returns the segment of a symbol, `seg(n)`.
+    fn segment(&self, number: u16) -> Result<u32> {
+        bail!("seg({number:x}) is not supported")
+    }
+}
+
+/// Returns the low `bits` bits of `value`, failing if `bits` is not a valid width.
+fn low_bits(operator: &str, bits: i64, value: i64) -> Result<i64> {
+    if !(0..=32).contains(&bits) {
+        bail!("{operator} bit count {bits} is out of range");
+    }
+    Ok(value & ((1i64 << bits) - 1))
+}
+
+impl Expression {
+    /// Evaluates the expression.
+    ///
+    /// Values are calculated with 64-bit signed arithmetic. An error is returned if the result
+    /// of any operation cannot be represented in 32 bits, signed or unsigned, if a division by
+    /// zero occurs, or if any of the SH-2 range checks fail.
+    ///
+    /// The SH-2 operators take a bit or byte count as their first operand:
+    ///
+    /// | Operator             | Result                                                         |
+    /// |----------------------|----------------------------------------------------------------|
+    /// | `(n-arshift_chk-x)`  | `x` arithmetically shifted right `n` bits. The low `n` bits of `x` must be 0. |
+    /// | `(n-check0-x)`       | `x`. The low `n` bits of `x` must be 0.                        |
+    /// | `(n-check1-x)`       | `x`. The low `n` bits of `x` must be 1.                        |
+    /// | `(n-bitrange-x)`     | `x`. `x` must fit in `n` bits, signed or unsigned.             |
+    /// | `(n-revword-x)`      | `x` with the order of its low `n` bytes reversed.              |
+    pub fn evaluate(&self, context: &impl Context) -> Result<i64> {
+        let binary = |lhs: &Expression, rhs: &Expression| -> Result<(i64, i64)> {
+            // operands are stored in reverse order
+            Ok((rhs.evaluate(context)?, lhs.evaluate(context)?))
+        };
+        let checked = |value: Option<i64>| -> Result<i64> {
+            match value {
+                Some(v) if (i64::from(i32::MIN)..=i64::from(u32::MAX)).contains(&v) => Ok(v),
+                _ => bail!("{self} overflows 32 bits"),
+            }
+        };
+
+        Ok(match self {
+            Self::Constant(value) => i64::from(*value),
+            Self::SymbolAddressIndex(number) => i64::from(context.symbol(*number)?),
+            Self::SectionAddressIndex(section) => i64::from(context.section_base(*section)?),
+            Self::Bank(number) => i64::from(context.bank(*number)?),
+            Self::SectionOffset(number) => i64::from(context.section_of(*number)?),
+            Self::Offset(number) => i64::from(context.offset(*number)?),
+            Self::SectionStart(section) => i64::from(context.section_start(*section)?),
+            Self::GroupStart(group) => i64::from(context.group_start(*group)?),
+            Self::GroupOffset(number) => i64::from(context.group_of(*number)?),
+            Self::Segment(number) => i64::from(context.segment(*number)?),
+            Self::GroupOrg(group) => i64::from(context.group_org(*group)?),
+            Self::SectionEnd(section) => i64::from(context.section_end(*section)?),
+
+            // comparison
+            Self::Equals(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                i64::from(l == r)
+            }
+            Self::NotEquals(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                i64::from(l != r)
+            }
+            Self::LTE(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                i64::from(l <= r)
+            }
+            Self::LessThan(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                i64::from(l < r)
+            }
+            Self::GTE(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                i64::from(l >= r)
+            }
+            Self::GreaterThan(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                i64::from(l > r)
+            }
+
+            // arithmetic
+            Self::Add(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                checked(l.checked_add(r))?
+            }
+            Self::Subtract(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                checked(l.checked_sub(r))?
+            }
+            Self::Multiply(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                checked(l.checked_mul(r))?
+            }
+            Self::Divide(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                if r == 0 {
+                    bail!("{self} divides by zero");
+                }
+                checked(l.checked_div(r))?
+            }
+            Self::Mod(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                if r == 0 {
+                    bail!("{self} divides by zero");
+                }
+                checked(l.checked_rem(r))?
+            }
+            Self::And(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                l & r
+            }
+            Self::Or(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                l | r
+            }
+            Self::XOR(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                l ^ r
+            }
+            Self::LeftShift(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                if !(0..32).contains(&r) {
+                    bail!("{self} shifts by {r} bits");
+                }
+                checked(l.checked_shl(r as u32))?
+            }
+            Self::RightShift(lhs, rhs) => {
+                let (l, r) = binary(lhs, rhs)?;
+                if !(0..32).contains(&r) {
+                    bail!("{self} shifts by {r} bits");
+                }
+                l >> r
+            }
+            Self::Dashes(_, _) => bail!("{self}: the --- operator is not supported"),
+
+            // SH-2
+            Self::ArshiftChk(lhs, rhs) => {
+                let (value, bits) = binary(lhs, rhs)?;
+                if low_bits("arshift_chk", bits, value)? != 0 {
+                    bail!("{self}: {value:#x} is not a multiple of {}", 1i64 << bits);
+                }
+                value >> bits
+            }
+            Self::Check0(lhs, rhs) => {
+                let (value, bits) = binary(lhs, rhs)?;
+                if low_bits("check0", bits, value)? != 0 {
+                    bail!("{self}: the low {bits} bits of {value:#x} are not 0");
+                }
+                value
+            }
+            Self::Check1(lhs, rhs) => {
+                let (value, bits) = binary(lhs, rhs)?;
+                if low_bits("check1", bits, value)? != low_bits("check1", bits, -1)? {
+                    bail!("{self}: the low {bits} bits of {value:#x} are not 1");
+                }
+                value
+            }
+            Self::BitRange(lhs, rhs) => {
+                let (value, bits) = binary(lhs, rhs)?;
+                if !(1..=32).contains(&bits) {
+                    bail!("{self}: bitrange bit count {bits} is out of range");
+                }
+                if value < -(1i64 << (bits - 1)) || value >= (1i64 << bits) {
+                    bail!("{self}: {value:#x} does not fit in {bits} bits");
+                }
+                value
+            }
+            Self::Revword(lhs, rhs) => {
+                let (value, bytes) = binary(lhs, rhs)?;
+                if !(1..=4).contains(&bytes) {
+                    bail!("{self}: revword byte count {bytes} is out of range");
+                }
+                let bytes = bytes as usize;
+                let le = (value as u32).to_le_bytes();
+                let mut reversed = [0u8; 4];
+                for i in 0..bytes {
+                    reversed[i] = le[bytes - 1 - i];
+                }
+                i64::from(u32::from_le_bytes(reversed))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Symbols are at `$1000 * n`, sections span `$100 * n` to `$100 * n + $80`, and groups
+    /// start at `$10000 * n`.
+    struct TestContext;
+
+    impl Context for TestContext {
+        fn symbol(&self, number: u16) -> Result<u32> {
+            Ok(0x1000 * u32::from(number))
+        }
+
+        fn section_base(&self, section: u16) -> Result<u32> {
+            Ok(0x100 * u32::from(section) + 0x10)
+        }
+
+        fn section_start(&self, section: u16) -> Result<u32> {
+            Ok(0x100 * u32::from(section))
+        }
+
+        fn section_end(&self, section: u16) -> Result<u32> {
+            Ok(0x100 * u32::from(section) + 0x80)
+        }
+
+        fn group_start(&self, group: u16) -> Result<u32> {
+            Ok(0x10000 * u32::from(group))
+        }
+    }
+
+    fn c(value: u32) -> Box<Expression> {
+        Box::new(Expression::Constant(value))
+    }
+
+    fn eval(expression: Expression) -> Result<i64> {
+        expression.evaluate(&TestContext)
+    }
+
+    #[test]
+    fn test_reference() {
+        assert_eq!(0x5000, eval(Expression::SymbolAddressIndex(5)).unwrap());
+        assert_eq!(0x210, eval(Expression::SectionAddressIndex(2)).unwrap());
+        assert_eq!(0x200, eval(Expression::SectionStart(2)).unwrap());
+        assert_eq!(0x280, eval(Expression::SectionEnd(2)).unwrap());
+        assert_eq!(0x30000, eval(Expression::GroupStart(3)).unwrap());
+        assert_eq!(0x30000, eval(Expression::GroupOrg(3)).unwrap());
+        assert_eq!(
+            "bank(3) is not supported",
+            eval(Expression::Bank(3)).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_operand_order() {
+        // ($4/(sectstart(1)-sectend(1)))
+        let size = Expression::Divide(
+            c(4),
+            Box::new(Expression::Subtract(
+                Box::new(Expression::SectionStart(1)),
+                Box::new(Expression::SectionEnd(1)),
+            )),
+        );
+        assert_eq!(0x20, eval(size).unwrap());
+
+        assert_eq!(1, eval(Expression::LessThan(c(2), c(1))).unwrap());
+        assert_eq!(0, eval(Expression::GreaterThan(c(2), c(1))).unwrap());
+        assert_eq!(1, eval(Expression::LTE(c(2), c(2))).unwrap());
+        assert_eq!(1, eval(Expression::NotEquals(c(2), c(3))).unwrap());
+        assert_eq!(0x40, eval(Expression::LeftShift(c(4), c(4))).unwrap());
+        assert_eq!(0x4, eval(Expression::RightShift(c(4), c(0x40))).unwrap());
+        assert_eq!(2, eval(Expression::Mod(c(5), c(12))).unwrap());
+        assert_eq!(-1, eval(Expression::Subtract(c(1), c(0))).unwrap());
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = eval(Expression::Divide(c(0), c(1))).unwrap_err();
+        assert_eq!("($0/$1) divides by zero", error.to_string());
+
+        let error = eval(Expression::Multiply(c(0x10000), c(0x10000))).unwrap_err();
+        assert_eq!("($10000*$10000) overflows 32 bits", error.to_string());
+
+        let error = eval(Expression::LeftShift(c(40), c(1))).unwrap_err();
+        assert_eq!("($28<<$1) shifts by 40 bits", error.to_string());
+    }
+
+    #[test]
+    fn test_sh2() {
+        // ($2-arshift_chk-(($fffffffc&(sectbase(1)+$22))-(sectbase(1)+$60)))
+        let base = || Box::new(Expression::SectionAddressIndex(1));
+        let displacement = Expression::ArshiftChk(
+            c(2),
+            Box::new(Expression::Subtract(
+                Box::new(Expression::And(
+                    c(0xFFFFFFFC),
+                    Box::new(Expression::Add(base(), c(0x22))),
+                )),
+                Box::new(Expression::Add(base(), c(0x60))),
+            )),
+        );
+        assert_eq!(0x10, eval(displacement).unwrap());
+
+        let backwards = Expression::ArshiftChk(c(1), Box::new(Expression::Subtract(c(4), c(0))));
+        assert_eq!(-2, eval(backwards).unwrap());
+        let error = eval(Expression::ArshiftChk(c(2), c(6))).unwrap_err();
+        assert_eq!(
+            "($2-arshift_chk-$6): 0x6 is not a multiple of 4",
+            error.to_string()
+        );
+
+        assert_eq!(8, eval(Expression::Check0(c(3), c(8))).unwrap());
+        assert!(eval(Expression::Check0(c(3), c(9))).is_err());
+        assert_eq!(7, eval(Expression::Check1(c(3), c(7))).unwrap());
+        assert!(eval(Expression::Check1(c(3), c(6))).is_err());
+
+        assert_eq!(0xFF, eval(Expression::BitRange(c(8), c(0xFF))).unwrap());
+        assert!(eval(Expression::BitRange(c(8), c(0x100))).is_err());
+
+        assert_eq!(0x3412, eval(Expression::Revword(c(2), c(0x1234))).unwrap());
+        assert_eq!(
+            0x78563412,
+            eval(Expression::Revword(c(4), c(0x12345678))).unwrap()
+        );
+    }
+}
@@ -0,0 +1,332 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! PlayStation `PS-X EXE` executables.
+//!
+//! A `PS-X EXE` is the executable format loaded by the PlayStation BIOS. It consists of a
+//! 2048 byte header followed by a single block of code and data which is copied to the text
+//! address before jumping to the initial program counter.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use psyk::{exe::EXE, io, link::Linker};
+//! use anyhow::Result;
+//!
+//! fn main() -> Result<()> {
+//!     let image = Linker::from_script(Path::new("MAIN.LNK"))?.link()?;
+//!     let exe = EXE::from_image(&image)?;
+//!     io::write_exe(&exe, &mut std::fs::File::create("MAIN.EXE")?)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use binrw::binrw;
+
+use crate::display::{self, DisplayWithOptions};
+use crate::link::Image;
+
+/// The size of the header and the alignment of the text segment.
+pub const SECTOR_SIZE: usize = 0x800;
+
+/// The size of the region marker following the header fields.
+const MARKER_SIZE: usize = SECTOR_SIZE - 0x4C;
+
+/// The stack address used when a linker script does not set `sp`.
+pub const DEFAULT_STACK: u32 = 0x801FFFF0;
+
+/// Region markers checked by the BIOS.
+pub mod region {
+    pub const JAPAN: &str = "Sony Computer Entertainment Inc. for Japan area";
+    pub const NORTH_AMERICA: &str = "Sony Computer Entertainment Inc. for North America area";
+    pub const EUROPE: &str = "Sony Computer Entertainment Inc. for Europe area";
+}
+
+/// A PlayStation executable.
+///
+/// # Structure on Disk
+///
+/// | Offset | Type      | Description                                             |
+/// |--------|-----------|---------------------------------------------------------|
+/// | 0      | `[u8; 8]` | Magic: "PS-X EXE"                                       |
+/// | 8      | `[u8; 8]` | Unused, zero filled.                                    |
+/// | 16     | `u32`     | Initial PC.                                             |
+/// | 20     | `u32`     | Initial GP.                                             |
+/// | 24     | `u32`     | Text address.                                           |
+/// | 28     | `u32`     | Text size, a multiple of 2048.                          |
+/// | 32     | `u32`     | Data address (unused).                                  |
+/// | 36     | `u32`     | Data size (unused).                                     |
+/// | 40     | `u32`     | BSS address.                                            |
+/// | 44     | `u32`     | BSS size.                                               |
+/// | 48     | `u32`     | Initial SP base. Ignored if zero.                       |
+/// | 52     | `u32`     | Initial SP offset.                                      |
+/// | 56     | `[u32;5]` | Registers saved by the BIOS `Exec` call, zero filled.   |
+/// | 76     | `[u8]`    | Region marker, `NULL` padded to 2048 bytes.             |
+/// | 2048   | `[u8]`    | Text.                                                   |
+#[binrw]
+#[brw(little, magic = b"PS-X EXE")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EXE {
+    unused: [u8; 8],
+    pc0: u32,
+    gp0: u32,
+    t_addr: u32,
+    t_size: u32,
+    d_addr: u32,
+    d_size: u32,
+    b_addr: u32,
+    b_size: u32,
+    s_addr: u32,
+    s_size: u32,
+    saved: [u32; 5],
+    #[br(count = MARKER_SIZE)]
+    #[bw(assert(marker.len() == MARKER_SIZE))]
+    marker: Vec<u8>,
+    #[br(count = t_size)]
+    text: Vec<u8>,
+}
+
+impl EXE {
+    /// Creates an executable which loads `text` at `t_addr` and starts executing at `pc0`.
+    ///
+    /// The text is padded to a multiple of 2048 bytes, the stack is set to
+    /// [DEFAULT_STACK], and the region marker is set to [North America](region::NORTH_AMERICA).
+    pub fn new(pc0: u32, gp0: u32, t_addr: u32, text: Vec<u8>) -> Self {
+        let mut exe = Self {
+            unused: [0; 8],
+            pc0,
+            gp0,
+            t_addr,
+            t_size: 0,
+            d_addr: 0,
+            d_size: 0,
+            b_addr: 0,
+            b_size: 0,
+            s_addr: DEFAULT_STACK,
+            s_size: 0,
+            saved: [0; 5],
+            marker: Vec::new(),
+            text: Vec::new(),
+        };
+        exe.set_text(text);
+        exe.set_region(region::NORTH_AMERICA);
+        exe
+    }
+
+    /// Creates an executable from a linked [Image].
+    ///
+    /// The initial PC must be set in the linker script with `regs pc=`. The initial GP and SP
+    /// are taken from `regs gp=` and `regs sp=` if present. The BSS range covers every
+    /// uninitialized section in a group that is not an overlay.
+    pub fn from_image(image: &Image) -> Result<Self> {
+        let Some(pc0) = image.entry() else {
+            bail!("No entry point. Set one in the linker script with `regs pc=`");
+        };
+        let registers = image.registers();
+        let gp0 = registers.get("gp").copied().unwrap_or(0);
+        let mut exe = Self::new(pc0, gp0, image.address(), image.to_bytes());
+
+        if let Some(&sp) = registers.get("sp") {
+            exe.set_stack(sp, 0);
+        }
+
+        let loaded = |group: &str| {
+            image
+                .groups()
+                .iter()
+                .any(|g| g.name() == group && g.overlay().is_none())
+        };
+        let bss = image
+            .sections()
+            .iter()
+            .filter(|s| s.is_bss() && s.size() > 0 && loaded(s.group()));
+        let start = bss.clone().map(|s| s.address()).min();
+        let end = bss.map(|s| s.address().wrapping_add(s.size())).max();
+        if let (Some(start), Some(end)) = (start, end) {
+            exe.set_bss(start, end - start);
+        }
+
+        Ok(exe)
+    }
+
+    /// Returns the initial program counter.
+    pub fn pc(&self) -> u32 {
+        self.pc0
+    }
+
+    pub fn set_pc(&mut self, pc0: u32) {
+        self.pc0 = pc0;
+    }
+
+    /// Returns the initial global pointer.
+    pub fn gp(&self) -> u32 {
+        self.gp0
+    }
+
+    pub fn set_gp(&mut self, gp0: u32) {
+        self.gp0 = gp0;
+    }
+
+    /// Returns the address the text is loaded at.
+    pub fn text_address(&self) -> u32 {
+        self.t_addr
+    }
+
+    /// Returns the text, including any padding.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// Replaces the text, padding it to a multiple of 2048 bytes.
+    pub fn set_text(&mut self, mut text: Vec<u8>) {
+        text.resize(text.len().next_multiple_of(SECTOR_SIZE), 0);
+        self.t_size = text.len() as u32;
+        self.text = text;
+    }
+
+    /// Returns the address and size of the uninitialized data cleared before execution.
+    pub fn bss(&self) -> (u32, u32) {
+        (self.b_addr, self.b_size)
+    }
+
+    pub fn set_bss(&mut self, address: u32, size: u32) {
+        self.b_addr = address;
+        self.b_size = size;
+    }
+
+    /// Returns the initial stack base and offset. The stack pointer is only set by the BIOS
+    /// if the base is non-zero.
+    pub fn stack(&self) -> (u32, u32) {
+        (self.s_addr, self.s_size)
+    }
+
+    pub fn set_stack(&mut self, address: u32, offset: u32) {
+        self.s_addr = address;
+        self.s_size = offset;
+    }
+
+    /// Returns the region marker.
+    pub fn region(&self) -> String {
+        let end = self
+            .marker
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(self.marker.len());
+        String::from_utf8_lossy(&self.marker[..end]).into_owned()
+    }
+
+    /// Sets the region marker. Markers longer than the header allows are truncated.
+    pub fn set_region(&mut self, region: &str) {
+        let mut marker = region.as_bytes().to_vec();
+        marker.resize(MARKER_SIZE, 0);
+        self.marker = marker;
+    }
+}
+
+impl fmt::Display for EXE {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_options(f, &display::Options::default())
+    }
+}
+
+impl display::DisplayWithOptions for EXE {
+    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
+        options.write_indent(f)?;
+        writeln!(f, "Header : PS-X EXE")?;
+        options.write_indent(f)?;
+        writeln!(f, "PC     : ${:08x}", self.pc0)?;
+        options.write_indent(f)?;
+        writeln!(f, "GP     : ${:08x}", self.gp0)?;
+        options.write_indent(f)?;
+        writeln!(f, "Text   : ${:08x} {} bytes", self.t_addr, self.text.len())?;
+        options.write_indent(f)?;
+        writeln!(f, "BSS    : ${:08x} {} bytes", self.b_addr, self.b_size)?;
+        options.write_indent(f)?;
+        writeln!(f, "Stack  : ${:08x}+${:x}", self.s_addr, self.s_size)?;
+        options.write_indent(f)?;
+        writeln!(f, "Region : {}", self.region())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use binrw::io::Cursor;
+    use binrw::{BinRead, BinWrite};
+
+    use crate::link::{parse_script, Linker};
+    use crate::{Code, LNKHeader, Section, OBJ, XDEF};
+
+    #[test]
+    fn test_exe() {
+        let mut exe = EXE::new(0x80010000, 0x80020000, 0x80010000, vec![1, 2, 3, 4]);
+        exe.set_bss(0x80010800, 0x100);
+        exe.set_region(region::JAPAN);
+
+        let mut writer = Cursor::new(Vec::new());
+        exe.write(&mut writer).unwrap();
+        let bytes = writer.into_inner();
+        assert_eq!(2 * SECTOR_SIZE, bytes.len());
+        assert_eq!(b"PS-X EXE", &bytes[0..8]);
+        assert_eq!([0x00, 0x00, 0x01, 0x80], bytes[0x10..0x14]);
+        assert_eq!([0x00, 0x08, 0x00, 0x00], bytes[0x1C..0x20]);
+        assert_eq!([0xF0, 0xFF, 0x1F, 0x80], bytes[0x30..0x34]);
+        assert_eq!(
+            region::JAPAN.as_bytes(),
+            &bytes[0x4C..0x4C + region::JAPAN.len()]
+        );
+        assert_eq!([1, 2, 3, 4], bytes[0x800..0x804]);
+
+        let read = EXE::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(exe, read);
+        assert_eq!(0x80010000, read.pc());
+        assert_eq!(0x80020000, read.gp());
+        assert_eq!((0x80010800, 0x100), read.bss());
+        assert_eq!(region::JAPAN, read.region());
+        assert_eq!(
+            "\
+            Header : PS-X EXE\n\
+            PC     : $80010000\n\
+            GP     : $80020000\n\
+            Text   : $80010000 2048 bytes\n\
+            BSS    : $80010800 256 bytes\n\
+            Stack  : $801ffff0+$0\n\
+            Region : Sony Computer Entertainment Inc. for Japan area\n",
+            read.to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_image() {
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".bss".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 12]).unwrap()),
+            Section::SectionSwitch(2),
+            Section::BSS(32),
+            Section::XDEF(XDEF::new(3, 1, 4, "main".into())),
+            Section::NOP,
+        ]);
+
+        let mut linker = Linker::new();
+        linker.add_object("MAIN.OBJ", obj.clone());
+        linker.set_origin(0x80010000);
+        let error = EXE::from_image(&linker.link().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("regs pc="), "{error}");
+
+        let mut linker = Linker::new();
+        for command in parse_script("org $80010000\nregs pc=main\nregs sp=$801FFF00\n").unwrap() {
+            linker.apply(command, std::path::Path::new("")).unwrap();
+        }
+        linker.add_object("MAIN.OBJ", obj);
+        let exe = EXE::from_image(&linker.link().unwrap()).unwrap();
+        assert_eq!(0x80010004, exe.pc());
+        assert_eq!(0x80010000, exe.text_address());
+        assert_eq!(SECTOR_SIZE, exe.text().len());
+        assert_eq!((0x80010010, 32), exe.bss());
+        assert_eq!((0x801FFF00, 0), exe.stack());
+    }
+}
@@ -0,0 +1,373 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Relocation-masked function fingerprints.
+//!
+//! The code of a function in an object differs from the same function linked into an
+//! executable only in the fields its [Patch](crate::Patch)es write. A [Fingerprint] keeps the
+//! bytes of a [Function] with those fields cleared, a mask of the bits that remain, a hash of
+//! both, and the symbols the patches refer to. Code matches a fingerprint when it equals
+//! [Fingerprint::data] in every bit set in [Fingerprint::mask].
+//!
+//! ```
+//! use psyk::fingerprint;
+//! use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF, XREF};
+//!
+//! // jal printf; nop
+//! let obj = OBJ::new(vec![
+//!     Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+//!     Section::SectionSwitch(1),
+//!     Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0, 0]).unwrap()),
+//!     Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(3))),
+//!     Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+//!     Section::XREF(XREF::new(3, "printf".into())),
+//!     Section::NOP,
+//! ]);
+//! let fingerprints = fingerprint::fingerprints(&obj).unwrap();
+//! assert_eq!("main", fingerprints[0].name());
+//! assert_eq!([0x00, 0x00, 0x00, 0xFC], fingerprints[0].mask()[..4]);
+//! assert_eq!(vec!["printf"], fingerprints[0].symbols());
+//! assert!(fingerprints[0].matches(&[0x40, 0x00, 0x00, 0x0C, 0, 0, 0, 0]));
+//! ```
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::model::{Function, ObjectModel, Relocation};
+use crate::patch::{mips, Target};
+use crate::{cputype, Expression, OBJ};
+
+/// A reference from a function to a symbol or section through a patch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reference {
+    offset: u32,
+    #[serde(rename = "type")]
+    tag: u8,
+    symbol: Option<String>,
+    section: bool,
+    addend: i64,
+}
+
+impl Reference {
+    /// Returns the offset of the patch from the start of the function.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the type of patch.
+    pub fn tag(&self) -> u8 {
+        self.tag
+    }
+
+    /// Returns the name of the symbol or section referred to, or `None` if the patch
+    /// expression is not an address and a constant.
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
+    }
+
+    /// Returns `true` if [Reference::symbol] is the name of a section in the object.
+    pub fn is_section(&self) -> bool {
+        self.section
+    }
+
+    /// Returns the constant added to the address of the symbol.
+    pub fn addend(&self) -> i64 {
+        self.addend
+    }
+}
+
+/// The code of a function with its relocated fields masked out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    name: String,
+    section: String,
+    size: u32,
+    #[serde(serialize_with = "hash", deserialize_with = "parse_hash")]
+    hash: u64,
+    #[serde(serialize_with = "hex", deserialize_with = "parse_hex")]
+    data: Vec<u8>,
+    #[serde(serialize_with = "hex", deserialize_with = "parse_hex")]
+    mask: Vec<u8>,
+    symbols: Vec<String>,
+    references: Vec<Reference>,
+}
+
+impl Fingerprint {
+    /// Creates the fingerprint of `function` from the object `model`.
+    pub fn new(model: &ObjectModel, function: &Function) -> Self {
+        let mut mask = vec![0xFF; function.data().len()];
+        let mut references = Vec::new();
+        for relocation in function.relocations() {
+            let offset = relocation.offset() - function.offset();
+            let patched = patched_bits(relocation.target(), relocation.tag());
+            for (i, bits) in patched.iter().enumerate() {
+                if let Some(mask) = mask.get_mut(offset as usize + i) {
+                    *mask &= !bits;
+                }
+            }
+            references.push(reference(model, relocation, offset));
+        }
+        let data: Vec<u8> = function
+            .data()
+            .iter()
+            .zip(&mask)
+            .map(|(byte, mask)| byte & mask)
+            .collect();
+
+        let mut symbols: Vec<String> = Vec::new();
+        for symbol in references.iter().filter_map(|r| r.symbol.as_ref()) {
+            if !symbols.contains(symbol) {
+                symbols.push(symbol.clone());
+            }
+        }
+
+        Self {
+            name: function.name().into(),
+            section: model
+                .section(function.section())
+                .map_or_else(String::new, |s| s.name().into()),
+            size: function.size(),
+            hash: fnv1a(data.iter().chain(&mask)),
+            data,
+            mask,
+            symbols,
+            references,
+        }
+    }
+
+    /// Returns the name of the function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the section the function is in.
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    /// Returns the size of the function in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the 64-bit FNV-1a hash of [Fingerprint::data] followed by
+    /// [Fingerprint::mask]. Functions with the same code outside of their relocated fields
+    /// have the same hash.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the code of the function with its relocated bits cleared.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the bits of the code which are not written by patches.
+    pub fn mask(&self) -> &[u8] {
+        &self.mask
+    }
+
+    /// Returns the names of the symbols and sections referred to in order of their first
+    /// reference.
+    pub fn symbols(&self) -> Vec<&str> {
+        self.symbols.iter().map(String::as_str).collect()
+    }
+
+    /// Returns the patches of the function.
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    /// Returns `true` if `code` begins with the function.
+    pub fn matches(&self, code: &[u8]) -> bool {
+        code.len() >= self.data.len()
+            && code
+                .iter()
+                .zip(&self.data)
+                .zip(&self.mask)
+                .all(|((byte, data), mask)| byte & mask == *data)
+    }
+}
+
+/// Returns the fingerprints of the functions in `obj`. See [ObjectModel::functions].
+pub fn fingerprints(obj: &OBJ) -> anyhow::Result<Vec<Fingerprint>> {
+    let model = ObjectModel::new(obj)?;
+    Ok(model
+        .functions()
+        .iter()
+        .map(|function| Fingerprint::new(&model, function))
+        .collect())
+}
+
+/// Returns the bits written by a patch with `tag`, starting at the patch offset.
+fn patched_bits(target: &Target, tag: u8) -> Vec<u8> {
+    if target.cpu() == cputype::MIPS_R3000 {
+        let bits: u32 = match tag {
+            mips::JUMP => 0x03FF_FFFF,
+            mips::HI16 | mips::LO16 | mips::GP_RELATIVE => 0xFFFF,
+            _ => 0xFFFF_FFFF,
+        };
+        return bits.to_le_bytes().to_vec();
+    }
+    // patches of unknown size are assumed to be a long
+    vec![0xFF; target.size(tag).unwrap_or(4)]
+}
+
+fn reference(model: &ObjectModel, relocation: &Relocation, offset: u32) -> Reference {
+    let (symbol, section, addend) = match relocation.address() {
+        Some((Expression::SymbolAddressIndex(number), addend)) => (
+            model.symbol(*number).map(|symbol| symbol.name()),
+            false,
+            addend,
+        ),
+        Some((Expression::SectionAddressIndex(number), addend)) => (
+            model.section(*number).map(|section| section.name().into()),
+            true,
+            addend,
+        ),
+        _ => (None, false, 0),
+    };
+    Reference {
+        offset,
+        tag: relocation.tag(),
+        symbol,
+        section,
+        addend,
+    }
+}
+
+/// 64-bit FNV-1a.
+fn fnv1a<'a>(bytes: impl Iterator<Item = &'a u8>) -> u64 {
+    bytes.fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+fn hash<S: Serializer>(hash: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{hash:016x}"))
+}
+
+fn hex<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    serializer.serialize_str(&hex)
+}
+
+fn parse_hash<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let hash = String::deserialize(deserializer)?;
+    u64::from_str_radix(&hash, 16).map_err(de::Error::custom)
+}
+
+fn parse_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(de::Error::custom(format!("invalid hex string {hex}")));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(de::Error::custom))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LNKHeader, Patch, Section, XDEF, XREF};
+
+    fn obj(code: Vec<u8>) -> OBJ {
+        OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".data".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(code).unwrap()),
+            Section::Patch(Patch::new(
+                mips::HI16,
+                0,
+                Expression::Add(
+                    Box::new(Expression::Constant(0x10)),
+                    Box::new(Expression::SectionAddressIndex(2)),
+                ),
+            )),
+            Section::Patch(Patch::new(
+                mips::LO16,
+                4,
+                Expression::Add(
+                    Box::new(Expression::Constant(0x10)),
+                    Box::new(Expression::SectionAddressIndex(2)),
+                ),
+            )),
+            Section::Patch(Patch::new(mips::JUMP, 8, Expression::SymbolAddressIndex(4))),
+            Section::SectionSwitch(2),
+            Section::Code(Code::new(vec![0; 0x20]).unwrap()),
+            Section::XDEF(XDEF::new(3, 1, 0, "main".into())),
+            Section::XREF(XREF::new(4, "printf".into())),
+            Section::NOP,
+        ])
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let code = vec![
+            0x00, 0x00, 0x04, 0x3C, // lui $a0, %hi(.data+0x10)
+            0x00, 0x00, 0x84, 0x24, // addiu $a0, $a0, %lo(.data+0x10)
+            0x00, 0x00, 0x00, 0x0C, // jal printf
+            0x00, 0x00, 0x00, 0x00, // nop
+        ];
+        let fingerprints = fingerprints(&obj(code.clone())).unwrap();
+        assert_eq!(1, fingerprints.len());
+        let fingerprint = &fingerprints[0];
+        assert_eq!("main", fingerprint.name());
+        assert_eq!(".text", fingerprint.section());
+        assert_eq!(16, fingerprint.size());
+        assert_eq!(code, fingerprint.data());
+        assert_eq!(
+            [0, 0, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 0, 0, 0, 0xFC, 0xFF, 0xFF, 0xFF, 0xFF],
+            fingerprint.mask()
+        );
+        assert_eq!(vec![".data", "printf"], fingerprint.symbols());
+
+        let references = fingerprint.references();
+        assert_eq!(3, references.len());
+        assert_eq!(Some(".data"), references[0].symbol());
+        assert!(references[0].is_section());
+        assert_eq!(0x10, references[0].addend());
+        assert_eq!(8, references[2].offset());
+        assert_eq!(mips::JUMP, references[2].tag());
+        assert_eq!(Some("printf"), references[2].symbol());
+        assert!(!references[2].is_section());
+
+        // linked code
+        let linked = [
+            0x01, 0x80, 0x04, 0x3C, 0x10, 0x00, 0x84, 0x24, 0x40, 0x00, 0x00, 0x0C, 0, 0, 0, 0,
+        ];
+        assert!(fingerprint.matches(&linked));
+        assert!(!fingerprint.matches(&linked[..12]));
+        let mut different = linked;
+        different[12] = 1;
+        assert!(!fingerprint.matches(&different));
+
+        // the hash ignores relocated fields
+        let mut relocated = code.clone();
+        relocated[0] = 0x01;
+        relocated[8] = 0x40;
+        let other = &super::fingerprints(&obj(relocated.clone())).unwrap()[0];
+        assert_eq!(fingerprint.hash(), other.hash());
+        relocated[12] = 1;
+        let other = &super::fingerprints(&obj(relocated)).unwrap()[0];
+        assert_ne!(fingerprint.hash(), other.hash());
+    }
+
+    #[test]
+    fn test_json() {
+        let fingerprints = fingerprints(&obj(vec![0; 16])).unwrap();
+        let json = serde_json::to_value(&fingerprints[0]).unwrap();
+        assert_eq!("main", json["name"]);
+        assert_eq!(16, json["size"]);
+        assert_eq!(format!("{:016x}", fingerprints[0].hash()), json["hash"]);
+        assert_eq!("0000ffff0000ffff000000fcffffffff", json["mask"]);
+        assert_eq!("printf", json["symbols"][1]);
+        assert_eq!(74, json["references"][2]["type"]);
+        assert_eq!(16, json["references"][0]["addend"]);
+
+        let fingerprint: Fingerprint = serde_json::from_value(json).unwrap();
+        assert_eq!(fingerprints[0], fingerprint);
+    }
+}
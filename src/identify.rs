@@ -0,0 +1,465 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Finding the functions of [LIB]s in linked MIPS code.
+//!
+//! An [Identifier] searches the code of an executable or memory dump for the
+//! [Fingerprint]s of the functions in the objects added to it. When a function matches, the
+//! patched fields of its code give the addresses of the symbols it refers to: jump targets,
+//! `%hi`/`%lo` pairs, and words. The result is a [SymbolMap] of the functions found and the
+//! symbols they refer to. A symbol found at more than one address, or an address matched by
+//! functions with different names, is reported as a [Conflict] rather than listed.
+//!
+//! Functions with fewer than four words of code unchanged by patches are not searched for
+//! because they match too much unrelated code.
+//!
+//! ```
+//! use psyk::identify::Identifier;
+//! use psyk::{Code, LNKHeader, Section, OBJ, XDEF};
+//!
+//! // addiu $v0, $zero, 1; addiu $v1, $zero, 2; addu $v0, $v0, $v1; jr $ra; nop
+//! let code = vec![
+//!     0x01, 0x00, 0x02, 0x24, 0x02, 0x00, 0x03, 0x24, 0x21, 0x10, 0x43, 0x00,
+//!     0x08, 0x00, 0xE0, 0x03, 0x00, 0x00, 0x00, 0x00,
+//! ];
+//! let obj = OBJ::new(vec![
+//!     Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+//!     Section::SectionSwitch(1),
+//!     Section::Code(Code::new(code.clone()).unwrap()),
+//!     Section::XDEF(XDEF::new(2, 1, 0, "three".into())),
+//!     Section::NOP,
+//! ]);
+//! let mut identifier = Identifier::new();
+//! identifier.add("THREE", &obj).unwrap();
+//!
+//! let mut memory = vec![0; 0x100];
+//! memory.extend(code);
+//! let map = identifier.identify(&memory, 0x8001_0000);
+//! assert_eq!("three", map.symbols()[0].name());
+//! assert_eq!(0x8001_0100, map.symbols()[0].address());
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+
+use crate::fingerprint::{Fingerprint, Reference};
+use crate::patch::mips;
+use crate::{LIB, OBJ};
+
+/// The fewest words of a function's code unchanged by patches for it to be searched for.
+const MINIMUM_WORDS: usize = 4;
+
+/// A function or symbol found in linked code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapSymbol {
+    name: String,
+    address: u32,
+    size: Option<u32>,
+    module: Option<String>,
+}
+
+impl MapSymbol {
+    /// Returns the name of the symbol.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the address of the symbol.
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    /// Returns the size of a function, or `None` for a symbol found through a reference.
+    pub fn size(&self) -> Option<u32> {
+        self.size
+    }
+
+    /// Returns the name of the module a function was found from, or `None` for a symbol found
+    /// through a reference.
+    pub fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+}
+
+/// Conflicting results which are left out of a [SymbolMap].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Conflict {
+    /// A symbol was found at more than one address.
+    Addresses { name: String, addresses: Vec<u32> },
+    /// Functions with different names matched at the same address.
+    Names { address: u32, names: Vec<String> },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Addresses { name, addresses } => {
+                let addresses: Vec<String> = addresses.iter().map(|a| format!("{a:08X}")).collect();
+                write!(f, "{name} found at {}", addresses.join(", "))
+            }
+            Self::Names { address, names } => {
+                write!(f, "{address:08X} matched by {}", names.join(", "))
+            }
+        }
+    }
+}
+
+/// The functions and symbols found in linked code.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SymbolMap {
+    symbols: Vec<MapSymbol>,
+    conflicts: Vec<Conflict>,
+}
+
+impl SymbolMap {
+    /// Returns the symbols found ordered by address and name.
+    pub fn symbols(&self) -> &[MapSymbol] {
+        &self.symbols
+    }
+
+    /// Returns the conflicting results.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+}
+
+impl fmt::Display for SymbolMap {
+    /// Writes a symbol map followed by any conflicts.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "  Address   Size      Module    Name")?;
+        for symbol in &self.symbols {
+            let size = symbol.size.map_or_else(String::new, |s| format!("{s:08X}"));
+            writeln!(
+                f,
+                "  {:08X}  {size:8}  {:8}  {}",
+                symbol.address,
+                symbol.module().unwrap_or(""),
+                symbol.name
+            )?;
+        }
+        if !self.conflicts.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "  Conflicts")?;
+            for conflict in &self.conflicts {
+                writeln!(f, "  {conflict}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Searches linked code for the functions of objects.
+#[derive(Clone, Debug, Default)]
+pub struct Identifier {
+    // module name and fingerprint
+    functions: Vec<(String, Fingerprint)>,
+    // first word of a function unchanged by patches -> function and the word's offset
+    anchors: HashMap<u32, Vec<(usize, usize)>>,
+}
+
+impl Identifier {
+    /// Creates an identifier without any functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the functions of every module of `lib`.
+    pub fn add_lib(&mut self, lib: &LIB) -> Result<()> {
+        for module in lib.modules() {
+            self.add(&module.name(), module.object())
+                .map_err(|e| anyhow!("{}: {e}", module.name()))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the functions of `obj`, which is identified in results as `module`.
+    pub fn add(&mut self, module: &str, obj: &OBJ) -> Result<()> {
+        self.add_fingerprints(module, crate::fingerprint::fingerprints(obj)?);
+        Ok(())
+    }
+
+    /// Adds the functions with `fingerprints`, which are identified in results as `module`.
+    pub fn add_fingerprints(&mut self, module: &str, fingerprints: Vec<Fingerprint>) {
+        for fingerprint in fingerprints {
+            let words: Vec<(usize, u32)> = fingerprint
+                .mask()
+                .chunks_exact(4)
+                .enumerate()
+                .filter(|(_, mask)| *mask == [0xFF; 4])
+                .map(|(i, _)| (i * 4, word(fingerprint.data(), i * 4)))
+                .collect();
+            if words.len() < MINIMUM_WORDS {
+                continue;
+            }
+            let (offset, anchor) = words[0];
+            self.anchors
+                .entry(anchor)
+                .or_default()
+                .push((self.functions.len(), offset));
+            self.functions.push((module.into(), fingerprint));
+        }
+    }
+
+    /// Searches `code`, which is loaded at `address`, for the functions added to the
+    /// identifier.
+    pub fn identify(&self, code: &[u8], address: u32) -> SymbolMap {
+        // start offset and function
+        let mut matches: Vec<(usize, usize)> = Vec::new();
+        for position in (0..code.len().saturating_sub(3)).step_by(4) {
+            let Some(candidates) = self.anchors.get(&word(code, position)) else {
+                continue;
+            };
+            for (function, offset) in candidates {
+                let Some(start) = position.checked_sub(*offset) else {
+                    continue;
+                };
+                if self.functions[*function].1.matches(&code[start..]) {
+                    matches.push((start, *function));
+                }
+            }
+        }
+
+        // matches inside of a larger match are coincidences
+        let size = |function: usize| self.functions[function].1.size() as usize;
+        let mut contained = vec![false; matches.len()];
+        for (i, (start, function)) in matches.iter().enumerate() {
+            let end = start + size(*function);
+            contained[i] = matches.iter().any(|(other, other_function)| {
+                other < start && end <= other + size(*other_function)
+            });
+        }
+
+        let mut addresses: BTreeMap<String, BTreeSet<u32>> = BTreeMap::new();
+        let mut functions: BTreeMap<(String, u32), (u32, String)> = BTreeMap::new();
+        let mut names: BTreeMap<u32, BTreeSet<String>> = BTreeMap::new();
+        for (&(start, function), _) in matches.iter().zip(contained).filter(|(_, c)| !c) {
+            let (module, fingerprint) = &self.functions[function];
+            let function_address = address.wrapping_add(start as u32);
+            let name = fingerprint.name().to_string();
+            addresses
+                .entry(name.clone())
+                .or_default()
+                .insert(function_address);
+            names
+                .entry(function_address)
+                .or_default()
+                .insert(name.clone());
+            functions.insert(
+                (name, function_address),
+                (fingerprint.size(), module.clone()),
+            );
+            for (name, symbol_address) in resolve(fingerprint, &code[start..], function_address) {
+                addresses.entry(name).or_default().insert(symbol_address);
+            }
+        }
+
+        let mut map = SymbolMap::default();
+        let mut conflicted: BTreeSet<String> = BTreeSet::new();
+        for (address, names) in names.iter().filter(|(_, names)| names.len() > 1) {
+            conflicted.extend(names.iter().cloned());
+            map.conflicts.push(Conflict::Names {
+                address: *address,
+                names: names.iter().cloned().collect(),
+            });
+        }
+        for (name, found) in addresses {
+            if found.len() > 1 {
+                map.conflicts.push(Conflict::Addresses {
+                    name,
+                    addresses: found.into_iter().collect(),
+                });
+            } else if !conflicted.contains(&name) {
+                let address = found.into_iter().next().expect("address");
+                let function = functions.get(&(name.clone(), address));
+                map.symbols.push(MapSymbol {
+                    name,
+                    address,
+                    size: function.map(|(size, _)| *size),
+                    module: function.map(|(_, module)| module.clone()),
+                });
+            }
+        }
+        map.symbols
+            .sort_by(|a, b| (a.address, &a.name).cmp(&(b.address, &b.name)));
+        map
+    }
+}
+
+fn word(code: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(code[offset..offset + 4].try_into().expect("4 bytes"))
+}
+
+/// Returns the addresses of the symbols referred to by `fingerprint`, which matched `code`
+/// at `address`.
+fn resolve(fingerprint: &Fingerprint, code: &[u8], address: u32) -> Vec<(String, u32)> {
+    let symbol = |reference: &Reference| {
+        reference
+            .symbol()
+            .filter(|_| !reference.is_section())
+            .map(str::to_string)
+    };
+    let references = fingerprint.references();
+    let mut resolved = Vec::new();
+    for (i, reference) in references.iter().enumerate() {
+        let Some(name) = symbol(reference) else {
+            continue;
+        };
+        let offset = reference.offset() as usize;
+        if offset + 4 > code.len() {
+            continue;
+        }
+        let instruction = word(code, offset);
+        let value = match reference.tag() {
+            mips::JUMP => {
+                let pc = address.wrapping_add(reference.offset()).wrapping_add(4);
+                (pc & 0xF000_0000) | ((instruction & 0x03FF_FFFF) << 2)
+            }
+            mips::WORD => instruction,
+            mips::HI16 => {
+                // the low half is added by the next `%lo` of the same address
+                let Some(lo) = references[i + 1..].iter().find(|lo| {
+                    lo.tag() == mips::LO16
+                        && symbol(lo).as_ref() == Some(&name)
+                        && lo.addend() == reference.addend()
+                }) else {
+                    continue;
+                };
+                if lo.offset() as usize + 4 > code.len() {
+                    continue;
+                }
+                let low = word(code, lo.offset() as usize) as u16 as i16;
+                (instruction << 16).wrapping_add_signed(i32::from(low))
+            }
+            _ => continue,
+        };
+        resolved.push((name, value.wrapping_sub(reference.addend() as u32)));
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Expression, LNKHeader, Patch, Section, XDEF, XREF};
+
+    // addiu $sp, $sp, -24; sw $ra, 16($sp); lui $a0, %hi(message+4);
+    // jal printf; addiu $a0, $a0, %lo(message+4); lw $ra, 16($sp); jr $ra;
+    // addiu $sp, $sp, 24
+    const HELLO: [u8; 32] = [
+        0xE8, 0xFF, 0xBD, 0x27, 0x10, 0x00, 0xBF, 0xAF, 0x00, 0x00, 0x04, 0x3C, 0x00, 0x00, 0x00,
+        0x0C, 0x00, 0x00, 0x84, 0x24, 0x10, 0x00, 0xBF, 0x8F, 0x08, 0x00, 0xE0, 0x03, 0x18, 0x00,
+        0xBD, 0x27,
+    ];
+
+    fn hello(name: &str) -> OBJ {
+        let message = || {
+            Expression::Add(
+                Box::new(Expression::Constant(4)),
+                Box::new(Expression::SymbolAddressIndex(4)),
+            )
+        };
+        OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(HELLO.to_vec()).unwrap()),
+            Section::Patch(Patch::new(mips::HI16, 8, message())),
+            Section::Patch(Patch::new(
+                mips::JUMP,
+                12,
+                Expression::SymbolAddressIndex(3),
+            )),
+            Section::Patch(Patch::new(mips::LO16, 16, message())),
+            Section::XDEF(XDEF::new(2, 1, 0, name.into())),
+            Section::XREF(XREF::new(3, "printf".into())),
+            Section::XREF(XREF::new(4, "message".into())),
+            Section::NOP,
+        ])
+    }
+
+    /// `HELLO` linked at `0x80010010` with `printf` at `0x80012340` and `message` at
+    /// `0x8001FFF0`.
+    fn linked() -> Vec<u8> {
+        let mut code = HELLO.to_vec();
+        code[8..10].copy_from_slice(&0x8002u16.to_le_bytes());
+        code[12..16].copy_from_slice(&(0x0C00_0000u32 | (0x12340 >> 2)).to_le_bytes());
+        code[16..18].copy_from_slice(&0xFFF4u16.to_le_bytes());
+        let mut memory = vec![0; 0x10];
+        memory.extend(code);
+        memory.extend([0; 8]);
+        memory
+    }
+
+    #[test]
+    fn test_identify() {
+        let mut identifier = Identifier::new();
+        identifier.add("HELLO", &hello("hello")).unwrap();
+        let map = identifier.identify(&linked(), 0x8001_0000);
+        assert!(map.conflicts().is_empty());
+        let symbols: Vec<(&str, u32, Option<u32>, Option<&str>)> = map
+            .symbols()
+            .iter()
+            .map(|s| (s.name(), s.address(), s.size(), s.module()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("hello", 0x8001_0010, Some(32), Some("HELLO")),
+                ("printf", 0x8001_2340, None, None),
+                ("message", 0x8001_FFF0, None, None),
+            ],
+            symbols
+        );
+        assert_eq!(
+            "  Address   Size      Module    Name\n\
+            \x20 80010010  00000020  HELLO     hello\n\
+            \x20 80012340                      printf\n\
+            \x20 8001FFF0                      message\n",
+            map.to_string()
+        );
+
+        // too small to search for
+        let mut identifier = Identifier::new();
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(HELLO[24..].to_vec()).unwrap()),
+            Section::XDEF(XDEF::new(2, 1, 0, "tiny".into())),
+            Section::NOP,
+        ]);
+        identifier.add("TINY", &obj).unwrap();
+        assert!(identifier
+            .identify(&linked(), 0x8001_0000)
+            .symbols()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        // the same code with different names
+        let mut identifier = Identifier::new();
+        identifier.add("HELLO", &hello("hello")).unwrap();
+        identifier.add("GREET", &hello("greet")).unwrap();
+        let map = identifier.identify(&linked(), 0x8001_0000);
+        assert_eq!(
+            vec![Conflict::Names {
+                address: 0x8001_0010,
+                names: vec!["greet".into(), "hello".into()],
+            }],
+            map.conflicts()
+        );
+        assert_eq!(2, map.symbols().len());
+
+        // found twice
+        let mut identifier = Identifier::new();
+        identifier.add("HELLO", &hello("hello")).unwrap();
+        let mut memory = linked();
+        memory.extend(linked());
+        let map = identifier.identify(&memory, 0x8001_0000);
+        assert_eq!(
+            "hello found at 80010010, 80010048",
+            map.conflicts()[0].to_string()
+        );
+        assert!(map.symbols().iter().all(|s| s.name() != "hello"));
+    }
+}
@@ -6,6 +6,9 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+use crate::cpe::CPE;
+use crate::elf::ELF;
+use crate::exe::EXE;
 use crate::{display, LIB, OBJ};
 use anyhow::{bail, Result};
 use binrw::io::Cursor;
@@ -15,6 +18,8 @@ use binrw::{meta::ReadMagic, BinRead, BinWrite};
 pub enum Type {
     OBJ(OBJ),
     LIB(LIB),
+    EXE(EXE),
+    CPE(CPE),
 }
 
 impl Display for Type {
@@ -22,6 +27,8 @@ impl Display for Type {
         match self {
             Self::OBJ(obj) => obj as &dyn Display,
             Self::LIB(lib) => lib as &dyn Display,
+            Self::EXE(exe) => exe as &dyn Display,
+            Self::CPE(cpe) => cpe as &dyn Display,
         }
         .fmt(f)
     }
@@ -32,6 +39,8 @@ impl display::DisplayWithOptions for Type {
         match self {
             Self::OBJ(obj) => obj as &dyn display::DisplayWithOptions,
             Self::LIB(lib) => lib as &dyn display::DisplayWithOptions,
+            Self::EXE(exe) => exe as &dyn display::DisplayWithOptions,
+            Self::CPE(cpe) => cpe as &dyn display::DisplayWithOptions,
         }
         .fmt_with_options(f, options)
     }
@@ -45,8 +54,8 @@ pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
     Ok(std::fs::read(path)?)
 }
 
-/// Reads a Psy-Q [LIB] or [OBJ]. If the file cannot be found or if the file
-/// does not contain valid data an error will be returned.
+/// Reads a Psy-Q [LIB], [OBJ], [CPE], or a PlayStation [EXE]. If the file cannot
+/// be found or if the file does not contain valid data an error will be returned.
 pub fn read(lib_or_obj_path: &Path) -> Result<Type> {
     let bytes = read_bytes(lib_or_obj_path)?;
 
@@ -58,6 +67,13 @@ pub fn read(lib_or_obj_path: &Path) -> Result<Type> {
     magic.clone_from_slice(&bytes[0..3]);
     let mut data = Cursor::new(&bytes);
 
+    if bytes.starts_with(&EXE::MAGIC) {
+        return Ok(Type::EXE(EXE::read(&mut data)?));
+    }
+    if bytes.starts_with(&CPE::MAGIC) {
+        return Ok(Type::CPE(CPE::read(&mut data)?));
+    }
+
     match magic {
         LIB::MAGIC => Ok(Type::LIB(LIB::read(&mut data)?)),
         OBJ::MAGIC => Ok(Type::OBJ(OBJ::read(&mut data)?)),
@@ -81,6 +97,30 @@ pub fn read_lib(lib_path: &Path) -> Result<LIB> {
     Ok(LIB::read(&mut data)?)
 }
 
+/// Reads a PlayStation [EXE]. If the file cannot be found or if the file
+/// does not contain valid data an error will be returned.
+pub fn read_exe(exe_path: &Path) -> Result<EXE> {
+    let bytes = read_bytes(exe_path)?;
+    let mut data = Cursor::new(&bytes);
+    Ok(EXE::read(&mut data)?)
+}
+
+/// Reads a `psylink` [CPE]. If the file cannot be found or if the file
+/// does not contain valid data an error will be returned.
+pub fn read_cpe(cpe_path: &Path) -> Result<CPE> {
+    let bytes = read_bytes(cpe_path)?;
+    let mut data = Cursor::new(&bytes);
+    Ok(CPE::read(&mut data)?)
+}
+
+/// Reads an [ELF] relocatable object. If the file cannot be found or if the file
+/// does not contain a valid 32-bit little-endian ELF object an error will be returned.
+pub fn read_elf(elf_path: &Path) -> Result<ELF> {
+    let bytes = read_bytes(elf_path)?;
+    let mut data = Cursor::new(&bytes);
+    Ok(ELF::read(&mut data)?)
+}
+
 /// Writes a Psy-Q [OBJ]. If the file cannot be written an error will
 /// be returned.
 pub fn write_obj(obj: &OBJ, file: &mut File) -> Result<()> {
@@ -100,3 +140,33 @@ pub fn write_lib(lib: &LIB, file: &mut File) -> Result<()> {
     file.write_all(&gen)?;
     Ok(())
 }
+
+/// Writes a PlayStation [EXE]. If the file cannot be written an error will
+/// be returned.
+pub fn write_exe(exe: &EXE, file: &mut File) -> Result<()> {
+    let mut writer = Cursor::new(Vec::new());
+    exe.write(&mut writer)?;
+    let gen = writer.into_inner();
+    file.write_all(&gen)?;
+    Ok(())
+}
+
+/// Writes a `psylink` [CPE]. If the file cannot be written an error will
+/// be returned.
+pub fn write_cpe(cpe: &CPE, file: &mut File) -> Result<()> {
+    let mut writer = Cursor::new(Vec::new());
+    cpe.write(&mut writer)?;
+    let gen = writer.into_inner();
+    file.write_all(&gen)?;
+    Ok(())
+}
+
+/// Writes an [ELF] relocatable object. If the file cannot be written an error will
+/// be returned.
+pub fn write_elf(elf: &ELF, file: &mut File) -> Result<()> {
+    let mut writer = Cursor::new(Vec::new());
+    elf.write(&mut writer)?;
+    let gen = writer.into_inner();
+    file.write_all(&gen)?;
+    Ok(())
+}
@@ -53,26 +53,39 @@
 //! ```
 
 use core::cmp;
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
 use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use binrw::binrw;
 use binrw::helpers::{until, until_eof};
 use chrono::{
     DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
 };
-use rabbitizer::{InstrCategory, Instruction};
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::display::DisplayWithOptions;
 
+pub mod arch;
+pub mod asm;
 pub mod cli;
+pub mod cpe;
+pub mod disasm;
 pub mod display;
+pub mod elf;
+pub mod eval;
+pub mod exe;
+pub mod fingerprint;
+pub mod identify;
 pub mod io;
 pub mod link;
+pub mod model;
+pub mod patch;
+pub mod sdk;
+pub mod types;
 
 /// A [LIB] is an archive of several [OBJ] files. It consists
 /// of a magic number followed by one or more [Modules](Module).
@@ -766,8 +779,35 @@ impl display::DisplayWithOptions for OBJ {
     fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
         options.write_indent(f)?;
         writeln!(f, "Header : LNK version {}", self.version)?;
-        for section in &self.sections {
-            section.fmt_with_options(f, options)?;
+        let mut listing = disasm::Listing::new(self);
+        for (i, section) in self.sections.iter().enumerate() {
+            match (section, &options.code_format) {
+                (Section::Code(code), display::CodeFormat::Disassembly) => {
+                    // the patches that follow the code relocate it
+                    let patches = self.sections[i + 1..]
+                        .iter()
+                        .map_while(|s| match s {
+                            Section::Patch(patch) => Some(patch),
+                            _ => None,
+                        })
+                        .collect::<Vec<&Patch>>();
+                    options.write_indent(f)?;
+                    writeln!(f, "2 : Code {} bytes\n", code.code.len())?;
+                    listing.write_code(f, options, code, &patches)?;
+                }
+                (Section::Patch(patch), _) if options.symbolic => {
+                    options.write_indent(f)?;
+                    write!(
+                        f,
+                        "10 : Patch type {} at offset {:x} with {}",
+                        patch.tag,
+                        patch.offset,
+                        patch.expression.symbolic(listing.names())
+                    )?;
+                }
+                _ => section.fmt_with_options(f, options)?,
+            }
+            listing.advance(section);
             writeln!(f)?;
         }
         Ok(())
@@ -794,6 +834,15 @@ pub struct Code {
 }
 
 impl Code {
+    /// Creates a new code section from raw bytes, or returns an error if there are more than
+    /// 65535 of them.
+    pub fn new(code: Vec<u8>) -> Result<Self> {
+        let Ok(size) = u16::try_from(code.len()) else {
+            bail!("{} bytes of code do not fit in a code section", code.len());
+        };
+        Ok(Self { size, code })
+    }
+
     /// Returns the code for this section as bytes. Their format can be determined by the value
     /// set in the [CPU](Section::CPU).
     pub fn code(&self) -> &Vec<u8> {
@@ -1373,60 +1422,104 @@ pub enum Expression {
     ArshiftChk(Box<Expression>, Box<Expression>),
 }
 
-impl fmt::Display for Expression {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Expression {
+    /// Returns a [Display](fmt::Display) of the expression which names the symbols, sections,
+    /// and groups it refers to, e.g. `[14 InitGeom]` and `sectbase(.text)`. Numbers missing
+    /// from `names` are written as they are by the expression's `Display`.
+    pub fn symbolic<'a>(&'a self, names: &'a HashMap<u16, String>) -> SymbolicExpression<'a> {
+        SymbolicExpression {
+            expression: self,
+            names,
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, names: Option<&HashMap<u16, String>>) -> fmt::Result {
+        let name = |number: &u16| names.and_then(|names| names.get(number));
+        let number = |number: &u16| match name(number) {
+            Some(name) => name.clone(),
+            None => format!("{number:x}"),
+        };
+        let binary = |f: &mut fmt::Formatter, lhs: &Self, operator: &str, rhs: &Self| {
+            write!(f, "(")?;
+            lhs.write(f, names)?;
+            write!(f, "{operator}")?;
+            rhs.write(f, names)?;
+            write!(f, ")")
+        };
         match self {
             Self::Constant(value) => write!(f, "${value:x}"),
-            Self::SymbolAddressIndex(addr) => write!(f, "[{addr:x}]"),
-            Self::SectionAddressIndex(base) => write!(f, "sectbase({base:x})"),
+            Self::SymbolAddressIndex(addr) => match name(addr) {
+                Some(name) => write!(f, "[{addr:x} {name}]"),
+                None => write!(f, "[{addr:x}]"),
+            },
+            Self::SectionAddressIndex(base) => write!(f, "sectbase({})", number(base)),
             // untested
-            Self::Bank(bank) => write!(f, "bank({bank:x})"),
+            Self::Bank(bank) => write!(f, "bank({})", number(bank)),
             // untested
-            Self::SectionOffset(section) => write!(f, "sectof({section:x})"),
+            Self::SectionOffset(section) => write!(f, "sectof({})", number(section)),
             // untested
-            Self::Offset(bank) => write!(f, "offs({bank:x})"),
-            Self::SectionStart(offset) => write!(f, "sectstart({offset:x})"),
+            Self::Offset(bank) => write!(f, "offs({})", number(bank)),
+            Self::SectionStart(offset) => write!(f, "sectstart({})", number(offset)),
             // untested
-            Self::GroupStart(group) => write!(f, "groupstart({group:x})"),
+            Self::GroupStart(group) => write!(f, "groupstart({})", number(group)),
             // untested
-            Self::GroupOffset(group) => write!(f, "groupof({group:x})"),
+            Self::GroupOffset(group) => write!(f, "groupof({})", number(group)),
             // untested
-            Self::Segment(segment) => write!(f, "seg({segment:x})"),
+            Self::Segment(segment) => write!(f, "seg({})", number(segment)),
             // untested
-            Self::GroupOrg(group) => write!(f, "grouporg({group:x})"),
-            Self::SectionEnd(offset) => write!(f, "sectend({offset:x})"),
+            Self::GroupOrg(group) => write!(f, "grouporg({})", number(group)),
+            Self::SectionEnd(offset) => write!(f, "sectend({})", number(offset)),
 
             // comparison
-            Self::Equals(lhs, rhs) => write!(f, "({lhs}={rhs})"),
-            Self::NotEquals(lhs, rhs) => write!(f, "({lhs}<>{rhs})"),
-            Self::LTE(lhs, rhs) => write!(f, "({lhs}<={rhs})"),
-            Self::LessThan(lhs, rhs) => write!(f, "({lhs}<{rhs})"),
-            Self::GTE(lhs, rhs) => write!(f, "({lhs}>={rhs})"),
-            Self::GreaterThan(lhs, rhs) => write!(f, "({lhs}>{rhs})"),
+            Self::Equals(lhs, rhs) => binary(f, lhs, "=", rhs),
+            Self::NotEquals(lhs, rhs) => binary(f, lhs, "<>", rhs),
+            Self::LTE(lhs, rhs) => binary(f, lhs, "<=", rhs),
+            Self::LessThan(lhs, rhs) => binary(f, lhs, "<", rhs),
+            Self::GTE(lhs, rhs) => binary(f, lhs, ">=", rhs),
+            Self::GreaterThan(lhs, rhs) => binary(f, lhs, ">", rhs),
 
             // arithmatic
-            Self::Add(lhs, rhs) => write!(f, "({lhs}+{rhs})"),
-            Self::Subtract(lhs, rhs) => write!(f, "({lhs}-{rhs})"),
-            Self::Multiply(lhs, rhs) => write!(f, "({lhs}*{rhs})"),
-            Self::Divide(lhs, rhs) => write!(f, "({lhs}/{rhs})",),
-            Self::And(lhs, rhs) => write!(f, "({lhs}&{rhs})"),
-            Self::Or(lhs, rhs) => write!(f, "({lhs}!{rhs})"),
-            Self::XOR(lhs, rhs) => write!(f, "({lhs}^{rhs})"),
-            Self::LeftShift(lhs, rhs) => write!(f, "({lhs}<<{rhs})"),
-            Self::RightShift(lhs, rhs) => write!(f, "({lhs}>>{rhs})"),
-            Self::Mod(lhs, rhs) => write!(f, "({lhs}%%{rhs})"),
-            Self::Dashes(lhs, rhs) => write!(f, "({lhs}---{rhs})"),
+            Self::Add(lhs, rhs) => binary(f, lhs, "+", rhs),
+            Self::Subtract(lhs, rhs) => binary(f, lhs, "-", rhs),
+            Self::Multiply(lhs, rhs) => binary(f, lhs, "*", rhs),
+            Self::Divide(lhs, rhs) => binary(f, lhs, "/", rhs),
+            Self::And(lhs, rhs) => binary(f, lhs, "&", rhs),
+            Self::Or(lhs, rhs) => binary(f, lhs, "!", rhs),
+            Self::XOR(lhs, rhs) => binary(f, lhs, "^", rhs),
+            Self::LeftShift(lhs, rhs) => binary(f, lhs, "<<", rhs),
+            Self::RightShift(lhs, rhs) => binary(f, lhs, ">>", rhs),
+            Self::Mod(lhs, rhs) => binary(f, lhs, "%%", rhs),
+            Self::Dashes(lhs, rhs) => binary(f, lhs, "---", rhs),
 
             // keyword
-            Self::Revword(lhs, rhs) => write!(f, "({lhs}-revword-{rhs})"),
-            Self::Check0(lhs, rhs) => write!(f, "({lhs}-check0-{rhs})"),
-            Self::Check1(lhs, rhs) => write!(f, "({lhs}-check1-{rhs})"),
-            Self::BitRange(lhs, rhs) => write!(f, "({lhs}-bitrange-{rhs})"),
-            Self::ArshiftChk(lhs, rhs) => write!(f, "({lhs}-arshift_chk-{rhs})"),
+            Self::Revword(lhs, rhs) => binary(f, lhs, "-revword-", rhs),
+            Self::Check0(lhs, rhs) => binary(f, lhs, "-check0-", rhs),
+            Self::Check1(lhs, rhs) => binary(f, lhs, "-check1-", rhs),
+            Self::BitRange(lhs, rhs) => binary(f, lhs, "-bitrange-", rhs),
+            Self::ArshiftChk(lhs, rhs) => binary(f, lhs, "-arshift_chk-", rhs),
         }
     }
 }
 
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+/// An [Expression] displayed with the names of the symbols, sections, and groups it refers
+/// to. Created by [Expression::symbolic].
+pub struct SymbolicExpression<'a> {
+    expression: &'a Expression,
+    names: &'a HashMap<u16, String>,
+}
+
+impl fmt::Display for SymbolicExpression<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.expression.write(f, Some(self.names))
+    }
+}
+
 /// A relocation patch to be applied by the linker.
 ///
 /// Patches modify code or data at a specific offset using a calculated expression.
@@ -1435,14 +1528,17 @@ impl fmt::Display for Expression {
 ///
 /// | Tag | Description                                              | Expression             |
 /// |-----|----------------------------------------------------------|------------------------|
-/// | 8   | Write 32-bit expression value (big-endian?)              | ``                     |
-/// | 10  | Unknown                                                  |                        |
-/// | 16  | Write 32-bit expression value (little-endian?)           | ``                     |
-/// | 30  | Possibly related to register allocation.                 |                        |
-/// | 74  | Function symbol relocation (24-bit, little-endian).      | `[14]`                 |
+/// | 8   | Write 32-bit expression value (big-endian)               | ``                     |
+/// | 10  | Unknown for MIPS. SH-2 8-bit displacement.               |                        |
+/// | 16  | Write 32-bit expression value (little-endian)            | ``                     |
+/// | 30  | Unknown. Possibly a 16-bit `$gp` relative offset.        |                        |
+/// | 74  | Function symbol relocation (26-bit, little-endian).      | `[14]`                 |
 /// | 82  | Copy expression high 16-bytes into instruction low bytes | `($20+sectbase(f001))` |
 /// | 84  | Copy expression low 16-bytes into instruction low bytes  | `($20+sectbase(f001))` |
 ///
+/// Patches are applied with [Patch::apply]. Constants for the tags can be found in the
+/// [patch] module.
+///
 /// # Structure on Disk
 ///
 /// | Offset | Type         | Description                                              |
@@ -1462,6 +1558,32 @@ pub struct Patch {
     expression: Expression,
 }
 
+impl Patch {
+    pub fn new(tag: u8, offset: u16, expression: Expression) -> Self {
+        Self {
+            tag,
+            offset,
+            expression,
+        }
+    }
+
+    /// Returns the type of patch.
+    pub fn tag(&self) -> u8 {
+        self.tag
+    }
+
+    /// Returns the offset of the patch relative to the start of the preceding
+    /// [Code](Section::Code) in the current section.
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Returns the expression used to calculate the patch value.
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+}
+
 /// Section header information.
 ///
 /// Defines properties of a section such as its group, alignment, and type name.
@@ -1489,6 +1611,33 @@ pub struct LNKHeader {
 }
 
 impl LNKHeader {
+    pub fn new(section: u16, group: u16, align: u8, type_name: String) -> Self {
+        let mut type_name = type_name.into_bytes();
+        type_name.truncate(u8::MAX.into());
+        Self {
+            section,
+            group,
+            align,
+            type_name_size: type_name.len() as u8,
+            type_name,
+        }
+    }
+
+    /// Returns the section ID.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the group ID, or 0 if the section does not belong to a group.
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns the alignment of the section in bytes.
+    pub fn align(&self) -> u8 {
+        self.align
+    }
+
     /// Returns the section type name (e.g., ".text", ".data", ".bss").
     pub fn type_name(&self) -> String {
         String::from_utf8_lossy(&self.type_name).into_owned()
@@ -1533,6 +1682,27 @@ pub struct LocalSymbol {
 }
 
 impl LocalSymbol {
+    pub fn new(section: u16, offset: u32, name: String) -> Self {
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            section,
+            offset,
+            name_size: name.len() as u8,
+            name,
+        }
+    }
+
+    /// Returns the section ID the symbol is defined in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the symbol from the start of its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1575,6 +1745,25 @@ pub struct GroupSymbol {
 }
 
 impl GroupSymbol {
+    pub fn new(number: u16, sym_type: u8, name: String) -> Self {
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            number,
+            sym_type,
+            name_size: name.len() as u8,
+            name,
+        }
+    }
+
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    pub fn sym_type(&self) -> u8 {
+        self.sym_type
+    }
+
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1603,7 +1792,7 @@ impl fmt::Debug for GroupSymbol {
 /// |--------|--------|-----------------------------------------|
 /// | 0      | `u16`  | Number.                                 |
 /// | 2      | `u16`  | Section ID.                             |
-/// | 4      | `u32`  | Offset (TODO: relative to what?)        |
+/// | 4      | `u32`  | Offset from the start of the section.   |
 /// | 8      | `u8`   | Size of the symbol name string.         |
 /// | 9      | `[u8]` | The symbol name. Not `NULL` terminated. |
 #[binrw]
@@ -1620,6 +1809,32 @@ pub struct XDEF {
 }
 
 impl XDEF {
+    pub fn new(number: u16, section: u16, offset: u32, symbol_name: String) -> Self {
+        let mut symbol_name = symbol_name.into_bytes();
+        symbol_name.truncate(u8::MAX.into());
+        Self {
+            number,
+            section,
+            offset,
+            symbol_name_size: symbol_name.len() as u8,
+            symbol_name,
+        }
+    }
+
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Returns the section ID the symbol is defined in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the symbol from the start of its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
     pub fn symbol_name(&self) -> String {
         // TODO: can a starred symbol be here as well?
         String::from_utf8_lossy(&self.symbol_name).into_owned()
@@ -1650,6 +1865,20 @@ pub struct XREF {
 }
 
 impl XREF {
+    pub fn new(number: u16, symbol_name: String) -> Self {
+        let mut symbol_name = symbol_name.into_bytes();
+        symbol_name.truncate(u8::MAX.into());
+        Self {
+            number,
+            symbol_name_size: symbol_name.len() as u8,
+            symbol_name,
+        }
+    }
+
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
     pub fn symbol_name(&self) -> String {
         String::from_utf8_lossy(&self.symbol_name).into_owned()
     }
@@ -1675,6 +1904,16 @@ pub struct Filename {
 }
 
 impl Filename {
+    pub fn new(number: u16, name: String) -> Self {
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            number,
+            size: name.len() as u8,
+            name,
+        }
+    }
+
     pub fn number(&self) -> u16 {
         self.number
     }
@@ -1699,10 +1938,13 @@ impl fmt::Debug for Filename {
 ///
 /// # Structure on Disk
 ///
-/// | Offset | Type   | Description                                  |
-/// |--------|--------|----------------------------------------------|
-/// | 0      | `u16`  | Offset (TODO: relative to what?)             |
-/// | 2      | `u8`   | Value.                                       |
+/// | Offset | Type   | Description                                   |
+/// |--------|--------|-----------------------------------------------|
+/// | 0      | `u16`  | Offset in the current section.                |
+/// | 2      | `u8`   | Value. Bit 1 is the M flag, bit 0 the X flag. |
+///
+/// The M and X flags select 8-bit (set) or 16-bit (clear) accumulator and index registers of
+/// a [WDC 65816](cputype::WDC_65816) for the code following the offset.
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
@@ -1711,6 +1953,22 @@ pub struct SetMXInfo {
     value: u8,
 }
 
+impl SetMXInfo {
+    pub fn new(offset: u16, value: u8) -> Self {
+        Self { offset, value }
+    }
+
+    /// Returns the offset in the current section where the flags take effect.
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Returns the M flag in bit 1 and the X flag in bit 0.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+}
+
 /// External BSS (uninitialized data) symbol.
 ///
 /// # Structure on Disk
@@ -1736,6 +1994,32 @@ pub struct XBSS {
 }
 
 impl XBSS {
+    pub fn new(number: u16, section: u16, size: u32, name: String) -> Self {
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            number,
+            section,
+            size,
+            name_size: name.len() as u8,
+            name,
+        }
+    }
+
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Returns the section ID the uninitialized data is allocated in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the size of the uninitialized data in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1747,7 +2031,7 @@ impl XBSS {
 ///
 /// | Offset | Type   | Description                      |
 /// |--------|--------|----------------------------------|
-/// | 0      | `u16`  | Offset (TODO: relative to what?) |
+/// | 0      | `u16`  | Offset in the current section.   |
 /// | 2      | `u32`  | Line number.                     |
 #[binrw]
 #[brw(little)]
@@ -1757,13 +2041,29 @@ pub struct SetSLDLineNum {
     linenum: u32,
 }
 
+impl SetSLDLineNum {
+    pub fn new(offset: u16, linenum: u32) -> Self {
+        Self { offset, linenum }
+    }
+
+    /// Returns the offset in the current section where the line starts.
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Returns the line number.
+    pub fn line(&self) -> u32 {
+        self.linenum
+    }
+}
+
 /// Set source line debugger (SLD) line number with file reference.
 ///
 /// # Structure on Disk
 ///
 /// | Offset | Type   | Description                       |
 /// |--------|--------|-----------------------------------|
-/// | 0      | `u16`  | Offset (TODO: relative to what?). |
+/// | 0      | `u16`  | Offset in the current section.    |
 /// | 2      | `u32`  | Line number.                      |
 /// | 6      | `u16`  | File ID.                          |
 #[binrw]
@@ -1775,8 +2075,36 @@ pub struct SetSLDLineNumFile {
     file: u16,
 }
 
-/// **n.b.!** this is completely untested and based on
-/// assumptions from the output from `dumpobj`.
+impl SetSLDLineNumFile {
+    pub fn new(offset: u16, linenum: u32, file: u16) -> Self {
+        Self {
+            offset,
+            linenum,
+            file,
+        }
+    }
+
+    /// Returns the offset in the current section where the line starts.
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Returns the line number.
+    pub fn line(&self) -> u32 {
+        self.linenum
+    }
+
+    /// Returns the number of the [Filename] of the line.
+    pub fn file(&self) -> u16 {
+        self.file
+    }
+}
+
+/// A procedure call record (68).
+///
+/// **n.b.!** the fields of this and [ProcedureDefinition] are assumptions from the output of
+/// `dumpobj`. No SDK object with either record has been found to confirm the layout, so
+/// reading one fails rather than misreading the sections after it.
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
@@ -1785,8 +2113,7 @@ pub struct ProcedureCall {
     symbol: u16,
 }
 
-/// **n.b.!** this is completely untested and based on
-/// assumptions from the output from `dumpobj`.
+/// A procedure definition record (70). See [ProcedureCall] for why reading one fails.
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
@@ -1833,7 +2160,82 @@ pub struct FunctionStart {
 }
 
 impl FunctionStart {
-    /// Function end debug information.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        section: u16,
+        offset: u32,
+        file: u16,
+        linenum: u32,
+        frame_register: u16,
+        frame_size: u32,
+        return_pc_register: u16,
+        mask: u32,
+        mask_offset: i32,
+        name: String,
+    ) -> Self {
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            section,
+            offset,
+            file,
+            linenum,
+            frame_register,
+            frame_size,
+            return_pc_register,
+            mask,
+            mask_offset,
+            name_size: name.len() as u8,
+            name,
+        }
+    }
+
+    /// Returns the section ID the function is defined in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the function from the start of its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the ID of the source file the function is defined in.
+    pub fn file(&self) -> u16 {
+        self.file
+    }
+
+    /// Returns the line number the function starts on.
+    pub fn line(&self) -> u32 {
+        self.linenum
+    }
+
+    /// Returns the register used as the frame pointer.
+    pub fn frame_register(&self) -> u16 {
+        self.frame_register
+    }
+
+    /// Returns the size of the function's stack frame.
+    pub fn frame_size(&self) -> u32 {
+        self.frame_size
+    }
+
+    /// Returns the register holding the return address.
+    pub fn return_pc_register(&self) -> u16 {
+        self.return_pc_register
+    }
+
+    /// Returns the mask of registers saved by the function.
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Returns the offset of the saved registers in the stack frame.
+    pub fn mask_offset(&self) -> i32 {
+        self.mask_offset
+    }
+
+    /// Returns the function name.
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1857,6 +2259,31 @@ pub struct SectionOffsetLine {
     linenum: u32,
 }
 
+impl SectionOffsetLine {
+    pub fn new(section: u16, offset: u32, linenum: u32) -> Self {
+        Self {
+            section,
+            offset,
+            linenum,
+        }
+    }
+
+    /// Returns the section ID.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset from the start of the section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the line number.
+    pub fn line(&self) -> u32 {
+        self.linenum
+    }
+}
+
 /// Variable or type definition debug information.
 ///
 /// # Structure on Disk
@@ -1885,6 +2312,52 @@ pub struct Def {
 }
 
 impl Def {
+    pub fn new(
+        section: u16,
+        value: u32,
+        class: u16,
+        def_type: u16,
+        size: u32,
+        name: String,
+    ) -> Self {
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            section,
+            value,
+            class,
+            def_type,
+            size,
+            name_size: name.len() as u8,
+            name,
+        }
+    }
+
+    /// Returns the section ID.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the value, such as an address, offset, or register, depending on the class.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the storage class. See [types::StorageClass].
+    pub fn class(&self) -> u16 {
+        self.class
+    }
+
+    /// Returns the encoded type. See [types::Type].
+    pub fn def_type(&self) -> u16 {
+        self.def_type
+    }
+
+    /// Returns the size of the data in bytes, or in bits for a bit field.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
     /// Returns the definition name.
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
@@ -1900,10 +2373,10 @@ pub enum Dim {
     ///
     /// # Structure on Disk
     ///
-    /// | Offset | Type | Description |
-    /// |--------|------|-------------|
-    /// | 0      | `u8` | Magic: 0x0  |
-    #[br(magic = 0u16)]
+    /// | Offset | Type  | Description |
+    /// |--------|-------|-------------|
+    /// | 0      | `u16` | Magic: 0x0  |
+    #[brw(magic = 0u16)]
     None,
 
     /// Single dimension with size.
@@ -1913,9 +2386,36 @@ pub enum Dim {
     /// | Offset | Type  | Description |
     /// |--------|-------|-------------|
     /// | 0      | `u16` | Magic: 0x1  |
-    /// | 1      | `u32` | Size        |
-    #[br(magic = 1u16)]
+    /// | 2      | `u32` | Size        |
+    #[brw(magic = 1u16)]
     Value(u32),
+
+    /// More than one dimension, outermost first.
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type    | Description               |
+    /// |--------|---------|---------------------------|
+    /// | 0      | `u16`   | The number of dimensions. |
+    /// | 2      | `[u32]` | The size of each.         |
+    Values {
+        #[br(temp, assert(count > 1))]
+        #[bw(calc = values.len() as u16)]
+        count: u16,
+        #[br(count = count)]
+        values: Vec<u32>,
+    },
+}
+
+impl Dim {
+    /// Returns the size of each dimension, outermost first.
+    pub fn values(&self) -> Vec<u32> {
+        match self {
+            Self::None => Vec::new(),
+            Self::Value(value) => vec![*value],
+            Self::Values { values } => values.clone(),
+        }
+    }
 }
 
 impl fmt::Display for Dim {
@@ -1923,6 +2423,13 @@ impl fmt::Display for Dim {
         match self {
             Self::None => write!(f, "0"),
             Self::Value(v) => write!(f, "1 {v}"),
+            Self::Values { values } => {
+                write!(f, "{}", values.len())?;
+                for value in values {
+                    write!(f, " {value}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -1962,6 +2469,65 @@ pub struct Def2 {
 }
 
 impl Def2 {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        section: u16,
+        value: u32,
+        class: u16,
+        def_type: u16,
+        size: u32,
+        dims: Dim,
+        tag: String,
+        name: String,
+    ) -> Self {
+        let mut tag = tag.into_bytes();
+        tag.truncate(u8::MAX.into());
+        let mut name = name.into_bytes();
+        name.truncate(u8::MAX.into());
+        Self {
+            section,
+            value,
+            class,
+            def_type,
+            size,
+            dims,
+            tag_size: tag.len() as u8,
+            tag,
+            name_size: name.len() as u8,
+            name,
+        }
+    }
+
+    /// Returns the section ID.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the value, such as an address, offset, or register, depending on the class.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the storage class. See [types::StorageClass].
+    pub fn class(&self) -> u16 {
+        self.class
+    }
+
+    /// Returns the encoded type. See [types::Type].
+    pub fn def_type(&self) -> u16 {
+        self.def_type
+    }
+
+    /// Returns the size of the data in bytes, or in bits for a bit field.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the dimensions of an array.
+    pub fn dims(&self) -> &Dim {
+        &self.dims
+    }
+
     pub fn tag(&self) -> String {
         String::from_utf8_lossy(&self.tag).into_owned()
     }
@@ -2005,11 +2571,6 @@ pub mod cputype {
     pub const HITACHI_SH2: u8 = 8;
 }
 
-fn unimplemented(s: &str) -> bool {
-    eprintln!("Unimplemented: {s}");
-    false
-}
-
 /// A section within an OBJ file.
 ///
 /// Sections can contain code, data, relocations, symbols, or debug information.
@@ -2148,15 +2709,21 @@ pub enum Section {
     #[brw(magic(20u8))]
     GroupSymbol(GroupSymbol),
 
-    /// Untested
+    /// Sets the [Patch] type used for byte sized patches in big-endian objects.
+    ///
+    /// See [patch::Target].
     #[brw(magic(22u8))]
     ByteSizeRegister(u16),
 
-    /// Untested
+    /// Sets the [Patch] type used for word sized patches in big-endian objects.
+    ///
+    /// See [patch::Target].
     #[brw(magic(24u8))]
     WordSizeRegister(u16),
 
-    /// Untested
+    /// Sets the [Patch] type used for long sized patches in big-endian objects.
+    ///
+    /// See [patch::Target].
     #[brw(magic(26u8))]
     LongSizeRegister(u16),
 
@@ -2324,15 +2891,18 @@ pub enum Section {
     RepeatWord(u32),
 
     /// Untested
-    #[brw(magic(66u8), assert(unimplemented("ProcedureCall")))]
+    #[brw(magic(66u8))]
     RepeatLong(u32),
 
     /// Untested
-    #[brw(magic(68u8), assert(unimplemented("ProcedureCall")))]
+    #[brw(magic(68u8), assert(false, "unsupported ProcedureCall section (68)"))]
     ProcedureCall(ProcedureCall),
 
     /// Untested
-    #[brw(magic(70u8), assert(unimplemented("ProcedureDefinition")))]
+    #[brw(
+        magic(70u8),
+        assert(false, "unsupported ProcedureDefinition section (70)")
+    )]
     ProcedureDefinition(ProcedureDefinition),
 
     /// Untested
@@ -2441,21 +3011,7 @@ impl display::DisplayWithOptions for Section {
                 match options.code_format {
                     display::CodeFormat::Disassembly => {
                         writeln!(f, "\n")?;
-                        for instruction in code.code.chunks(4) {
-                            if instruction.len() == 4 {
-                                let ins = u32::from_le_bytes(instruction.try_into().unwrap());
-                                let asm = Instruction::new(ins, 0x80000000, InstrCategory::CPU)
-                                    .disassemble(None, 0);
-                                options.write_indent(f)?;
-                                writeln!(f, "    /* {ins:08x} */   {asm}")?;
-                            } else {
-                                write!(f, "    /* ")?;
-                                for byte in instruction {
-                                    write!(f, "{byte:02x}")?;
-                                }
-                                writeln!(f, " */ ; invalid")?;
-                            }
-                        }
+                        disasm::Listing::default().write_mips(f, options, code, &[])?;
                     }
                     display::CodeFormat::Hex => {
                         writeln!(f, "\n")?;
@@ -2560,7 +3116,12 @@ impl display::DisplayWithOptions for Section {
                 "44 : Set MX info at offset {:x} to {:x}",
                 set_mx_info.offset, set_mx_info.value,
             ),
-            Self::CPU(cpu) => write!(f, "46 : Processor type {}", { *cpu }),
+            Self::CPU(cpu) => match arch::for_cpu(*cpu) {
+                Some(architecture) => {
+                    write!(f, "46 : Processor type {cpu} ({})", architecture.name())
+                }
+                None => write!(f, "46 : Processor type {cpu}"),
+            },
             Self::XBSS(xbss) => write!(
                 f,
                 "48 : XBSS symbol number {:x} '{}' size {:x} in section {:x}",
@@ -2595,9 +3156,9 @@ impl display::DisplayWithOptions for Section {
             Self::RepeatLong(count) => write!(f, "66 : Repeat long {count} times"),
             Self::ProcedureCall(call) => write!(f, "68 : <<<<Unimplemented>>>> {:?}", call),
             Self::ProcedureDefinition(definition) => {
-                write!(f, "68 : <<<<Unimplemented>>>> {:?}", definition)
+                write!(f, "70 : <<<<Unimplemented>>>> {:?}", definition)
             }
-            Self::Repeat3Byte(count) => write!(f, "70 : Repeat 3-byte {count} times"),
+            Self::Repeat3Byte(count) => write!(f, "72 : Repeat 3-byte {count} times"),
             Self::FunctionStart(start) => write!(
                 f,
                 "74 : Function start :\n\
@@ -3163,6 +3724,28 @@ mod test {
         let _ = Section::read(&mut data).unwrap();
     }
 
+    #[test]
+    fn test_code() {
+        assert_eq!(0xFFFF, Code::new(vec![0; 0xFFFF]).unwrap().code().len());
+        assert_eq!(
+            "65536 bytes of code do not fit in a code section",
+            Code::new(vec![0; 0x10000]).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_cpu_display() {
+        assert_eq!(
+            "46 : Processor type 7 (MIPS R3000)",
+            Section::CPU(cputype::MIPS_R3000).to_string()
+        );
+        assert_eq!(
+            "46 : Processor type 8 (Hitachi SH-2)",
+            Section::CPU(cputype::HITACHI_SH2).to_string()
+        );
+        assert_eq!("46 : Processor type 12", Section::CPU(12).to_string());
+    }
+
     #[test]
     fn test_expression() {
         // ExpressionDefinition::{ // 0x0A
@@ -3189,6 +3772,37 @@ mod test {
         let _ = Section::read(&mut data).unwrap();
     }
 
+    #[test]
+    fn test_symbolic_expression() {
+        use Expression as E;
+        let names = HashMap::from([
+            (0x14, "InitGeom".to_string()),
+            (0xF001, ".text".to_string()),
+        ]);
+        let expression = E::Add(
+            Box::new(E::Constant(0x20)),
+            Box::new(E::SectionAddressIndex(0xF001)),
+        );
+        assert_eq!("($20+sectbase(f001))", expression.to_string());
+        assert_eq!(
+            "($20+sectbase(.text))",
+            expression.symbolic(&names).to_string()
+        );
+        assert_eq!(
+            "[14 InitGeom]",
+            E::SymbolAddressIndex(0x14).symbolic(&names).to_string()
+        );
+        // numbers without names are unchanged
+        let expression = E::Subtract(
+            Box::new(E::SectionStart(0xF001)),
+            Box::new(E::SymbolAddressIndex(0x15)),
+        );
+        assert_eq!(
+            "(sectstart(.text)-[15])",
+            expression.symbolic(&names).to_string()
+        );
+    }
+
     #[test]
     fn test_function_start() {
         let bytes = b"\
@@ -3223,11 +3837,66 @@ mod test {
         assert_eq!(def2.class, 102);
         assert_eq!(def2.def_type, 0);
         assert_eq!(def2.size, 4);
-        // assert_eq!(def2.dims, Dim::None);
+        assert_eq!(def2.dims, Dim::None);
         assert_eq!(def2.tag(), "_physadr");
         assert_eq!(def2.name(), ".eos");
     }
 
+    #[test]
+    fn test_repeat() {
+        for (bytes, section, text) in [
+            (
+                b"\x42\x03\x00\x00\x00".to_vec(),
+                Section::RepeatLong(3),
+                "66 : Repeat long 3 times",
+            ),
+            (
+                b"\x48\x03\x00\x00\x00".to_vec(),
+                Section::Repeat3Byte(3),
+                "72 : Repeat 3-byte 3 times",
+            ),
+        ] {
+            let read = Section::read(&mut Cursor::new(&bytes)).unwrap();
+            assert_eq!(section, read);
+            assert_eq!(text, read.to_string());
+            let mut written = Cursor::new(Vec::new());
+            read.write(&mut written).unwrap();
+            assert_eq!(bytes, written.into_inner());
+        }
+    }
+
+    #[test]
+    fn test_procedure_unconfirmed() {
+        // the layouts are unconfirmed, so the records are rejected
+        for (bytes, name) in [
+            (b"\x44\x08\x05\x00".to_vec(), "ProcedureCall section (68)"),
+            (b"\x46\x0A\x00".to_vec(), "ProcedureDefinition section (70)"),
+        ] {
+            let error = Section::read(&mut Cursor::new(&bytes)).unwrap_err();
+            assert!(format!("{error:?}").contains(name), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn test_dim() {
+        for (bytes, dim, text) in [
+            (b"\x00\x00".to_vec(), Dim::None, "0"),
+            (b"\x01\x00\x10\x00\x00\x00".to_vec(), Dim::Value(16), "1 16"),
+            (
+                b"\x02\x00\x02\x00\x00\x00\x03\x00\x00\x00".to_vec(),
+                Dim::Values { values: vec![2, 3] },
+                "2 2 3",
+            ),
+        ] {
+            let read = Dim::read(&mut Cursor::new(&bytes)).unwrap();
+            assert_eq!(dim, read);
+            assert_eq!(text, read.to_string());
+            let mut written = Cursor::new(Vec::new());
+            read.write(&mut written).unwrap();
+            assert_eq!(bytes, written.into_inner());
+        }
+    }
+
     #[test]
     fn test_libsn_sat() {
         let bytes =
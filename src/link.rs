@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
-//! PSY-Q Linker Script File Parser
+//! PSY-Q Linker Script File Parser and Linker
 //!
 //! **n.b.!** This interface is not stable and should be considered unstable.
 //!
@@ -11,6 +11,9 @@
 //! `psylink.exe` program links object files together, specifying memory layouts, symbol definitions, section placement,
 //! and overlay configurations.
 //!
+//! The [Linker] consumes parsed scripts along with [OBJ](crate::OBJ)s and [LIB](crate::LIB)s to produce a linked
+//! [Image].
+//!
 //! ## Purpose
 //!
 //! PSY-Q linker scripts define:
@@ -290,6 +293,8 @@
 use std::fmt;
 use std::fmt::Debug;
 
+use anyhow::anyhow;
+use anyhow::bail;
 use winnow::ascii::digit1;
 use winnow::ascii::hex_digit1;
 use winnow::ascii::space0;
@@ -312,6 +317,10 @@ use winnow::token::take_while;
 use winnow::ModalResult;
 use winnow::Parser;
 
+mod linker;
+
+pub use linker::{Image, LinkedGroup, LinkedSection, Linker};
+
 #[derive(Debug, PartialEq)]
 pub enum Attribute {
     BSS,
@@ -572,7 +581,7 @@ fn parse_file_name(input: &mut &str) -> ModalResult<String> {
 
 fn parse_symbol(input: &mut &str) -> ModalResult<String> {
     let s = (seq!(
-        take_while(1, (('a'..='z'), ('A'..='Z'), '_')),
+        take_while(1, (('a'..='z'), ('A'..='Z'), '_', '.')),
         take_while(0.., (('a'..='z'), ('A'..='Z'), ('0'..='9'), '?', '_', '.'))
     ))
     .parse_next(input)?;
@@ -1091,6 +1100,23 @@ pub fn parse_line(input: &mut &str) -> ModalResult<(Option<Command>, Option<Comm
     Ok((command, comment))
 }
 
+/// Parses every line of a linker script, returning the commands in the order they appear.
+///
+/// Unlike [parse_line], any unrecognized input is reported as an error along with its line number.
+pub fn parse_script(script: &str) -> anyhow::Result<Vec<Command>> {
+    let mut commands = Vec::new();
+    for (number, line) in script.lines().enumerate() {
+        let mut input = line;
+        let (command, _) =
+            parse_line(&mut input).map_err(|e| anyhow!("line {}: {e}", number + 1))?;
+        if !input.trim().is_empty() {
+            bail!("line {}: unexpected input \"{}\"", number + 1, input.trim());
+        }
+        commands.extend(command);
+    }
+    Ok(commands)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1291,6 +1317,33 @@ mod test {
         };
         assert_eq!("squares".to_string(), group);
         assert!(attributes.is_empty());
+
+        let output = parse_command("\tsection\t.rdata,text");
+        let Command::Section { name, group, .. } = output else {
+            panic!("unexpected output: {:?}", output);
+        };
+        assert_eq!(".rdata", name);
+        assert_eq!(Some("text".to_string()), group);
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let commands = parse_script("\torg\t$80010000\n\n; comment\ntext\tgroup\n").unwrap();
+        assert_eq!(
+            vec![
+                Command::Origin {
+                    address: 0x80010000
+                },
+                Command::Group {
+                    name: "text".into(),
+                    attributes: vec![],
+                },
+            ],
+            commands
+        );
+
+        let error = parse_script("org $80010000\norg ???\n").unwrap_err();
+        assert_eq!("line 2: unexpected input \"org ???\"", error.to_string());
     }
 
     #[test]
@@ -0,0 +1,1287 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! PSY-Q compatible linker.
+//!
+//! The [Linker] consumes the [Command]s of a linker script along with [OBJ]s and [LIB]s and
+//! produces an [Image]: a laid out, fully relocated memory image with a symbol table.
+//!
+//! ## Layout
+//!
+//! Sections are collected by name from every linked object. Sections declared in the script
+//! are placed in declaration order followed by any undeclared sections in the order they are
+//! first encountered. Each section belongs to a group; sections without a group are placed in
+//! an implicit group that follows all declared groups. Within a group, initialized sections
+//! are placed before uninitialized (BSS) sections.
+//!
+//! A group starts at its `org()` address, at the address of the group named by `over()`, or
+//! immediately after the previous group. The first group without an explicit origin starts at
+//! the script's `org` address.
+//!
+//! ## Symbols
+//!
+//! Objects explicitly included by the script are always linked. Modules from libraries are
+//! only linked when they define a symbol that is referenced but not yet defined. Symbols may
+//! also be defined by script assignments (`SYM = expr`) and aliases (`name alias target`).
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use psyk::link::Linker;
+//! use anyhow::Result;
+//!
+//! fn main() -> Result<()> {
+//!     let linker = Linker::from_script(Path::new("MAIN.LNK"))?;
+//!     let image = linker.link()?;
+//!     println!("entry point: {:x?}", image.entry());
+//!     std::fs::write("MAIN.BIN", image.to_bytes())?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{parse_script, Attribute, BinaryOp, Command, Expression, UnaryOp};
+use crate::eval::Context;
+use crate::io::{self, Type};
+use crate::model::{ObjectModel, Relocation, Symbol};
+use crate::{LIB, OBJ};
+
+/// A group declared by a linker script.
+#[derive(Clone, Debug, Default)]
+struct GroupDefinition {
+    name: String,
+    bss: bool,
+    origin: Option<u32>,
+    over: Option<String>,
+    file: Option<String>,
+    max_size: Option<u32>,
+}
+
+/// A section declared by a linker script.
+#[derive(Clone, Debug)]
+struct SectionDefinition {
+    name: String,
+    group: Option<String>,
+    bss: bool,
+}
+
+/// Links PSY-Q objects and libraries into an [Image].
+#[derive(Clone, Debug, Default)]
+pub struct Linker {
+    origin: Option<u32>,
+    groups: Vec<GroupDefinition>,
+    sections: Vec<SectionDefinition>,
+    objects: Vec<(String, OBJ)>,
+    libraries: Vec<LIB>,
+    assignments: Vec<(String, Expression)>,
+    aliases: Vec<(String, String)>,
+    registers: Vec<(String, Expression)>,
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a linker configured by the script at `path`.
+    ///
+    /// Relative `include` and `inclib` paths are resolved against the directory containing the
+    /// script.
+    pub fn from_script(path: &Path) -> Result<Self> {
+        // linker scripts are DOS text files, treat them as ISO-8859-1
+        let script = io::read_bytes(path)?
+            .iter()
+            .map(|&c| c as char)
+            .collect::<String>();
+        let base = path.parent().unwrap_or(Path::new(""));
+
+        let mut linker = Self::new();
+        for command in parse_script(&script)? {
+            linker.apply(command, base)?;
+        }
+        Ok(linker)
+    }
+
+    /// Applies a single linker script command. Files referenced by `include` and `inclib` are
+    /// read relative to `base`.
+    pub fn apply(&mut self, command: Command, base: &Path) -> Result<()> {
+        match command {
+            Command::Include { filename } => match io::read(&resolve_path(base, &filename))? {
+                Type::OBJ(obj) => self.add_object(filename, obj),
+                Type::LIB(lib) => {
+                    for module in lib.modules() {
+                        self.add_object(module.name(), module.object().clone());
+                    }
+                }
+                Type::EXE(_) | Type::CPE(_) => {
+                    bail!("{filename} is an executable and cannot be linked")
+                }
+            },
+            Command::IncLib { filename } => {
+                self.add_library(io::read_lib(&resolve_path(base, &filename))?)
+            }
+            Command::Origin { address } => self.origin = Some(to_address(address)?),
+            Command::Equals { left, right } => self.assignments.push((left, right)),
+            Command::Regs {
+                register,
+                expression,
+            } => self.registers.push((register.to_lowercase(), expression)),
+            Command::Group { name, attributes } => self.define_group(name, attributes)?,
+            Command::Section {
+                name,
+                group,
+                attributes,
+            } => self.define_section(name, group, attributes)?,
+            Command::Alias { name, target } => self.aliases.push((name, target)),
+            Command::DC { .. } => bail!("dc is not supported by the linker"),
+            // These only affect the workspace and symbol visibility of the original tools
+            // and have no bearing on the linked image.
+            Command::Workspace { .. }
+            | Command::Unit { .. }
+            | Command::Global { .. }
+            | Command::XDef { .. }
+            | Command::XRef { .. }
+            | Command::Public { .. } => (),
+        }
+        Ok(())
+    }
+
+    /// Adds an object which will always be linked.
+    pub fn add_object(&mut self, name: impl Into<String>, obj: OBJ) {
+        self.objects.push((name.into(), obj));
+    }
+
+    /// Adds a library. Modules are only linked if they define an otherwise undefined symbol.
+    pub fn add_library(&mut self, lib: LIB) {
+        self.libraries.push(lib);
+    }
+
+    /// Sets the address of the first group without an explicit origin.
+    pub fn set_origin(&mut self, origin: u32) {
+        self.origin = Some(origin);
+    }
+
+    fn define_group(&mut self, name: String, attributes: Vec<Attribute>) -> Result<()> {
+        if self.groups.iter().any(|g| g.name == name) {
+            bail!("Group {name} is defined more than once");
+        }
+        let mut group = GroupDefinition {
+            name,
+            ..Default::default()
+        };
+        for attribute in attributes {
+            match attribute {
+                Attribute::BSS => group.bss = true,
+                Attribute::Origin { address } => group.origin = Some(to_address(address)?),
+                Attribute::Over { group: over } => group.over = Some(over),
+                Attribute::File { filename } => group.file = Some(filename),
+                Attribute::Size { maxsize } => group.max_size = Some(to_address(maxsize)?),
+                // the load address and word alignment do not change the linked image
+                Attribute::Obj { .. } | Attribute::Word => (),
+            }
+        }
+        self.groups.push(group);
+        Ok(())
+    }
+
+    fn define_section(
+        &mut self,
+        name: String,
+        group: Option<String>,
+        attributes: Vec<Attribute>,
+    ) -> Result<()> {
+        if self.sections.iter().any(|s| s.name == name) {
+            bail!("Section {name} is defined more than once");
+        }
+        let mut section = SectionDefinition {
+            name,
+            group,
+            bss: false,
+        };
+        for attribute in attributes {
+            match attribute {
+                Attribute::BSS => section.bss = true,
+                Attribute::Word | Attribute::Obj { .. } => (),
+                a => bail!("Attribute {a:?} is not supported for sections"),
+            }
+        }
+        self.sections.push(section);
+        Ok(())
+    }
+
+    /// Returns the objects to be linked: every explicitly included object followed by any
+    /// library modules needed to satisfy undefined references.
+    fn resolve_units(&self) -> Result<Vec<Unit<'_>>> {
+        let mut units = self
+            .objects
+            .iter()
+            .map(|(name, obj)| Unit::new(name.clone(), obj))
+            .collect::<Result<Vec<Unit>>>()?;
+
+        let mut defined: HashSet<String> = self
+            .assignments
+            .iter()
+            .map(|(name, _)| name.clone())
+            .chain(self.aliases.iter().map(|(name, _)| name.clone()))
+            .chain(units.iter().flat_map(|u| u.obj.exports()))
+            .collect();
+
+        let mut linked_modules = HashSet::new();
+        loop {
+            let undefined = units
+                .iter()
+                .flat_map(|u| u.references())
+                .filter(|name| !defined.contains(name))
+                .collect::<Vec<String>>();
+
+            let mut changed = false;
+            for name in undefined {
+                if defined.contains(&name) {
+                    continue;
+                }
+                let candidate = self.libraries.iter().enumerate().find_map(|(l, lib)| {
+                    lib.modules()
+                        .iter()
+                        .enumerate()
+                        .find(|(m, module)| {
+                            !linked_modules.contains(&(l, *m))
+                                && module.object().exports().contains(&name)
+                        })
+                        .map(|(m, module)| ((l, m), module))
+                });
+                if let Some((key, module)) = candidate {
+                    linked_modules.insert(key);
+                    defined.extend(module.object().exports());
+                    units.push(Unit::new(module.name(), module.object())?);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let mut unresolved = units
+            .iter()
+            .flat_map(|u| u.references())
+            .filter(|name| !defined.contains(name))
+            .collect::<Vec<String>>();
+        if !unresolved.is_empty() {
+            unresolved.sort();
+            unresolved.dedup();
+            bail!("Unresolved external symbols: {}", unresolved.join(", "));
+        }
+
+        Ok(units)
+    }
+
+    /// Links all objects and required library modules into an [Image].
+    pub fn link(&self) -> Result<Image> {
+        let units = self.resolve_units()?;
+
+        // collect the contents of every section of every object
+        let mut fragments: Vec<Fragment> = Vec::new();
+        let mut fragment_index: HashMap<(usize, u16), usize> = HashMap::new();
+        let mut patches: Vec<PendingPatch> = Vec::new();
+        for (u, unit) in units.iter().enumerate() {
+            unit.collect(u, &mut fragments, &mut fragment_index, &mut patches);
+        }
+
+        // uninitialized symbols without a definition are allocated by the linker
+        let defined: HashSet<String> = units.iter().flat_map(|u| u.definitions()).collect();
+        let mut commons: Vec<Common> = Vec::new();
+        for unit in &units {
+            for (name, section, size) in unit.commons() {
+                if defined.contains(&name) {
+                    continue;
+                }
+                match commons.iter_mut().find(|c| c.name == name) {
+                    Some(common) => common.size = common.size.max(size),
+                    None => commons.push(Common {
+                        name,
+                        section,
+                        size,
+                        address: 0,
+                    }),
+                }
+            }
+        }
+
+        let mut layout = self.layout(&mut fragments, &mut commons)?;
+
+        // object symbols
+        for (u, unit) in units.iter().enumerate() {
+            for (name, section, offset) in unit.definitions_with_offsets() {
+                let Some(&f) = fragment_index.get(&(u, section)) else {
+                    bail!(
+                        "{}: symbol {name} is defined in unknown section {section:x}",
+                        unit.name
+                    );
+                };
+                layout.define(&name, fragments[f].address.wrapping_add(offset))?;
+            }
+        }
+        for common in &commons {
+            layout.define(&common.name, common.address)?;
+        }
+
+        // script symbols
+        for (name, expression) in &self.assignments {
+            let value = layout.evaluate_script(expression)?;
+            layout.define(name, value)?;
+        }
+        for (name, target) in &self.aliases {
+            let Some(&value) = layout.symbols.get(target) else {
+                bail!("Alias {name} refers to undefined symbol {target}");
+            };
+            layout.define(name, value)?;
+        }
+
+        // relocation
+        for patch in &patches {
+            let unit = &units[patch.unit];
+            let context = UnitContext {
+                unit: patch.unit,
+                model: &unit.model,
+                fragments: &fragments,
+                fragment_index: &fragment_index,
+                layout: &layout,
+            };
+            let offset = patch.relocation.offset();
+            let error = |e: anyhow::Error| {
+                anyhow!(
+                    "{}: patch at {offset:x} in section {:x}: {e}",
+                    unit.name,
+                    patch.section
+                )
+            };
+            let value = patch
+                .relocation
+                .expression()
+                .evaluate(&context)
+                .map_err(error)?;
+            let data = &mut fragments[patch.fragment].data;
+            patch.relocation.apply(data, value).map_err(error)?;
+        }
+
+        let mut registers = BTreeMap::new();
+        for (register, expression) in &self.registers {
+            registers.insert(register.clone(), layout.evaluate_script(expression)?);
+        }
+
+        // assemble the groups
+        let mut groups = Vec::new();
+        for group in &layout.groups {
+            let mut data = Vec::new();
+            for section in layout
+                .sections
+                .iter()
+                .filter(|s| s.group.as_deref() == Some(&group.name))
+            {
+                if section.bss {
+                    continue;
+                }
+                for &f in &section.fragments {
+                    let fragment = &fragments[f];
+                    let start = (fragment.address - group.address) as usize;
+                    data.resize(start, 0);
+                    data.extend_from_slice(&fragment.data);
+                    data.resize(start + fragment.size as usize, 0);
+                }
+                let end = add_address(section.address, section.size)?;
+                data.resize((end - group.address) as usize, 0);
+            }
+            groups.push(LinkedGroup {
+                name: group.name.clone(),
+                address: group.address,
+                size: group.size,
+                data,
+                overlay: group.over.clone(),
+                file: group.file.clone(),
+            });
+        }
+
+        let sections = layout
+            .sections
+            .iter()
+            .map(|s| LinkedSection {
+                name: s.name.clone(),
+                group: s.group.clone().unwrap_or_default(),
+                address: s.address,
+                size: s.size,
+                bss: s.bss,
+            })
+            .collect();
+
+        Ok(Image {
+            groups,
+            sections,
+            symbols: layout.symbols,
+            registers,
+        })
+    }
+
+    /// Assigns addresses to every section, fragment, and common symbol.
+    fn layout(&self, fragments: &mut [Fragment], commons: &mut [Common]) -> Result<Layout> {
+        // sections in script order, followed by the order they were encountered
+        let mut sections: Vec<OutputSection> = self
+            .sections
+            .iter()
+            .map(|s| OutputSection::new(s.name.clone(), s.group.clone(), s.bss))
+            .collect();
+        for (f, fragment) in fragments.iter().enumerate() {
+            let s = match sections.iter().position(|s| s.name == fragment.name) {
+                Some(s) => s,
+                None => {
+                    sections.push(OutputSection::new(fragment.name.clone(), None, false));
+                    sections.len() - 1
+                }
+            };
+            sections[s].fragments.push(f);
+        }
+        for (c, common) in commons.iter().enumerate() {
+            let s = match sections.iter().position(|s| s.name == common.section) {
+                Some(s) => s,
+                None => {
+                    sections.push(OutputSection::new(common.section.clone(), None, true));
+                    sections.len() - 1
+                }
+            };
+            sections[s].commons.push(c);
+        }
+
+        // groups in script order, followed by the implicit group
+        let mut groups: Vec<GroupDefinition> = self.groups.clone();
+        if sections.iter().any(|s| s.group.is_none()) {
+            groups.push(GroupDefinition::default());
+        }
+        for section in sections.iter_mut() {
+            let group_name = section.group.get_or_insert_with(String::new);
+            let Some(group) = groups.iter().find(|g| &g.name == group_name) else {
+                bail!(
+                    "Section {} refers to undefined group {group_name}",
+                    section.name
+                );
+            };
+            let initialized = section.fragments.iter().any(|&f| fragments[f].initialized);
+            if (section.bss || group.bss) && initialized {
+                bail!(
+                    "Section {} is uninitialized but contains initialized data",
+                    section.name
+                );
+            }
+            section.bss = !initialized;
+        }
+
+        let mut cursor = self.origin.unwrap_or(0);
+        let mut placed: Vec<PlacedGroup> = Vec::new();
+        for group in &groups {
+            let start = if let Some(origin) = group.origin {
+                origin
+            } else if let Some(over) = &group.over {
+                let Some(base) = placed.iter().find(|g| &g.name == over) else {
+                    bail!("Group {} overlays unknown group {over}", group.name);
+                };
+                base.address
+            } else {
+                let align = sections
+                    .iter()
+                    .filter(|s| s.group.as_deref() == Some(&group.name))
+                    .flat_map(|s| s.fragments.iter().map(|&f| fragments[f].align))
+                    .max()
+                    .unwrap_or(1);
+                align_up(cursor, align)?
+            };
+
+            let mut address = start;
+            // initialized sections are placed before uninitialized sections
+            for bss in [false, true] {
+                for section in sections
+                    .iter_mut()
+                    .filter(|s| s.group.as_deref() == Some(&group.name) && s.bss == bss)
+                {
+                    let align = section
+                        .fragments
+                        .iter()
+                        .map(|&f| fragments[f].align)
+                        .max()
+                        .unwrap_or(1);
+                    address = align_up(address, align)?;
+                    section.address = address;
+                    for &f in &section.fragments {
+                        address = align_up(address, fragments[f].align)?;
+                        fragments[f].address = address;
+                        address = add_address(address, fragments[f].size)?;
+                    }
+                    for &c in &section.commons {
+                        address = align_up(address, common_alignment(commons[c].size))?;
+                        commons[c].address = address;
+                        address = add_address(address, commons[c].size)?;
+                    }
+                    section.size = address - section.address;
+                }
+            }
+
+            let size = address - start;
+            if let Some(max_size) = group.max_size {
+                if size > max_size {
+                    bail!(
+                        "Group {} is {size:#x} bytes which exceeds its maximum size of {max_size:#x} bytes",
+                        group.name
+                    );
+                }
+            }
+            cursor = cursor.max(address);
+            placed.push(PlacedGroup {
+                name: group.name.clone(),
+                address: start,
+                size,
+                over: group.over.clone(),
+                file: group.file.clone(),
+            });
+        }
+
+        Ok(Layout {
+            groups: placed,
+            sections,
+            symbols: BTreeMap::new(),
+        })
+    }
+}
+
+/// Replaces DOS path separators and resolves `filename` relative to `base`.
+fn resolve_path(base: &Path, filename: &str) -> PathBuf {
+    base.join(filename.replace('\\', "/"))
+}
+
+fn to_address(value: u64) -> Result<u32> {
+    u32::try_from(value).map_err(|_| anyhow!("Address {value:#x} is out of range"))
+}
+
+fn add_address(address: u32, size: u32) -> Result<u32> {
+    to_address(u64::from(address) + u64::from(size))
+}
+
+fn align_up(address: u32, align: u32) -> Result<u32> {
+    if align <= 1 {
+        return Ok(address);
+    }
+    to_address(u64::from(address).div_ceil(u64::from(align)) * u64::from(align))
+}
+
+fn common_alignment(size: u32) -> u32 {
+    match size {
+        0..=1 => 1,
+        2..=3 => 2,
+        _ => 4,
+    }
+}
+
+/// An object being linked.
+struct Unit<'a> {
+    name: String,
+    obj: &'a OBJ,
+    model: ObjectModel,
+}
+
+impl<'a> Unit<'a> {
+    fn new(name: String, obj: &'a OBJ) -> Result<Self> {
+        let model = ObjectModel::new(obj).map_err(|e| anyhow!("{name}: {e}"))?;
+        Ok(Self { name, obj, model })
+    }
+
+    /// Symbols referenced by this object.
+    fn references(&self) -> Vec<String> {
+        self.model
+            .symbols()
+            .values()
+            .filter_map(|s| match s {
+                Symbol::Reference(xref) => Some(xref.symbol_name()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Symbols defined by this object.
+    fn definitions(&self) -> Vec<String> {
+        self.definitions_with_offsets()
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+
+    fn definitions_with_offsets(&self) -> Vec<(String, u16, u32)> {
+        self.model
+            .symbols()
+            .values()
+            .filter_map(|s| match s {
+                Symbol::Definition(xdef) => {
+                    Some((xdef.symbol_name(), xdef.section(), xdef.offset()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Uninitialized symbols with the name of the section they should be allocated in.
+    fn commons(&self) -> Vec<(String, String, u32)> {
+        self.model
+            .symbols()
+            .values()
+            .filter_map(|s| match s {
+                Symbol::Common(xbss) => Some((
+                    xbss.name(),
+                    self.model
+                        .section(xbss.section())
+                        .map(|section| section.name().to_string())
+                        .unwrap_or_else(|| ".bss".into()),
+                    xbss.size(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Collects the contents of each section and the patches to apply to them.
+    fn collect<'u>(
+        &'u self,
+        unit: usize,
+        fragments: &mut Vec<Fragment>,
+        fragment_index: &mut HashMap<(usize, u16), usize>,
+        patches: &mut Vec<PendingPatch<'u>>,
+    ) {
+        for section in self.model.sections() {
+            let initialized = !section.data().is_empty();
+            let mut data = section.data().to_vec();
+            if initialized {
+                data.resize(section.size() as usize, 0);
+            }
+            fragments.push(Fragment {
+                name: section.name().to_string(),
+                align: u32::from(section.align()).max(1),
+                data,
+                size: section.size(),
+                initialized,
+                address: 0,
+            });
+            let f = fragments.len() - 1;
+            fragment_index.insert((unit, section.number()), f);
+            patches.extend(section.relocations().iter().map(|relocation| PendingPatch {
+                unit,
+                fragment: f,
+                section: section.number(),
+                relocation,
+            }));
+        }
+    }
+}
+
+/// The contents of a single section from a single object.
+#[derive(Debug, Default)]
+struct Fragment {
+    name: String,
+    align: u32,
+    data: Vec<u8>,
+    size: u32,
+    initialized: bool,
+    address: u32,
+}
+
+/// An uninitialized symbol allocated by the linker.
+struct Common {
+    name: String,
+    section: String,
+    size: u32,
+    address: u32,
+}
+
+struct PendingPatch<'a> {
+    unit: usize,
+    fragment: usize,
+    section: u16,
+    relocation: &'a Relocation,
+}
+
+struct OutputSection {
+    name: String,
+    group: Option<String>,
+    bss: bool,
+    fragments: Vec<usize>,
+    commons: Vec<usize>,
+    address: u32,
+    size: u32,
+}
+
+impl OutputSection {
+    fn new(name: String, group: Option<String>, bss: bool) -> Self {
+        Self {
+            name,
+            group,
+            bss,
+            fragments: Vec::new(),
+            commons: Vec::new(),
+            address: 0,
+            size: 0,
+        }
+    }
+}
+
+struct PlacedGroup {
+    name: String,
+    address: u32,
+    size: u32,
+    over: Option<String>,
+    file: Option<String>,
+}
+
+/// Placement of groups and sections along with the symbols defined so far.
+struct Layout {
+    groups: Vec<PlacedGroup>,
+    sections: Vec<OutputSection>,
+    symbols: BTreeMap<String, u32>,
+}
+
+impl Layout {
+    fn define(&mut self, name: &str, value: u32) -> Result<()> {
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            bail!("Symbol {name} is defined more than once");
+        }
+        Ok(())
+    }
+
+    fn section(&self, name: &str) -> Result<&OutputSection> {
+        self.sections
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| anyhow!("Unknown section {name}"))
+    }
+
+    fn group(&self, name: &str) -> Result<&PlacedGroup> {
+        self.groups
+            .iter()
+            .find(|g| g.name == name)
+            .ok_or_else(|| anyhow!("Unknown group {name}"))
+    }
+
+    /// Evaluates a linker script expression.
+    fn evaluate_script(&self, expression: &Expression) -> Result<u32> {
+        Ok(self.evaluate_script_value(expression)? as u32)
+    }
+
+    fn evaluate_script_value(&self, expression: &Expression) -> Result<i64> {
+        Ok(match expression {
+            Expression::Constant(value) => *value as i64,
+            Expression::Symbol(name) => match self.symbols.get(name) {
+                Some(&value) => value as i64,
+                None => bail!("Undefined symbol {name}"),
+            },
+            Expression::Parens(expression) => self.evaluate_script_value(expression)?,
+            Expression::Unary { op, operand } => {
+                let value = self.evaluate_script_value(operand)?;
+                match op {
+                    UnaryOp::Neg => value.wrapping_neg(),
+                    UnaryOp::Not => !value,
+                    UnaryOp::LogNot => (value == 0) as i64,
+                }
+            }
+            Expression::Binary { left, op, right } => {
+                let l = self.evaluate_script_value(left)?;
+                let r = self.evaluate_script_value(right)?;
+                match op {
+                    BinaryOp::Add => l.wrapping_add(r),
+                    BinaryOp::Sub => l.wrapping_sub(r),
+                    BinaryOp::Mul => l.wrapping_mul(r),
+                    BinaryOp::Div => l
+                        .checked_div(r)
+                        .ok_or_else(|| anyhow!("Division by zero in {expression}"))?,
+                    BinaryOp::Mod => l
+                        .checked_rem(r)
+                        .ok_or_else(|| anyhow!("Division by zero in {expression}"))?,
+                    BinaryOp::And => l & r,
+                    BinaryOp::Or => l | r,
+                    BinaryOp::Xor => l ^ r,
+                    BinaryOp::Shl => l.wrapping_shl(r as u32),
+                    BinaryOp::Shr => l.wrapping_shr(r as u32),
+                    BinaryOp::Eq => (l == r) as i64,
+                    BinaryOp::Ne => (l != r) as i64,
+                    BinaryOp::Lt => (l < r) as i64,
+                    BinaryOp::Le => (l <= r) as i64,
+                    BinaryOp::Gt => (l > r) as i64,
+                    BinaryOp::Ge => (l >= r) as i64,
+                    BinaryOp::LogAnd => (l != 0 && r != 0) as i64,
+                    BinaryOp::LogOr => (l != 0 || r != 0) as i64,
+                }
+            }
+            Expression::Function { name, arg } => {
+                let Expression::Symbol(target) = arg.as_ref() else {
+                    bail!("{name}() expects a section or group name");
+                };
+                match name.as_str() {
+                    "sectstart" | "sectbase" => self.section(target)?.address as i64,
+                    "sectend" => {
+                        let section = self.section(target)?;
+                        section.address.wrapping_add(section.size) as i64
+                    }
+                    "groupstart" | "grouporg" => self.group(target)?.address as i64,
+                    _ => bail!("{name}() is not supported by the linker"),
+                }
+            }
+        })
+    }
+}
+
+/// Resolves the numbered references of a single object.
+struct UnitContext<'a> {
+    unit: usize,
+    model: &'a ObjectModel,
+    fragments: &'a [Fragment],
+    fragment_index: &'a HashMap<(usize, u16), usize>,
+    layout: &'a Layout,
+}
+
+impl UnitContext<'_> {
+    fn fragment(&self, section: u16) -> Result<&Fragment> {
+        self.fragment_index
+            .get(&(self.unit, section))
+            .map(|&f| &self.fragments[f])
+            .ok_or_else(|| anyhow!("Unknown section {section:x}"))
+    }
+
+    fn group_address(&self, number: u16) -> Result<u32> {
+        let Some(Symbol::Group(group)) = self.model.symbol(number) else {
+            bail!("Unknown group {number:x}");
+        };
+        Ok(self.layout.group(&group.name())?.address)
+    }
+}
+
+impl Context for UnitContext<'_> {
+    fn symbol(&self, number: u16) -> Result<u32> {
+        match self.model.symbol(number) {
+            Some(Symbol::Group(group)) => Ok(self.layout.group(&group.name())?.address),
+            Some(symbol) => {
+                let name = symbol.name();
+                match self.layout.symbols.get(&name) {
+                    Some(&address) => Ok(address),
+                    None => bail!("Undefined symbol {name}"),
+                }
+            }
+            None if self.model.section(number).is_some() => Ok(self.fragment(number)?.address),
+            None => bail!("Unknown symbol {number:x}"),
+        }
+    }
+
+    fn section_base(&self, section: u16) -> Result<u32> {
+        Ok(self.fragment(section)?.address)
+    }
+
+    fn section_start(&self, section: u16) -> Result<u32> {
+        let name = &self.fragment(section)?.name;
+        Ok(self.layout.section(name)?.address)
+    }
+
+    fn section_end(&self, section: u16) -> Result<u32> {
+        let name = &self.fragment(section)?.name;
+        let section = self.layout.section(name)?;
+        Ok(section.address.wrapping_add(section.size))
+    }
+
+    fn group_start(&self, group: u16) -> Result<u32> {
+        self.group_address(group)
+    }
+}
+
+/// A group in a linked [Image].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkedGroup {
+    name: String,
+    address: u32,
+    size: u32,
+    data: Vec<u8>,
+    overlay: Option<String>,
+    file: Option<String>,
+}
+
+impl LinkedGroup {
+    /// Returns the name of the group. Sections that were not assigned a group by the linker
+    /// script are placed in a group with an empty name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the address the group was linked at.
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    /// Returns the size of the group in memory, including uninitialized data.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the initialized contents of the group.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the name of the group this group overlays, if any.
+    pub fn overlay(&self) -> Option<&str> {
+        self.overlay.as_deref()
+    }
+
+    /// Returns the file this group should be written to, if any.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+}
+
+/// A section in a linked [Image].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkedSection {
+    name: String,
+    group: String,
+    address: u32,
+    size: u32,
+    bss: bool,
+}
+
+impl LinkedSection {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the group containing this section.
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns true if the section contains only uninitialized data.
+    pub fn is_bss(&self) -> bool {
+        self.bss
+    }
+}
+
+/// A linked memory image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    groups: Vec<LinkedGroup>,
+    sections: Vec<LinkedSection>,
+    symbols: BTreeMap<String, u32>,
+    registers: BTreeMap<String, u32>,
+}
+
+impl Image {
+    pub fn groups(&self) -> &[LinkedGroup] {
+        &self.groups
+    }
+
+    pub fn sections(&self) -> &[LinkedSection] {
+        &self.sections
+    }
+
+    /// Returns all global symbols and their addresses.
+    pub fn symbols(&self) -> &BTreeMap<String, u32> {
+        &self.symbols
+    }
+
+    /// Returns the address of a global symbol.
+    pub fn symbol(&self, name: &str) -> Option<u32> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Returns the initial register values set with `regs`. Register names are lowercase.
+    pub fn registers(&self) -> &BTreeMap<String, u32> {
+        &self.registers
+    }
+
+    /// Returns the initial program counter set with `regs pc=`.
+    pub fn entry(&self) -> Option<u32> {
+        self.registers.get("pc").copied()
+    }
+
+    /// Groups which contain initialized data and are not overlays.
+    fn loaded_groups(&self) -> impl Iterator<Item = &LinkedGroup> {
+        self.groups
+            .iter()
+            .filter(|g| g.overlay.is_none() && !g.data.is_empty())
+    }
+
+    /// Returns the address of the first byte returned by [to_bytes](Self::to_bytes).
+    pub fn address(&self) -> u32 {
+        self.loaded_groups().map(|g| g.address).min().unwrap_or(0)
+    }
+
+    /// Returns the initialized contents of all groups that are not overlays as a single
+    /// contiguous block of memory starting at [address](Self::address). Gaps between groups
+    /// are filled with zeros.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let start = self.address();
+        let mut bytes = Vec::new();
+        for group in self.loaded_groups() {
+            let offset = (group.address - start) as usize;
+            if bytes.len() < offset + group.data.len() {
+                bytes.resize(offset + group.data.len(), 0);
+            }
+            bytes[offset..offset + group.data.len()].copy_from_slice(&group.data);
+        }
+        bytes
+    }
+}
+
+impl fmt::Display for Image {
+    /// Writes a link map.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "  Start     Stop      Length    Group            Section"
+        )?;
+        for section in &self.sections {
+            writeln!(
+                f,
+                "  {:08X}  {:08X}  {:08X}  {:16} {}",
+                section.address,
+                section.address.wrapping_add(section.size).wrapping_sub(1),
+                section.size,
+                section.group,
+                section.name,
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "  Address   Names alphabetically")?;
+        for (name, address) in &self.symbols {
+            writeln!(f, "  {address:08X}  {name}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LNKHeader, Module, ModuleMetadata, Patch, Section, XBSS, XDEF, XREF};
+
+    fn header(section: u16, name: &str) -> Section {
+        Section::LNKHeader(LNKHeader::new(section, 0, 8, name.into()))
+    }
+
+    fn main_obj() -> OBJ {
+        use crate::Expression as E;
+        OBJ::new(vec![
+            header(1, ".text"),
+            header(2, ".data"),
+            header(3, ".bss"),
+            Section::SectionSwitch(1),
+            // jal 0; nop; lui v0, 0; addiu v0, v0, 0
+            Section::Code(
+                Code::new(vec![
+                    0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x00, //
+                    0x00, 0x00, 0x02, 0x3C, 0x00, 0x00, 0x42, 0x24,
+                ])
+                .unwrap(),
+            ),
+            Section::Patch(Patch::new(74, 0, E::SymbolAddressIndex(5))),
+            Section::Patch(Patch::new(
+                82,
+                8,
+                E::Add(
+                    Box::new(E::Constant(4)),
+                    Box::new(E::SectionAddressIndex(2)),
+                ),
+            )),
+            Section::Patch(Patch::new(
+                84,
+                12,
+                E::Add(
+                    Box::new(E::Constant(4)),
+                    Box::new(E::SectionAddressIndex(2)),
+                ),
+            )),
+            Section::SectionSwitch(2),
+            Section::Code(Code::new(vec![0xAA; 8]).unwrap()),
+            Section::Code(Code::new(vec![0, 0, 0, 0]).unwrap()),
+            Section::Patch(Patch::new(16, 0, E::SymbolAddressIndex(6))),
+            Section::SectionSwitch(3),
+            Section::BSS(16),
+            Section::XDEF(XDEF::new(4, 1, 0, "main".into())),
+            Section::XREF(XREF::new(5, "func".into())),
+            Section::XREF(XREF::new(6, "counter".into())),
+            Section::NOP,
+        ])
+    }
+
+    fn lib() -> LIB {
+        let func = OBJ::new(vec![
+            header(1, ".text"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0x08, 0x00, 0xE0, 0x03, 0, 0, 0, 0]).unwrap()),
+            Section::XDEF(XDEF::new(2, 1, 0, "func".into())),
+            Section::XBSS(XBSS::new(3, 4, 4, "counter".into())),
+            header(4, ".bss"),
+            Section::NOP,
+        ]);
+        let unused = OBJ::new(vec![
+            header(1, ".text"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 4]).unwrap()),
+            Section::XDEF(XDEF::new(2, 1, 0, "unused".into())),
+            Section::NOP,
+        ]);
+        let module = |name: &str, obj: OBJ| {
+            let metadata = ModuleMetadata::new(
+                name.into(),
+                std::time::SystemTime::now(),
+                0,
+                obj.exports().into_iter().map(crate::Export::new).collect(),
+            );
+            Module::new(obj, metadata)
+        };
+        LIB::new(vec![module("FUNC", func), module("UNUSED", unused)])
+    }
+
+    fn linker(script: &str) -> Linker {
+        let mut linker = Linker::new();
+        for command in parse_script(script).unwrap() {
+            linker.apply(command, Path::new("")).unwrap();
+        }
+        linker.add_object("MAIN.OBJ", main_obj());
+        linker.add_library(lib());
+        linker
+    }
+
+    #[test]
+    fn test_link() {
+        let image = linker(
+            "\torg\t$80010000\n\
+             \tregs\tpc=main\n\
+             STACK = $801FFF00\n",
+        )
+        .link()
+        .unwrap();
+
+        assert_eq!(Some(0x80010000), image.symbol("main"));
+        // main.obj .text is 16 bytes, func is aligned to 8
+        assert_eq!(Some(0x80010010), image.symbol("func"));
+        assert_eq!(None, image.symbol("unused"));
+        assert_eq!(Some(0x801FFF00), image.symbol("STACK"));
+        assert_eq!(Some(0x80010000), image.entry());
+
+        let names = image
+            .sections()
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<&str>>();
+        assert_eq!(vec![".text", ".data", ".bss"], names);
+
+        let data = &image.sections()[1];
+        assert_eq!(0x80010018, data.address());
+        assert_eq!(12, data.size());
+        let bss = &image.sections()[2];
+        assert!(bss.is_bss());
+        assert_eq!(0x80010028, bss.address());
+        // 16 bytes from main.obj and the common counter
+        assert_eq!(20, bss.size());
+        assert_eq!(Some(0x80010038), image.symbol("counter"));
+
+        let bytes = image.to_bytes();
+        assert_eq!(0x80010000, image.address());
+        assert_eq!(0x24, bytes.len());
+        // jal func
+        assert_eq!(0x0C004004u32.to_le_bytes(), bytes[0..4]);
+        // lui v0, %hi(.data + 4); addiu v0, v0, %lo(.data + 4)
+        assert_eq!(0x3C028001u32.to_le_bytes(), bytes[8..12]);
+        assert_eq!(0x2442001Cu32.to_le_bytes(), bytes[12..16]);
+        // patch offsets are relative to the start of the second chunk
+        assert_eq!([0xAA; 8], bytes[0x18..0x20]);
+        assert_eq!(0x80010038u32.to_le_bytes(), bytes[0x20..0x24]);
+    }
+
+    #[test]
+    fn test_link_groups() {
+        let image = linker(
+            "text group org($80020000)\n\
+             bss group bss\n\
+             section .text,text\n\
+             section .data,text\n\
+             section .bss,bss\n",
+        )
+        .link()
+        .unwrap();
+
+        let groups = image.groups();
+        assert_eq!(2, groups.len());
+        assert_eq!("text", groups[0].name());
+        assert_eq!(0x80020000, groups[0].address());
+        assert_eq!(0x24, groups[0].data().len());
+        assert_eq!("bss", groups[1].name());
+        assert_eq!(0x80020028, groups[1].address());
+        assert!(groups[1].data().is_empty());
+    }
+
+    #[test]
+    fn test_link_big_endian() {
+        use crate::Expression as E;
+        let obj = OBJ::new(vec![
+            Section::CPU(crate::cputype::HITACHI_SH2),
+            Section::WordSizeRegister(12),
+            header(1, ".text"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 8]).unwrap()),
+            Section::Patch(Patch::new(12, 0, E::SymbolAddressIndex(2))),
+            Section::Patch(Patch::new(8, 4, E::SymbolAddressIndex(2))),
+            Section::XDEF(XDEF::new(2, 1, 6, "start".into())),
+            Section::NOP,
+        ]);
+        let mut linker = Linker::new();
+        linker.set_origin(0x1000);
+        linker.add_object("SH2.OBJ", obj);
+        let image = linker.link().unwrap();
+        assert_eq!(
+            [0x10, 0x06, 0x00, 0x00, 0x00, 0x00, 0x10, 0x06],
+            image.to_bytes()[..]
+        );
+    }
+
+    #[test]
+    fn test_link_errors() {
+        let mut linker = Linker::new();
+        linker.add_object("MAIN.OBJ", main_obj());
+        let error = linker.link().unwrap_err().to_string();
+        assert_eq!("Unresolved external symbols: counter, func", error);
+
+        let error = self::linker("text group size($10)\nsection .text,text\n")
+            .link()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("exceeds its maximum size"), "{error}");
+
+        let error = self::linker("main = 4\n").link().unwrap_err().to_string();
+        assert_eq!("Symbol main is defined more than once", error);
+
+        let mut linker = Linker::new();
+        linker.set_origin(0xFFFFFFF0);
+        linker.add_object(
+            "HIGH.OBJ",
+            OBJ::new(vec![
+                header(1, ".text"),
+                Section::SectionSwitch(1),
+                Section::Code(Code::new(vec![0; 0x20]).unwrap()),
+                Section::NOP,
+            ]),
+        );
+        let error = linker.link().unwrap_err().to_string();
+        assert_eq!("Address 0x100000010 is out of range", error);
+    }
+}
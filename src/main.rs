@@ -10,7 +10,7 @@ use clap::{CommandFactory, Parser, Subcommand};
 
 mod dos;
 
-use psyk::cli::{self, get_binary_name};
+use psyk::cli::{self, get_binary_name, LinkFormat, Region};
 
 /// Inspect, extract, and create PSY-Q LIB and OBJ files.
 #[derive(Debug, Parser)]
@@ -43,6 +43,80 @@ enum CLICommand {
         /// recursively print all OBJ entries in a LIB
         #[clap(short, long)]
         recursive: bool,
+
+        /// name the symbols, sections, and groups in patch expressions
+        #[clap(short, long)]
+        symbolic: bool,
+    },
+
+    /// List the functions in a LIB or OBJ
+    Functions {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+    },
+
+    /// Print the source file and line of code in an OBJ
+    Addr2line {
+        /// the OBJ with line information
+        #[arg(required = true)]
+        obj: PathBuf,
+        /// a section name or number and a hexadecimal offset, e.g. .text:1c
+        #[arg(required = true)]
+        addresses: Vec<String>,
+    },
+
+    /// Print C declarations of the types, variables, and functions in the debug records of a
+    /// LIB or OBJ
+    Headers {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+    },
+
+    /// Write relocation-masked fingerprints of the functions in a LIB or OBJ as JSON
+    Fingerprint {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+    },
+
+    /// Find the functions of LIBs in a PS-X EXE or raw binary and write a symbol map
+    Identify {
+        /// the PS-X EXE or raw binary to search
+        #[arg(required = true)]
+        binary: PathBuf,
+        /// the LIBs or OBJs to search for
+        #[arg(required = true)]
+        libs: Vec<PathBuf>,
+        /// the address the binary is loaded at, in hexadecimal
+        #[clap(short, long, value_parser = parse_address)]
+        address: Option<u32>,
+    },
+
+    /// Build a database of the modules of Psy-Q SDK versions from their LIBs or DUMPOBJ
+    /// listings of their LIBs
+    SdkDatabase {
+        /// the database to write
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+        /// an SDK version and the directory of its LIBs, e.g. 4.0=PSX/LIB
+        #[arg(required = true, value_parser = parse_version_dir)]
+        versions: Vec<(String, PathBuf)>,
+    },
+
+    /// Report the Psy-Q SDK and library versions most likely used by a LIB, OBJ, PS-X EXE,
+    /// or raw binary
+    Sdk {
+        /// the database written by sdk-database
+        #[arg(required = true)]
+        database: PathBuf,
+        /// the LIB, OBJ, PS-X EXE, or raw binary to compare
+        #[arg(required = true)]
+        input: PathBuf,
+        /// the address a binary is loaded at, in hexadecimal
+        #[clap(short, long, value_parser = parse_address)]
+        address: Option<u32>,
     },
 
     /// splits a LIB into multiple OBJs
@@ -91,6 +165,82 @@ enum CLICommand {
         #[arg(num_args=1..)]
         obj_names: Vec<String>,
     },
+
+    /// Links OBJs and LIBs using a PSY-Q linker script
+    Link {
+        /// the linker script
+        #[arg(required = true)]
+        script: PathBuf,
+        /// the linked file to create
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+        /// the format of the linked file
+        #[clap(short, long, value_enum, default_value_t)]
+        format: LinkFormat,
+        /// write a symbol map
+        #[clap(short, long)]
+        map: Option<PathBuf>,
+    },
+
+    /// Converts a CPE into a PS-X EXE or a PS-X EXE into a CPE
+    Cpe2x {
+        /// the CPE or PS-X EXE to convert
+        #[arg(required = true)]
+        input: PathBuf,
+        /// the file to create
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// the region marker of a created PS-X EXE
+        #[clap(short, long, value_enum, default_value_t)]
+        region: Region,
+    },
+
+    /// Converts an OBJ, or the OBJs in a LIB, into ELF relocatable objects
+    Obj2elf {
+        /// the OBJ or LIB to convert
+        #[arg(required = true)]
+        input: PathBuf,
+        /// the file to create, or the directory to write a LIB's objects to
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Converts an ELF relocatable object into an OBJ
+    Elf2obj {
+        /// the ELF object to convert
+        #[arg(required = true)]
+        input: PathBuf,
+        /// the OBJ to create
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Writes GNU as source for an OBJ, or the OBJs in a LIB
+    Asm {
+        /// the OBJ or LIB to disassemble
+        #[arg(required = true)]
+        input: PathBuf,
+        /// the file to create, or the directory to write a LIB's sources to
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn parse_address(address: &str) -> Result<u32, String> {
+    let digits = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix('$'))
+        .unwrap_or(address);
+    u32::from_str_radix(digits, 16).map_err(|e| e.to_string())
+}
+
+fn parse_version_dir(argument: &str) -> Result<(String, PathBuf), String> {
+    match argument.split_once('=') {
+        Some((version, dir)) if !version.is_empty() && !dir.is_empty() => {
+            Ok((version.into(), dir.into()))
+        }
+        _ => Err(format!("expected VERSION=DIR, found {argument}")),
+    }
 }
 
 fn main() -> Result<()> {
@@ -109,18 +259,58 @@ fn main() -> Result<()> {
                 code,
                 disassemble,
                 recursive,
-            } => cli::info(
-                &mut std::io::stdout(),
-                &lib_or_obj,
-                code,
-                disassemble,
-                recursive,
-            )?,
+                symbolic,
+            } => {
+                let options = cli::ListOptions::default()
+                    .with_code(code)
+                    .with_disassembly(disassemble)
+                    .with_recursive(recursive)
+                    .with_symbolic(symbolic)
+                    .build();
+                cli::info_with_options(&mut std::io::stdout(), &lib_or_obj, options)?
+            }
+            CLICommand::Functions { lib_or_obj } => {
+                cli::functions(&mut std::io::stdout(), &lib_or_obj)?
+            }
+            CLICommand::Addr2line { obj, addresses } => {
+                cli::addr2line(&mut std::io::stdout(), &obj, &addresses)?
+            }
+            CLICommand::Headers { lib_or_obj } => {
+                cli::headers(&mut std::io::stdout(), &lib_or_obj)?
+            }
+            CLICommand::Fingerprint { lib_or_obj } => {
+                cli::fingerprint(&mut std::io::stdout(), &lib_or_obj)?
+            }
+            CLICommand::Identify {
+                binary,
+                libs,
+                address,
+            } => cli::identify(&mut std::io::stdout(), &binary, &libs, address)?,
+            CLICommand::SdkDatabase { output, versions } => cli::sdk_database(&output, &versions)?,
+            CLICommand::Sdk {
+                database,
+                input,
+                address,
+            } => cli::sdk(&mut std::io::stdout(), &database, &input, address)?,
             CLICommand::Extract { lib } => cli::split(&lib)?,
             CLICommand::Create { lib, objs } => cli::join(&lib, objs)?,
             CLICommand::Add { lib, obj } => cli::add(&lib, &obj)?,
             CLICommand::Update { lib, objs } => cli::update(&lib, objs)?,
             CLICommand::Delete { lib, obj_names } => cli::delete(&lib, obj_names)?,
+            CLICommand::Link {
+                script,
+                output,
+                format,
+                map,
+            } => cli::link(&script, &output, format, map.as_deref())?,
+            CLICommand::Cpe2x {
+                input,
+                output,
+                region,
+            } => cli::cpe2x(&input, output.as_deref(), region)?,
+            CLICommand::Obj2elf { input, output } => cli::obj2elf(&input, output.as_deref())?,
+            CLICommand::Elf2obj { input, output } => cli::elf2obj(&input, output.as_deref())?,
+            CLICommand::Asm { input, output } => cli::asm(&input, output.as_deref())?,
         },
         None => match args.lib_or_obj {
             Some(lib_or_obj) => {
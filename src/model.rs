@@ -0,0 +1,1024 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! A resolved view of an [OBJ].
+//!
+//! An [OBJ] is a stream of [Section]s which are interpreted in order. Switching sections, code,
+//! uninitialized data, repeats, and patches all depend on the state left by the entries before
+//! them. [ObjectModel] folds the stream into a record for each section with its contents and
+//! relocations, and a table of the numbered symbols the object defines and refers to.
+//! [ObjectModel::functions] divides the code of the object into [Function]s, and
+//! [ObjectModel::lines] maps its code to source lines with a [LineTable].
+//!
+//! ```
+//! use psyk::model::{ObjectModel, Symbol};
+//! use psyk::{Code, LNKHeader, Section, OBJ, XDEF};
+//!
+//! let obj = OBJ::new(vec![
+//!     Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+//!     Section::SectionSwitch(1),
+//!     Section::Code(Code::new(vec![0x08, 0x00, 0xE0, 0x03, 0, 0, 0, 0]).unwrap()),
+//!     Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+//!     Section::NOP,
+//! ]);
+//! let model = ObjectModel::new(&obj).unwrap();
+//! let text = model.section(1).unwrap();
+//! assert_eq!(".text", text.name());
+//! assert_eq!(8, text.data().len());
+//! assert!(matches!(model.symbol(2), Some(Symbol::Definition(_))));
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::patch::{mips, Target};
+use crate::{
+    cputype, Expression, FunctionStart, GroupSymbol, LocalSymbol, Patch, Section,
+    SectionOffsetLine, OBJ, XBSS, XDEF, XREF,
+};
+
+/// A numbered symbol in an object.
+///
+/// Sections, groups, and symbols share the same numbers. Sections are available from
+/// [ObjectModel::section].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Symbol {
+    /// A symbol defined by the object.
+    Definition(XDEF),
+    /// A symbol defined by another object.
+    Reference(XREF),
+    /// An uninitialized symbol which may be shared with other objects.
+    Common(XBSS),
+    /// A group of sections.
+    Group(GroupSymbol),
+}
+
+impl Symbol {
+    /// Returns the number used to refer to the symbol.
+    pub fn number(&self) -> u16 {
+        match self {
+            Self::Definition(xdef) => xdef.number(),
+            Self::Reference(xref) => xref.number(),
+            Self::Common(xbss) => xbss.number(),
+            Self::Group(group) => group.number(),
+        }
+    }
+
+    /// Returns the name of the symbol.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Definition(xdef) => xdef.symbol_name(),
+            Self::Reference(xref) => xref.symbol_name(),
+            Self::Common(xbss) => xbss.name(),
+            Self::Group(group) => group.name(),
+        }
+    }
+}
+
+/// A patch with its offset from the start of its section.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Relocation {
+    chunk: u32,
+    patch: Patch,
+    target: Target,
+}
+
+impl Relocation {
+    /// Returns the offset of the patch from the start of the section.
+    pub fn offset(&self) -> u32 {
+        self.chunk + u32::from(self.patch.offset())
+    }
+
+    /// Returns the type of patch.
+    pub fn tag(&self) -> u8 {
+        self.patch.tag()
+    }
+
+    /// Returns the expression used to calculate the patch value.
+    pub fn expression(&self) -> &Expression {
+        self.patch.expression()
+    }
+
+    /// Returns the processor and patch sizes in effect when the patch was read.
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Returns the patch as it appears in the object. Its offset is relative to the
+    /// [Code](Section::Code) it follows.
+    pub fn patch(&self) -> &Patch {
+        &self.patch
+    }
+
+    /// Writes `value` into the contents of the section, `data`.
+    pub fn apply(&self, data: &mut [u8], value: i64) -> Result<()> {
+        let Some(data) = data.get_mut(self.chunk as usize..) else {
+            bail!("patch offset {:x} is outside of the section", self.offset());
+        };
+        self.patch.apply(&self.target, data, value)
+    }
+
+    /// Returns the [SymbolAddressIndex](Expression::SymbolAddressIndex) or
+    /// [SectionAddressIndex](Expression::SectionAddressIndex) the patch refers to and the
+    /// constant added to it, or `None` if the expression is not that simple.
+    pub fn address(&self) -> Option<(&Expression, i64)> {
+        patch_address(&self.target, &self.patch)
+    }
+}
+
+/// Returns the symbol or section address `patch` refers to and the constant added to it. The
+/// `$gp` relative patches of MIPS objects refer to the offset of the address from `$gp`.
+pub(crate) fn patch_address<'a>(
+    target: &Target,
+    patch: &'a Patch,
+) -> Option<(&'a Expression, i64)> {
+    let expression = patch.expression();
+    if target.cpu() != cputype::MIPS_R3000 || patch.tag() != mips::GP_RELATIVE {
+        return address(expression);
+    }
+    // `(sectstart(n)-x)` is the offset of `x` from `$gp`
+    match expression {
+        Expression::Subtract(lhs, rhs) if matches!(lhs.as_ref(), Expression::SectionStart(_)) => {
+            address(rhs)
+        }
+        _ => None,
+    }
+}
+
+/// Splits `expression` into a symbol or section address and the constant added to it.
+pub(crate) fn address(expression: &Expression) -> Option<(&Expression, i64)> {
+    use Expression as E;
+    // operands are stored in reverse order
+    match expression {
+        E::SymbolAddressIndex(_) | E::SectionAddressIndex(_) => Some((expression, 0)),
+        E::Add(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+            (E::Constant(c), other) | (other, E::Constant(c)) => {
+                let (address, addend) = address(other)?;
+                Some((address, addend + i64::from(*c)))
+            }
+            _ => None,
+        },
+        E::Subtract(lhs, rhs) => match lhs.as_ref() {
+            E::Constant(c) => {
+                let (address, addend) = address(rhs)?;
+                Some((address, addend - i64::from(*c)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Formats the `name` of an address and the constant added to it, e.g. `sym+0x10`.
+pub(crate) fn format_address(name: &str, addend: i64) -> String {
+    match addend {
+        0 => name.into(),
+        1.. => format!("{name}+{addend:#x}"),
+        _ => format!("{name}-{:#x}", addend.unsigned_abs()),
+    }
+}
+
+/// The contents of a section in an object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectSection {
+    number: u16,
+    name: String,
+    group: u16,
+    align: u8,
+    data: Vec<u8>,
+    size: u32,
+    relocations: Vec<Relocation>,
+    // position new contents are written to
+    cursor: u32,
+    // start of the most recent code, patch offsets are relative to it
+    chunk: u32,
+}
+
+impl ObjectSection {
+    /// Returns the number used to refer to the section.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Returns the name of the section, e.g. `.text`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of the group the section belongs to, or 0.
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns the alignment of the section.
+    pub fn align(&self) -> u8 {
+        self.align
+    }
+
+    /// Returns the initialized contents of the section with repeats expanded. Uninitialized
+    /// data followed by code is filled with zeros.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the size of the uninitialized data following [ObjectSection::data].
+    pub fn bss_size(&self) -> u32 {
+        self.size - self.data.len() as u32
+    }
+
+    /// Returns the size of the section including uninitialized data.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the patches to apply to the section in the order they appear.
+    pub fn relocations(&self) -> &[Relocation] {
+        &self.relocations
+    }
+
+    fn write(&mut self, code: &[u8], count: u32) -> Result<()> {
+        let start = self.cursor as usize;
+        let end = code
+            .len()
+            .checked_mul(count as usize)
+            .and_then(|len| len.checked_add(start))
+            .filter(|end| *end <= u32::MAX as usize)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} bytes of code repeated {count} times at {start:#x} overflows section {:x}",
+                    code.len(),
+                    self.number
+                )
+            })?;
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        for chunk in self.data[start..end].chunks_mut(code.len().max(1)) {
+            chunk.copy_from_slice(code);
+        }
+        self.chunk = self.cursor;
+        self.seek(end as u32);
+        Ok(())
+    }
+
+    fn seek(&mut self, cursor: u32) {
+        self.cursor = cursor;
+        self.size = self.size.max(cursor);
+    }
+}
+
+/// A function in the code of an object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    name: String,
+    section: u16,
+    offset: u32,
+    data: Vec<u8>,
+    relocations: Vec<Relocation>,
+}
+
+impl Function {
+    /// Returns the name of the function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of the section the function is in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the function from the start of its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the size of the function in bytes.
+    pub fn size(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    /// Returns the code of the function before patches are applied.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the patches to apply to the function. Their offsets are from the start of the
+    /// section, not the function.
+    pub fn relocations(&self) -> &[Relocation] {
+        &self.relocations
+    }
+}
+
+/// The source line of the code starting at an offset in a section.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    section: u16,
+    offset: u32,
+    file: Option<u16>,
+    line: u32,
+}
+
+impl Line {
+    /// Returns the number of the section the code is in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the code from the start of its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the number of the source file, or `None` if no file was set.
+    pub fn file(&self) -> Option<u16> {
+        self.file
+    }
+
+    /// Returns the line number.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+}
+
+/// The source lines of the code of an [OBJ].
+///
+/// Objects describe their source lines with a state machine of a current file and line. The
+/// [SetToFile](Section::SetToFile), [SetToLine](Section::SetToLine), and
+/// [IncrementLineNumber](Section::IncrementLineNumber) records apply to the code that follows
+/// them in the current section. The source line debugger records
+/// ([SetSLDLineNum](Section::SetSLDLineNum), [IncSLDLineNum](Section::IncSLDLineNum), and
+/// their variants) apply from an offset in the current section until the next line or an
+/// [EndSLDInfo](Section::EndSLDInfo).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineTable {
+    files: BTreeMap<u16, String>,
+    lines: Vec<Line>,
+    // section and offset of the end of line information
+    ends: Vec<(u16, u32)>,
+}
+
+impl LineTable {
+    /// Returns the source file names by number.
+    pub fn files(&self) -> &BTreeMap<u16, String> {
+        &self.files
+    }
+
+    /// Returns the name of source file `number`.
+    pub fn file_name(&self, number: u16) -> Option<&str> {
+        self.files.get(&number).map(String::as_str)
+    }
+
+    /// Returns the lines ordered by section and offset.
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Returns the line of the code at `offset` in `section`, or `None` if the code has no line
+    /// information.
+    pub fn find(&self, section: u16, offset: u32) -> Option<&Line> {
+        // the last of the lines starting at the same offset replaces the others
+        let line = self
+            .lines
+            .iter()
+            .rev()
+            .find(|line| line.section == section && line.offset <= offset)?;
+        let ended = self.ends.iter().any(|(end_section, end)| {
+            *end_section == section && line.offset < *end && *end <= offset
+        });
+        (!ended).then_some(line)
+    }
+
+    fn push(&mut self, section: u16, offset: u32, file: Option<u16>, line: u32) {
+        self.lines.push(Line {
+            section,
+            offset,
+            file,
+            line,
+        });
+    }
+}
+
+/// The sections and symbols of an [OBJ].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectModel {
+    cpu: u8,
+    sections: Vec<ObjectSection>,
+    symbols: BTreeMap<u16, Symbol>,
+    locals: Vec<LocalSymbol>,
+    // function start and end debug records
+    debug_functions: Vec<(FunctionStart, Option<SectionOffsetLine>)>,
+    lines: LineTable,
+}
+
+impl ObjectModel {
+    /// Builds a model by interpreting the sections of `obj`.
+    ///
+    /// An error is returned if code, uninitialized data, or a patch appears before a section is
+    /// selected, a section switch refers to an unknown section, or a number is used more than
+    /// once.
+    pub fn new(obj: &OBJ) -> Result<Self> {
+        let mut model = Self {
+            cpu: Target::default().cpu(),
+            sections: Vec::new(),
+            symbols: BTreeMap::new(),
+            locals: Vec::new(),
+            debug_functions: Vec::new(),
+            lines: LineTable::default(),
+        };
+        let mut target = Target::default();
+        let mut current: Option<usize> = None;
+        let mut repeat = 1;
+        // current source file and line
+        let mut file: Option<u16> = None;
+        let mut line: u32 = 0;
+
+        for section in obj.sections() {
+            target.update(section);
+            match section {
+                Section::LNKHeader(header) => {
+                    let number = header.section();
+                    model.check_unused(number)?;
+                    model.sections.push(ObjectSection {
+                        number,
+                        name: header.type_name(),
+                        group: header.group(),
+                        align: header.align(),
+                        data: Vec::new(),
+                        size: 0,
+                        relocations: Vec::new(),
+                        cursor: 0,
+                        chunk: 0,
+                    });
+                }
+                Section::SectionSwitch(number) => current = Some(model.index(*number)?),
+                Section::RunAtOffset(number, offset) => {
+                    let index = model.index(*number)?;
+                    model.sections[index].seek(u32::from(*offset));
+                    current = Some(index);
+                }
+                Section::Code(code) => {
+                    model.current(current, "code")?.write(code.code(), repeat)?;
+                    repeat = 1;
+                }
+                Section::BSS(size) => {
+                    let section = model.current(current, "uninitialized data")?;
+                    section.seek(section.cursor.wrapping_add(*size));
+                }
+                Section::RepeatByte(count)
+                | Section::RepeatWord(count)
+                | Section::Repeat3Byte(count)
+                | Section::RepeatLong(count) => repeat = *count,
+                Section::Patch(patch) => {
+                    let section = model.current(current, "patch")?;
+                    section.relocations.push(Relocation {
+                        chunk: section.chunk,
+                        patch: patch.clone(),
+                        target,
+                    });
+                }
+                Section::XDEF(xdef) => model.insert(Symbol::Definition(xdef.clone()))?,
+                Section::XREF(xref) => model.insert(Symbol::Reference(xref.clone()))?,
+                Section::XBSS(xbss) => model.insert(Symbol::Common(xbss.clone()))?,
+                Section::GroupSymbol(group) => model.insert(Symbol::Group(group.clone()))?,
+                Section::LocalSymbol(local) => model.locals.push(local.clone()),
+                Section::FunctionStart(start) => model.debug_functions.push((start.clone(), None)),
+                Section::FunctionEnd(end) => {
+                    if let Some((_, function_end)) =
+                        model
+                            .debug_functions
+                            .iter_mut()
+                            .rev()
+                            .find(|(start, function_end)| {
+                                start.section() == end.section() && function_end.is_none()
+                            })
+                    {
+                        *function_end = Some(end.clone());
+                    }
+                }
+                Section::Filename(filename) => {
+                    model.lines.files.insert(filename.number(), filename.name());
+                }
+                Section::SetToFile(number, to) => {
+                    (file, line) = (Some(*number), *to);
+                    model.line(current, None, file, line)?;
+                }
+                Section::SetToLine(to) => {
+                    line = *to;
+                    model.line(current, None, file, line)?;
+                }
+                Section::IncrementLineNumber => {
+                    line = line.wrapping_add(1);
+                    model.line(current, None, file, line)?;
+                }
+                Section::IncrementLineNumberByte(by) => {
+                    line = line.wrapping_add(u32::from(*by));
+                    model.line(current, None, file, line)?;
+                }
+                Section::IncrementLineNumberWord(by) => {
+                    line = line.wrapping_add(*by);
+                    model.line(current, None, file, line)?;
+                }
+                Section::IncSLDLineNum(offset) => {
+                    line = line.wrapping_add(1);
+                    model.line(current, Some(*offset), file, line)?;
+                }
+                Section::IncSLDLineNumByte(offset, by) => {
+                    line = line.wrapping_add(u32::from(*by));
+                    model.line(current, Some(*offset), file, line)?;
+                }
+                Section::IncSLDLineNumWord(offset, by) => {
+                    line = line.wrapping_add(*by);
+                    model.line(current, Some(*offset), file, line)?;
+                }
+                Section::SetSLDLineNum(set) => {
+                    line = set.line();
+                    model.line(current, Some(set.offset()), file, line)?;
+                }
+                Section::SetSLDLineNumFile(set) => {
+                    (file, line) = (Some(set.file()), set.line());
+                    model.line(current, Some(set.offset()), file, line)?;
+                }
+                Section::EndSLDInfo(offset) => {
+                    let number = model.current(current, "line number")?.number;
+                    model.lines.ends.push((number, u32::from(*offset)));
+                }
+                _ => (),
+            }
+        }
+
+        model.cpu = target.cpu();
+        model
+            .lines
+            .lines
+            .sort_by_key(|line| (line.section, line.offset));
+        Ok(model)
+    }
+
+    /// Returns the processor the object was assembled for.
+    pub fn cpu(&self) -> u8 {
+        self.cpu
+    }
+
+    /// Returns the sections in the order they are declared.
+    pub fn sections(&self) -> &[ObjectSection] {
+        &self.sections
+    }
+
+    /// Returns the section with the given number.
+    pub fn section(&self, number: u16) -> Option<&ObjectSection> {
+        self.sections.iter().find(|s| s.number == number)
+    }
+
+    /// Returns the numbered symbols.
+    pub fn symbols(&self) -> &BTreeMap<u16, Symbol> {
+        &self.symbols
+    }
+
+    /// Returns the symbol with the given number.
+    pub fn symbol(&self, number: u16) -> Option<&Symbol> {
+        self.symbols.get(&number)
+    }
+
+    /// Returns the local symbols. Local symbols are not numbered and cannot be referred to by
+    /// patches.
+    pub fn locals(&self) -> &[LocalSymbol] {
+        &self.locals
+    }
+
+    /// Returns the [FunctionStart] of each function described by debug records, and the
+    /// [FunctionEnd](Section::FunctionEnd) if it has one.
+    pub fn debug_functions(&self) -> &[(FunctionStart, Option<SectionOffsetLine>)] {
+        &self.debug_functions
+    }
+
+    /// Returns the source lines of the code of the object.
+    pub fn lines(&self) -> &LineTable {
+        &self.lines
+    }
+
+    /// Returns the functions in the code of the object ordered by section and offset.
+    ///
+    /// Functions are found from [FunctionStart] and [FunctionEnd](Section::FunctionEnd) debug
+    /// records when the object has them. Exported and local symbols outside of those functions
+    /// in the same section, or in any `.text` section, also start a function, which ends where
+    /// the next function starts or at the end of the section's code.
+    pub fn functions(&self) -> Vec<Function> {
+        let mut functions = Vec::new();
+        for section in &self.sections {
+            let size = section.data.len() as u32;
+            // start offset -> name and end from debug records
+            let mut starts: BTreeMap<u32, (String, Option<u32>)> = BTreeMap::new();
+            for (start, end) in &self.debug_functions {
+                if start.section() == section.number && start.offset() < size {
+                    let end = end
+                        .as_ref()
+                        .map(|end| end.offset().min(size))
+                        .filter(|end| *end > start.offset());
+                    starts.entry(start.offset()).or_insert((start.name(), end));
+                }
+            }
+            if starts.is_empty() && !section.name.starts_with(".text") {
+                continue;
+            }
+
+            let debug: Vec<(u32, u32)> = starts
+                .iter()
+                .filter_map(|(start, (_, end))| end.map(|end| (*start, end)))
+                .collect();
+            let definitions = self.symbols.values().filter_map(|symbol| match symbol {
+                Symbol::Definition(xdef) if xdef.section() == section.number => {
+                    Some((xdef.offset(), xdef.symbol_name()))
+                }
+                _ => None,
+            });
+            let locals = self
+                .locals
+                .iter()
+                .filter(|local| local.section() == section.number)
+                .map(|local| (local.offset(), local.name()));
+            for (offset, name) in definitions.chain(locals) {
+                let inside = debug
+                    .iter()
+                    .any(|(start, end)| (*start..*end).contains(&offset));
+                if offset < size && !inside {
+                    starts.entry(offset).or_insert((name, None));
+                }
+            }
+
+            let offsets: Vec<u32> = starts.keys().copied().collect();
+            for (i, (offset, (name, end))) in starts.into_iter().enumerate() {
+                let end = end.unwrap_or_else(|| offsets.get(i + 1).copied().unwrap_or(size));
+                functions.push(Function {
+                    name,
+                    section: section.number,
+                    offset,
+                    data: section.data[offset as usize..end as usize].to_vec(),
+                    relocations: section
+                        .relocations
+                        .iter()
+                        .filter(|relocation| (offset..end).contains(&relocation.offset()))
+                        .cloned()
+                        .collect(),
+                });
+            }
+        }
+        functions
+    }
+
+    fn index(&self, number: u16) -> Result<usize> {
+        self.sections
+            .iter()
+            .position(|s| s.number == number)
+            .ok_or_else(|| anyhow!("switch to unknown section {number:x}"))
+    }
+
+    fn current(&mut self, current: Option<usize>, what: &str) -> Result<&mut ObjectSection> {
+        match current {
+            Some(index) => Ok(&mut self.sections[index]),
+            None => bail!("{what} outside of a section"),
+        }
+    }
+
+    /// Records `line` of `file` for the code at `offset` in the current section, or at the
+    /// section's cursor if `offset` is `None`.
+    fn line(
+        &mut self,
+        current: Option<usize>,
+        offset: Option<u16>,
+        file: Option<u16>,
+        line: u32,
+    ) -> Result<()> {
+        let section = self.current(current, "line number")?;
+        let (number, cursor) = (section.number, section.cursor);
+        let offset = offset.map_or(cursor, u32::from);
+        self.lines.push(number, offset, file, line);
+        Ok(())
+    }
+
+    fn check_unused(&self, number: u16) -> Result<()> {
+        if self.symbols.contains_key(&number) || self.section(number).is_some() {
+            bail!("number {number:x} is used more than once");
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, symbol: Symbol) -> Result<()> {
+        self.check_unused(symbol.number())?;
+        self.symbols.insert(symbol.number(), symbol);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cputype, Code, Filename, LNKHeader, SetSLDLineNum, SetSLDLineNumFile};
+    use binrw::BinRead;
+    use std::io::Cursor;
+
+    fn header(section: u16, name: &str) -> Section {
+        Section::LNKHeader(LNKHeader::new(section, 0, 8, name.into()))
+    }
+
+    #[test]
+    fn test_model() {
+        let obj = OBJ::new(vec![
+            header(1, ".text"),
+            header(2, ".bss"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![1, 2, 3, 4]).unwrap()),
+            Section::BSS(4),
+            Section::Code(Code::new(vec![0, 0, 0, 0]).unwrap()),
+            Section::Patch(Patch::new(16, 0, Expression::SymbolAddressIndex(3))),
+            Section::RepeatByte(3),
+            Section::Code(Code::new(vec![0xFF]).unwrap()),
+            Section::BSS(5),
+            Section::SectionSwitch(2),
+            Section::BSS(16),
+            Section::XDEF(XDEF::new(3, 1, 4, "value".into())),
+            Section::XREF(XREF::new(4, "printf".into())),
+            Section::XBSS(XBSS::new(5, 2, 4, "counter".into())),
+            Section::LocalSymbol(LocalSymbol::new(1, 0, "local".into())),
+            Section::NOP,
+        ]);
+        let model = ObjectModel::new(&obj).unwrap();
+        assert_eq!(cputype::MIPS_R3000, model.cpu());
+
+        let text = model.section(1).unwrap();
+        assert_eq!(".text", text.name());
+        assert_eq!(8, text.align());
+        assert_eq!(
+            [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF],
+            text.data()
+        );
+        assert_eq!(5, text.bss_size());
+        assert_eq!(20, text.size());
+        assert_eq!(1, text.relocations().len());
+        assert_eq!(8, text.relocations()[0].offset());
+
+        let bss = model.section(2).unwrap();
+        assert!(bss.data().is_empty());
+        assert_eq!(16, bss.bss_size());
+
+        let names = model
+            .symbols()
+            .values()
+            .map(|s| s.name())
+            .collect::<Vec<String>>();
+        assert_eq!(vec!["value", "printf", "counter"], names);
+        assert_eq!(1, model.locals().len());
+
+        let mut data = text.data().to_vec();
+        text.relocations()[0].apply(&mut data, 0x80010000).unwrap();
+        assert_eq!([0x00, 0x00, 0x01, 0x80], data[8..12]);
+    }
+
+    #[test]
+    fn test_run_at_offset() {
+        let obj = OBJ::new(vec![
+            Section::CPU(cputype::HITACHI_SH2),
+            header(1, "abs"),
+            Section::RunAtOffset(1, 0x10),
+            Section::Code(Code::new(vec![0xAA, 0xBB]).unwrap()),
+            Section::RunAtOffset(1, 0x2),
+            Section::Code(Code::new(vec![0xCC]).unwrap()),
+            Section::NOP,
+        ]);
+        let model = ObjectModel::new(&obj).unwrap();
+        assert_eq!(cputype::HITACHI_SH2, model.cpu());
+        let section = model.section(1).unwrap();
+        assert_eq!(0x12, section.size());
+        assert_eq!(0xCC, section.data()[2]);
+        assert_eq!([0xAA, 0xBB], section.data()[0x10..]);
+    }
+
+    #[test]
+    fn test_relocation_address() {
+        use Expression as E;
+        let obj = OBJ::new(vec![
+            header(1, ".text"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 12]).unwrap()),
+            // ($10+[3])
+            Section::Patch(Patch::new(
+                mips::WORD,
+                0,
+                E::Add(
+                    Box::new(E::Constant(0x10)),
+                    Box::new(E::SymbolAddressIndex(3)),
+                ),
+            )),
+            // (sectstart(1)-($4-sectbase(1)))
+            Section::Patch(Patch::new(
+                mips::GP_RELATIVE,
+                4,
+                E::Subtract(
+                    Box::new(E::SectionStart(1)),
+                    Box::new(E::Subtract(
+                        Box::new(E::Constant(4)),
+                        Box::new(E::SectionAddressIndex(1)),
+                    )),
+                ),
+            )),
+            // sectend(1)
+            Section::Patch(Patch::new(mips::WORD, 8, E::SectionEnd(1))),
+            Section::NOP,
+        ]);
+        let model = ObjectModel::new(&obj).unwrap();
+        let addresses = model
+            .section(1)
+            .unwrap()
+            .relocations()
+            .iter()
+            .map(|r| {
+                r.address()
+                    .map(|(address, addend)| (address.clone(), addend))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Some((E::SymbolAddressIndex(3), 0x10)),
+                Some((E::SectionAddressIndex(1), -4)),
+                None
+            ],
+            addresses
+        );
+        assert_eq!("sym+0x10", format_address("sym", 0x10));
+        assert_eq!(".text-0x4", format_address(".text", -4));
+    }
+
+    fn function_start(section: u16, offset: u32, name: &str) -> Section {
+        let mut bytes = vec![74];
+        bytes.extend(section.to_le_bytes());
+        bytes.extend(offset.to_le_bytes());
+        bytes.extend([0; 22]);
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+        Section::read(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn test_functions() {
+        let obj = OBJ::new(vec![
+            header(1, ".text"),
+            header(2, ".data"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new((0..32).collect()).unwrap()),
+            Section::Patch(Patch::new(74, 4, Expression::SymbolAddressIndex(4))),
+            Section::Patch(Patch::new(74, 20, Expression::SymbolAddressIndex(4))),
+            Section::SectionSwitch(2),
+            Section::Code(Code::new(vec![0; 8]).unwrap()),
+            Section::XDEF(XDEF::new(3, 1, 16, "exported".into())),
+            Section::XDEF(XDEF::new(4, 1, 0, "main".into())),
+            Section::XDEF(XDEF::new(5, 2, 0, "table".into())),
+            Section::LocalSymbol(LocalSymbol::new(1, 24, "helper".into())),
+            Section::NOP,
+        ]);
+        let model = ObjectModel::new(&obj).unwrap();
+        let functions = model.functions();
+        let names = functions.iter().map(|f| f.name()).collect::<Vec<&str>>();
+        assert_eq!(vec!["main", "exported", "helper"], names);
+        assert_eq!(1, functions[0].section());
+        assert_eq!(16, functions[0].size());
+        assert_eq!((0..16).collect::<Vec<u8>>(), functions[0].data());
+        assert_eq!(1, functions[0].relocations().len());
+        assert_eq!(4, functions[0].relocations()[0].offset());
+        assert_eq!(16, functions[1].offset());
+        assert_eq!(8, functions[1].size());
+        assert_eq!(20, functions[1].relocations()[0].offset());
+        assert_eq!(8, functions[2].size());
+
+        // debug records take precedence over symbols
+        let obj = OBJ::new(vec![
+            header(1, ".text"),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 32]).unwrap()),
+            function_start(1, 0, "main"),
+            Section::FunctionEnd(SectionOffsetLine::new(1, 12, 10)),
+            function_start(1, 16, "helper"),
+            Section::FunctionEnd(SectionOffsetLine::new(1, 28, 20)),
+            Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+            Section::XDEF(XDEF::new(3, 1, 8, "inner".into())),
+            Section::LocalSymbol(LocalSymbol::new(1, 12, "between".into())),
+            Section::NOP,
+        ]);
+        let model = ObjectModel::new(&obj).unwrap();
+        let functions = model
+            .functions()
+            .iter()
+            .map(|f| (f.name().to_string(), f.offset(), f.size()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("main".to_string(), 0, 12),
+                ("between".to_string(), 12, 4),
+                ("helper".to_string(), 16, 12),
+            ],
+            functions
+        );
+    }
+
+    #[test]
+    fn test_model_errors() {
+        let error = |sections: Vec<Section>| {
+            ObjectModel::new(&OBJ::new(sections))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            "code outside of a section",
+            error(vec![
+                Section::Code(Code::new(vec![0]).unwrap()),
+                Section::NOP
+            ])
+        );
+        assert_eq!(
+            "switch to unknown section 2",
+            error(vec![
+                header(1, ".text"),
+                Section::SectionSwitch(2),
+                Section::NOP
+            ])
+        );
+        assert_eq!(
+            "number 1 is used more than once",
+            error(vec![
+                header(1, ".text"),
+                Section::XREF(XREF::new(1, "printf".into())),
+                Section::NOP
+            ])
+        );
+        assert_eq!(
+            "2 bytes of code repeated 4294967295 times at 0x4 overflows section 1",
+            error(vec![
+                header(1, ".text"),
+                Section::SectionSwitch(1),
+                Section::Code(Code::new(vec![0, 0, 0, 0]).unwrap()),
+                Section::RepeatWord(u32::MAX),
+                Section::Code(Code::new(vec![0, 0]).unwrap()),
+                Section::NOP
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let obj = OBJ::new(vec![
+            header(1, ".text"),
+            Section::Filename(Filename::new(1, "main.c".into())),
+            Section::Filename(Filename::new(2, "util.h".into())),
+            Section::SectionSwitch(1),
+            // assembler line records apply at the cursor
+            Section::SetToFile(1, 10),
+            Section::Code(Code::new(vec![0; 8]).unwrap()),
+            Section::IncrementLineNumber,
+            Section::Code(Code::new(vec![0; 4]).unwrap()),
+            Section::IncrementLineNumberByte(3),
+            Section::Code(Code::new(vec![0; 4]).unwrap()),
+            // source line debugger records have offsets
+            Section::SetSLDLineNumFile(SetSLDLineNumFile::new(0x20, 40, 2)),
+            Section::IncSLDLineNum(0x24),
+            Section::IncSLDLineNumByte(0x28, 2),
+            Section::SetSLDLineNum(SetSLDLineNum::new(0x28, 50)),
+            Section::EndSLDInfo(0x30),
+            Section::Code(Code::new(vec![0; 0x20]).unwrap()),
+            Section::NOP,
+        ]);
+        let model = ObjectModel::new(&obj).unwrap();
+        let lines = model.lines();
+        assert_eq!(Some("util.h"), lines.file_name(2));
+        assert_eq!(2, lines.files().len());
+        let rows: Vec<(u32, Option<u16>, u32)> = lines
+            .lines()
+            .iter()
+            .map(|line| (line.offset(), line.file(), line.line()))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, Some(1), 10),
+                (8, Some(1), 11),
+                (12, Some(1), 14),
+                (0x20, Some(2), 40),
+                (0x24, Some(2), 41),
+                (0x28, Some(2), 43),
+                (0x28, Some(2), 50),
+            ],
+            rows
+        );
+
+        let find = |offset| lines.find(1, offset).map(|line| (line.file(), line.line()));
+        assert_eq!(Some((Some(1), 10)), find(4));
+        assert_eq!(Some((Some(1), 11)), find(8));
+        assert_eq!(Some((Some(1), 14)), find(0x1C));
+        assert_eq!(Some((Some(2), 41)), find(0x27));
+        assert_eq!(Some((Some(2), 50)), find(0x2C));
+        assert_eq!(None, find(0x30));
+        assert_eq!(None, lines.find(2, 0));
+
+        // line numbers are recorded in a section
+        let obj = OBJ::new(vec![Section::SetToLine(1), Section::NOP]);
+        assert!(ObjectModel::new(&obj).is_err());
+    }
+}
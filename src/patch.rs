@@ -0,0 +1,401 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Application of relocation [Patch]es.
+//!
+//! A [Patch] describes where a value is written and how it is encoded, its
+//! [Expression](crate::Expression) describes how the value is calculated. Once the expression
+//! has been [evaluated](crate::Expression::evaluate), [Patch::apply] writes the value into the
+//! contents of a section.
+//!
+//! The meaning of a patch's tag depends on the processor the object was assembled for and, for
+//! big-endian processors, on the byte, word, and long size registers set in the object. A
+//! [Target] tracks both as the [Section]s of an object are read. Tags which are not understood
+//! for a processor are reported as errors rather than ignored.
+
+use anyhow::{bail, Result};
+
+use crate::{arch, cputype, Patch, Section};
+
+/// Patch tags used by [MIPS R3000](cputype::MIPS_R3000) objects.
+pub mod mips {
+    /// 32-bit big-endian value.
+    pub const WORD_BE: u8 = 8;
+
+    /// 32-bit little-endian value.
+    pub const WORD: u8 = 16;
+
+    /// Possibly a signed 16-bit offset from `$gp` in the low half of an instruction.
+    ///
+    /// No SDK object has confirmed the meaning, so [Patch::apply](crate::Patch::apply) reports
+    /// it as an unknown patch type.
+    pub const GP_RELATIVE: u8 = 30;
+
+    /// 26-bit jump target of a `j` or `jal` instruction.
+    pub const JUMP: u8 = 74;
+
+    /// High 16 bits of a value in the low half of a `lui` instruction.
+    ///
+    /// The high half is incremented when bit 15 is set to account for the sign extension of
+    /// the matching [LO16].
+    pub const HI16: u8 = 82;
+
+    /// Low 16 bits of a value in the low half of an instruction.
+    pub const LO16: u8 = 84;
+}
+
+/// Default patch tags used by big-endian [Hitachi SH-2](cputype::HITACHI_SH2) and
+/// [Motorola 68000](cputype::MOTOROLA_68000) objects.
+///
+/// Objects may assign other tags with [ByteSizeRegister](Section::ByteSizeRegister),
+/// [WordSizeRegister](Section::WordSizeRegister), and
+/// [LongSizeRegister](Section::LongSizeRegister). There is no default tag for words.
+pub mod big_endian {
+    /// 8-bit value, e.g. the displacement of an SH-2 `mov.l @(disp,pc),rn`.
+    pub const BYTE: u8 = 10;
+
+    /// 32-bit value.
+    pub const LONG: u8 = 8;
+}
+
+/// The processor and patch sizes used to interpret the tags of an object's [Patch]es.
+///
+/// ```
+/// use psyk::patch::Target;
+/// use psyk::{cputype, Section};
+///
+/// let mut target = Target::default();
+/// for section in [Section::CPU(cputype::HITACHI_SH2), Section::WordSizeRegister(12)] {
+///     target.update(&section);
+/// }
+/// assert_eq!(target.cpu(), cputype::HITACHI_SH2);
+/// assert_eq!(target.word_size_register(), Some(12));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Target {
+    cpu: u8,
+    byte: Option<u16>,
+    word: Option<u16>,
+    three_byte: Option<u16>,
+    long: Option<u16>,
+}
+
+impl Default for Target {
+    /// A [MIPS R3000](cputype::MIPS_R3000) target.
+    fn default() -> Self {
+        Self::new(cputype::MIPS_R3000)
+    }
+}
+
+impl Target {
+    /// Creates a target for `cpu` using the default patch sizes.
+    pub fn new(cpu: u8) -> Self {
+        Self {
+            cpu,
+            byte: Some(u16::from(big_endian::BYTE)),
+            word: None,
+            three_byte: None,
+            long: Some(u16::from(big_endian::LONG)),
+        }
+    }
+
+    /// Updates the target from a [CPU](Section::CPU) or size register section. Other sections
+    /// are ignored.
+    pub fn update(&mut self, section: &Section) {
+        match section {
+            Section::CPU(cpu) => self.cpu = *cpu,
+            Section::ByteSizeRegister(tag) => self.byte = Some(*tag),
+            Section::WordSizeRegister(tag) => self.word = Some(*tag),
+            Section::Set3ByteRegister(tag) => self.three_byte = Some(*tag),
+            Section::LongSizeRegister(tag) => self.long = Some(*tag),
+            _ => (),
+        }
+    }
+
+    /// Returns the processor type.
+    pub fn cpu(&self) -> u8 {
+        self.cpu
+    }
+
+    /// Returns the architecture of the processor, or `None` if it is not known.
+    pub fn architecture(&self) -> Option<&'static dyn arch::Architecture> {
+        arch::for_cpu(self.cpu)
+    }
+
+    /// Returns the tag of byte sized patches.
+    pub fn byte_size_register(&self) -> Option<u16> {
+        self.byte
+    }
+
+    /// Returns the tag of word sized patches.
+    pub fn word_size_register(&self) -> Option<u16> {
+        self.word
+    }
+
+    /// Returns the tag of 3-byte patches, e.g. the long addresses of a
+    /// [WDC 65816](cputype::WDC_65816).
+    pub fn three_byte_size_register(&self) -> Option<u16> {
+        self.three_byte
+    }
+
+    /// Returns the tag of long sized patches.
+    pub fn long_size_register(&self) -> Option<u16> {
+        self.long
+    }
+
+    /// Returns the size in bytes of patches with `tag` on big-endian processors, or `None` if
+    /// the tag is not assigned to a size register.
+    pub fn size(&self, tag: u8) -> Option<usize> {
+        let tag = Some(u16::from(tag));
+        if tag == self.byte {
+            Some(1)
+        } else if tag == self.word {
+            Some(2)
+        } else if tag == self.three_byte {
+            Some(3)
+        } else if tag == self.long {
+            Some(4)
+        } else {
+            None
+        }
+    }
+}
+
+impl Patch {
+    /// Writes `value` into `data` at the patch's offset.
+    ///
+    /// `data` must begin at the start of the [Code](Section::Code) the patch follows. `target`
+    /// determines how the patch's tag is interpreted. An error is returned if the tag is not
+    /// known for the processor, the patch lies outside of `data`, or `value` cannot be
+    /// encoded.
+    ///
+    /// Patches are written by the [Architecture](arch::Architecture) of the processor. MIPS
+    /// tags are fixed and the size registers are ignored. SH-2 and 680x0 patches write
+    /// big-endian values of the size assigned to the tag.
+    ///
+    /// ```
+    /// use psyk::patch::{self, Target};
+    /// use psyk::{Expression, Patch};
+    ///
+    /// // lui $v0, 0 ; addiu $v0, $v0, 0
+    /// let mut data = vec![0x00, 0x00, 0x02, 0x3C, 0x00, 0x00, 0x42, 0x24];
+    /// let hi = Patch::new(patch::mips::HI16, 0, Expression::Constant(0x80018000));
+    /// let lo = Patch::new(patch::mips::LO16, 4, Expression::Constant(0x80018000));
+    /// hi.apply(&Target::default(), &mut data, 0x80018000).unwrap();
+    /// lo.apply(&Target::default(), &mut data, 0x80018000).unwrap();
+    /// assert_eq!(data, [0x02, 0x80, 0x02, 0x3C, 0x00, 0x80, 0x42, 0x24]);
+    /// ```
+    pub fn apply(&self, target: &Target, data: &mut [u8], value: i64) -> Result<()> {
+        match arch::for_cpu(target.cpu) {
+            Some(architecture) => architecture.apply_patch(target, self, data, value),
+            None => bail!(
+                "patches for processor type {} are not supported",
+                target.cpu
+            ),
+        }
+    }
+}
+
+/// Returns `value` as a 32-bit word if it fits in 32 bits, signed or unsigned.
+fn word(value: i64) -> Result<u32> {
+    if !(i64::from(i32::MIN)..=i64::from(u32::MAX)).contains(&value) {
+        bail!("value {value:#x} does not fit in 32 bits");
+    }
+    Ok(value as u32)
+}
+
+pub(crate) fn apply_mips(tag: u8, data: &mut [u8], offset: usize, value: i64) -> Result<()> {
+    let Some(bytes) = data.get_mut(offset..offset + 4) else {
+        bail!("patch offset {offset:x} is outside of the section");
+    };
+    let instruction = u32::from_le_bytes(bytes.try_into().expect("4 bytes"));
+    let value = word(value)?;
+    let patched = match tag {
+        mips::WORD => value,
+        mips::WORD_BE => {
+            bytes.copy_from_slice(&value.to_be_bytes());
+            return Ok(());
+        }
+        mips::JUMP => {
+            if value & 3 != 0 {
+                bail!("jump target {value:#x} is not word aligned");
+            }
+            (instruction & 0xFC00_0000) | ((value >> 2) & 0x03FF_FFFF)
+        }
+        mips::HI16 => (instruction & 0xFFFF_0000) | (value.wrapping_add(0x8000) >> 16),
+        mips::LO16 => (instruction & 0xFFFF_0000) | (value & 0xFFFF),
+        _ => bail!("unknown MIPS patch type {tag}"),
+    };
+    bytes.copy_from_slice(&patched.to_le_bytes());
+    Ok(())
+}
+
+/// Writes a big-endian `value` of the size assigned to the tag of `patch` by `target`.
+pub(crate) fn apply_sized(
+    cpu: u8,
+    target: &Target,
+    patch: &Patch,
+    data: &mut [u8],
+    value: i64,
+) -> Result<()> {
+    let Some(size) = target.size(patch.tag()) else {
+        bail!(
+            "unknown patch type {} for processor type {cpu}",
+            patch.tag()
+        );
+    };
+    apply_big_endian(size, data, usize::from(patch.offset()), value)
+}
+
+fn apply_big_endian(size: usize, data: &mut [u8], offset: usize, value: i64) -> Result<()> {
+    let Some(bytes) = data.get_mut(offset..offset + size) else {
+        bail!("patch offset {offset:x} is outside of the section");
+    };
+    let bits = 8 * size as u32;
+    if value < -(1i64 << (bits - 1)) || value >= (1i64 << bits) {
+        bail!("value {value:#x} does not fit in {bits} bits");
+    }
+    bytes.copy_from_slice(&(value as u32).to_be_bytes()[4 - size..]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Expression;
+    use anyhow::anyhow;
+    use binrw::BinRead;
+
+    fn apply(tag: u8, data: &mut [u8], offset: u16, value: i64) -> Result<()> {
+        Patch::new(tag, offset, Expression::Constant(0)).apply(&Target::default(), data, value)
+    }
+
+    #[test]
+    fn test_apply_mips() {
+        let mut data = vec![0u8; 4];
+        assert!(apply(mips::WORD, &mut data, 0, 0x12345678).is_ok());
+        assert_eq!([0x78, 0x56, 0x34, 0x12], data[..]);
+        assert!(apply(mips::WORD_BE, &mut data, 0, 0x12345678).is_ok());
+        assert_eq!([0x12, 0x34, 0x56, 0x78], data[..]);
+        assert!(apply(mips::WORD, &mut data, 0, -1).is_ok());
+        assert_eq!([0xFF, 0xFF, 0xFF, 0xFF], data[..]);
+        assert!(apply(mips::WORD, &mut data, 0, 0x1_0000_0000).is_err());
+
+        // jal 0
+        let mut data = vec![0x00, 0x00, 0x00, 0x0C];
+        assert!(apply(mips::JUMP, &mut data, 0, 0x80012344).is_ok());
+        assert_eq!([0xD1, 0x48, 0x00, 0x0C], data[..]);
+        assert!(apply(mips::JUMP, &mut data, 0, 0x80012346).is_err());
+
+        // lui $at, 0 ; lw $at, 0($at)
+        let mut data = vec![0x00, 0x00, 0x01, 0x3C, 0x00, 0x00, 0x21, 0x8C];
+        assert!(apply(mips::HI16, &mut data, 0, 0x8001FFFC).is_ok());
+        assert!(apply(mips::LO16, &mut data, 4, 0x8001FFFC).is_ok());
+        assert_eq!([0x02, 0x80, 0x01, 0x3C, 0xFC, 0xFF, 0x21, 0x8C], data[..]);
+        assert!(apply(mips::HI16, &mut data, 0, 0x80017FFC).is_ok());
+        assert_eq!([0x01, 0x80, 0x01, 0x3C], data[..4]);
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let mut data = vec![0u8; 4];
+        assert_eq!(
+            "patch offset 2 is outside of the section",
+            apply(mips::WORD, &mut data, 2, 0).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "unknown MIPS patch type 10",
+            apply(10, &mut data, 0, 0).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "unknown MIPS patch type 30",
+            apply(mips::GP_RELATIVE, &mut data, 0, 0)
+                .unwrap_err()
+                .to_string()
+        );
+        let error = Patch::new(10, 0, Expression::Constant(0))
+            .apply(&Target::new(cputype::ZILOG_Z80), &mut data, 0)
+            .unwrap_err();
+        assert_eq!(
+            "patches for processor type 6 are not supported",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_apply_big_endian() {
+        let mut target = Target::new(cputype::MOTOROLA_68000);
+        let patch = |tag| Patch::new(tag, 2, Expression::Constant(0));
+
+        let mut data = vec![0u8; 6];
+        assert!(patch(big_endian::LONG)
+            .apply(&target, &mut data, 0x00FF0000)
+            .is_ok());
+        assert_eq!([0, 0, 0x00, 0xFF, 0x00, 0x00], data[..]);
+        assert!(patch(big_endian::BYTE)
+            .apply(&target, &mut data, -1)
+            .is_ok());
+        assert_eq!([0, 0, 0xFF, 0xFF, 0x00, 0x00], data[..]);
+        assert_eq!(
+            "value 0x100 does not fit in 8 bits",
+            patch(big_endian::BYTE)
+                .apply(&target, &mut data, 0x100)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "unknown patch type 12 for processor type 0",
+            patch(12)
+                .apply(&target, &mut data, 0)
+                .unwrap_err()
+                .to_string()
+        );
+
+        target.update(&Section::WordSizeRegister(12));
+        target.update(&Section::LongSizeRegister(14));
+        assert!(patch(12).apply(&target, &mut data, 0x1234).is_ok());
+        assert_eq!([0, 0, 0x12, 0x34, 0x00, 0x00], data[..]);
+        assert!(patch(14).apply(&target, &mut data, 0x1234).is_ok());
+        assert_eq!([0, 0, 0x00, 0x00, 0x12, 0x34], data[..]);
+        assert!(patch(big_endian::LONG)
+            .apply(&target, &mut data, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_apply_sh2() {
+        struct Section1;
+
+        impl crate::eval::Context for Section1 {
+            fn symbol(&self, number: u16) -> Result<u32> {
+                Err(anyhow!("no symbol {number}"))
+            }
+            fn section_base(&self, _: u16) -> Result<u32> {
+                Ok(0x06004000)
+            }
+            fn section_start(&self, number: u16) -> Result<u32> {
+                Err(anyhow!("no section start {number}"))
+            }
+            fn section_end(&self, number: u16) -> Result<u32> {
+                Err(anyhow!("no section end {number}"))
+            }
+            fn group_start(&self, number: u16) -> Result<u32> {
+                Err(anyhow!("no group start {number}"))
+            }
+        }
+
+        // mov.l @(disp,pc),r12 patched from LIBSN.LIB SNMAIN
+        let bytes = b"\x0A\x0A\x1F\x00\x4A\x00\x02\x00\x00\x00\x2E\x34\x00\xFC\xFF\xFF\xFF\x2C\x04\x01\x00\x00\x22\x00\x00\x00\x2C\x04\x01\x00\x00\x60\x00\x00\x00";
+        let Section::Patch(patch) = Section::read(&mut std::io::Cursor::new(&bytes)).unwrap()
+        else {
+            panic!("expected a patch");
+        };
+        let value = patch.expression().evaluate(&Section1).unwrap();
+        let mut data = vec![0u8; 0x20];
+        data[0x1E] = 0xDC;
+        patch
+            .apply(&Target::new(cputype::HITACHI_SH2), &mut data, value)
+            .unwrap();
+        assert_eq!([0xDC, 0x10], data[0x1E..]);
+    }
+}
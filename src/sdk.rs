@@ -0,0 +1,648 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Psy-Q SDK version detection.
+//!
+//! A [Database] records the modules of the `LIB`s of each SDK version with their creation
+//! timestamps, exports, and the [Fingerprint]s of their functions. Modules can also be
+//! added from the listing `DUMPOBJ` writes for a `LIB`, which has no code, so they are only
+//! matched by their timestamps. A `LIB` or `OBJ` is compared module by module: a module
+//! created at the same time as a module of the same name in an SDK scores 1, and each of
+//! its functions with the hash of a function in the SDK module scores a share of 1. An
+//! executable is searched for the functions of each SDK with an [Identifier], and each SDK
+//! module scores the share of its functions found.
+//!
+//! The [Report] ranks the SDK versions by their total score, lists the evidence for each, and
+//! picks the most likely version of each library, since a program may link libraries from
+//! more than one SDK.
+//!
+//! ```
+//! use std::time::{Duration, SystemTime};
+//! use psyk::sdk::Database;
+//! use psyk::{Code, LNKHeader, Module, ModuleMetadata, Section, LIB, OBJ, XDEF};
+//!
+//! let lib = |created: u64| {
+//!     let obj = OBJ::new(vec![
+//!         Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+//!         Section::SectionSwitch(1),
+//!         Section::Code(Code::new(vec![0x08, 0x00, 0xE0, 0x03, 0, 0, 0, 0]).unwrap()),
+//!         Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+//!         Section::NOP,
+//!     ]);
+//!     let created = SystemTime::UNIX_EPOCH + Duration::from_secs(created);
+//!     LIB::new(vec![Module::new(obj, ModuleMetadata::new("MAIN".into(), created, 0, vec![]))])
+//! };
+//!
+//! let mut database = Database::new();
+//! database.add_lib("3.5", "LIBMAIN", &lib(820_000_000)).unwrap();
+//! database.add_lib("4.0", "LIBMAIN", &lib(880_000_000)).unwrap();
+//!
+//! let report = database.detect_lib(&lib(880_000_000)).unwrap();
+//! assert_eq!("4.0", report.best().unwrap().version());
+//! assert_eq!(vec![("LIBMAIN", "4.0")], report.libraries());
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::fingerprint::{self, Fingerprint};
+use crate::identify::Identifier;
+use crate::{LIB, OBJ};
+
+/// A module of a library in an SDK.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModuleRecord {
+    library: String,
+    module: String,
+    created: String,
+    exports: Vec<String>,
+    functions: Vec<Fingerprint>,
+}
+
+impl ModuleRecord {
+    /// Returns the name of the library the module is in, e.g. `LIBAPI`.
+    pub fn library(&self) -> &str {
+        &self.library
+    }
+
+    /// Returns the name of the module.
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Returns the creation timestamp of the module, e.g. `15-05-96 16:09:38`.
+    pub fn created(&self) -> &str {
+        &self.created
+    }
+
+    /// Returns the names of the symbols the module exports.
+    pub fn exports(&self) -> &[String] {
+        &self.exports
+    }
+
+    /// Returns the fingerprints of the module's functions. Modules added from a listing have
+    /// none.
+    pub fn functions(&self) -> &[Fingerprint] {
+        &self.functions
+    }
+}
+
+/// The modules of the libraries of SDK versions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Database {
+    versions: BTreeMap<String, Vec<ModuleRecord>>,
+}
+
+impl Database {
+    /// Creates an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a database written by [Database::write].
+    pub fn read(reader: impl Read) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Writes the database as JSON.
+    pub fn write(&self, writer: impl Write) -> Result<()> {
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Adds the modules of `lib`, named `library`, to SDK `version`.
+    pub fn add_lib(&mut self, version: &str, library: &str, lib: &LIB) -> Result<()> {
+        let mut records = Vec::new();
+        for module in lib.modules() {
+            let functions = fingerprint::fingerprints(module.object())
+                .map_err(|e| anyhow!("{library} {}: {e}", module.name()))?;
+            records.push(ModuleRecord {
+                library: library.into(),
+                module: module.name(),
+                created: module.created(),
+                exports: module.exports(),
+                functions,
+            });
+        }
+        self.versions
+            .entry(version.into())
+            .or_default()
+            .extend(records);
+        Ok(())
+    }
+
+    /// Adds the modules in `listing`, the `DUMPOBJ` listing of the `LIB` named `library`, to
+    /// SDK `version`.
+    ///
+    /// ```text
+    /// PsyLib version 2.04
+    ///
+    /// Module     Date     Time   Externals defined
+    ///
+    /// C57      20-05-97 11:38:26 InitHeap
+    /// ```
+    pub fn add_listing(&mut self, version: &str, library: &str, listing: &str) -> Result<()> {
+        let mut lines = listing
+            .lines()
+            .skip_while(|line| !line.starts_with("PsyLib"));
+        if lines.next().is_none() {
+            bail!("{library} is not a LIB listing");
+        }
+        let mut records: Vec<ModuleRecord> = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split_whitespace();
+            if line.starts_with(char::is_whitespace) {
+                // exports continued from the previous module
+                let Some(record) = records.last_mut() else {
+                    bail!("{library}: exports without a module: {line}");
+                };
+                record.exports.extend(fields.map(str::to_string));
+                continue;
+            }
+            let (Some(module), Some(date), Some(time)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                bail!("{library}: invalid module: {line}");
+            };
+            if module == "Module" && date == "Date" {
+                continue;
+            }
+            records.push(ModuleRecord {
+                library: library.into(),
+                module: module.into(),
+                created: format!("{date} {time}"),
+                exports: fields.map(str::to_string).collect(),
+                functions: Vec::new(),
+            });
+        }
+        self.versions
+            .entry(version.into())
+            .or_default()
+            .extend(records);
+        Ok(())
+    }
+
+    /// Returns the SDK versions in the database.
+    pub fn versions(&self) -> Vec<&str> {
+        self.versions.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the modules of SDK `version`.
+    pub fn modules(&self, version: &str) -> &[ModuleRecord] {
+        self.versions.get(version).map_or(&[], Vec::as_slice)
+    }
+
+    /// Compares the modules of `lib` with the modules of each SDK.
+    pub fn detect_lib(&self, lib: &LIB) -> Result<Report> {
+        let mut modules = Vec::new();
+        for module in lib.modules() {
+            let functions = fingerprint::fingerprints(module.object())
+                .map_err(|e| anyhow!("{}: {e}", module.name()))?;
+            modules.push((module.name(), Some(module.created()), functions));
+        }
+        Ok(self.detect_modules(&modules))
+    }
+
+    /// Compares `obj`, named `name`, with the modules of each SDK. Objects do not have
+    /// timestamps, so only their functions are compared.
+    pub fn detect_obj(&self, name: &str, obj: &OBJ) -> Result<Report> {
+        let functions = fingerprint::fingerprints(obj)?;
+        Ok(self.detect_modules(&[(name.into(), None, functions)]))
+    }
+
+    /// Searches `code`, which is loaded at `address`, for the functions of each SDK.
+    pub fn detect_code(&self, code: &[u8], address: u32) -> Report {
+        let mut candidates = Vec::new();
+        for (version, records) in &self.versions {
+            let mut identifier = Identifier::new();
+            let mut labels = HashMap::new();
+            for (i, record) in records.iter().enumerate() {
+                let label = format!("{}/{}", record.library, record.module);
+                identifier.add_fingerprints(&label, record.functions.clone());
+                labels.insert(label, i);
+            }
+            let mut found: BTreeMap<usize, usize> = BTreeMap::new();
+            for symbol in identifier.identify(code, address).symbols() {
+                if let Some(i) = symbol.module().and_then(|label| labels.get(label)) {
+                    *found.entry(*i).or_default() += 1;
+                }
+            }
+            let evidence = found
+                .into_iter()
+                .map(|(i, functions)| {
+                    let record = &records[i];
+                    let total = record.functions.len();
+                    Evidence {
+                        module: record.module.clone(),
+                        library: record.library.clone(),
+                        matched: record.module.clone(),
+                        timestamp: false,
+                        functions,
+                        total,
+                        score: functions as f64 / total as f64,
+                    }
+                })
+                .collect();
+            candidates.push(Candidate::new(version, evidence));
+        }
+        Report::new(candidates)
+    }
+
+    /// Compares modules, given as their name, creation timestamp, and function fingerprints,
+    /// with the modules of each SDK.
+    fn detect_modules(&self, modules: &[(String, Option<String>, Vec<Fingerprint>)]) -> Report {
+        let mut candidates = Vec::new();
+        for (version, records) in &self.versions {
+            let hashes: Vec<HashSet<u64>> = records
+                .iter()
+                .map(|record| record.functions.iter().map(Fingerprint::hash).collect())
+                .collect();
+            let mut evidence = Vec::new();
+            for (name, created, functions) in modules {
+                let best = records
+                    .iter()
+                    .zip(&hashes)
+                    .map(|(record, hashes)| {
+                        let timestamp =
+                            record.module == *name && created.as_ref() == Some(&record.created);
+                        let matched = functions
+                            .iter()
+                            .filter(|f| hashes.contains(&f.hash()))
+                            .count();
+                        let mut score = if timestamp { 1.0 } else { 0.0 };
+                        if !functions.is_empty() {
+                            score += matched as f64 / functions.len() as f64;
+                        }
+                        Evidence {
+                            module: name.clone(),
+                            library: record.library.clone(),
+                            matched: record.module.clone(),
+                            timestamp,
+                            functions: matched,
+                            total: functions.len(),
+                            score,
+                        }
+                    })
+                    .filter(|evidence| evidence.score > 0.0)
+                    .max_by(|a, b| a.score.total_cmp(&b.score));
+                evidence.extend(best);
+            }
+            candidates.push(Candidate::new(version, evidence));
+        }
+        Report::new(candidates)
+    }
+}
+
+/// A module found to be similar to a module of an SDK.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evidence {
+    module: String,
+    library: String,
+    matched: String,
+    timestamp: bool,
+    functions: usize,
+    total: usize,
+    score: f64,
+}
+
+impl Evidence {
+    /// Returns the name of the module compared. For executables this is the SDK module.
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Returns the library of the SDK module.
+    pub fn library(&self) -> &str {
+        &self.library
+    }
+
+    /// Returns the name of the SDK module.
+    pub fn matched(&self) -> &str {
+        &self.matched
+    }
+
+    /// Returns `true` if the module has the same timestamp as the SDK module.
+    pub fn timestamp(&self) -> bool {
+        self.timestamp
+    }
+
+    /// Returns the number of functions matched.
+    pub fn functions(&self) -> usize {
+        self.functions
+    }
+
+    /// Returns the number of functions compared.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the score of the module.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+/// An SDK version and the evidence for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    version: String,
+    score: f64,
+    evidence: Vec<Evidence>,
+}
+
+impl Candidate {
+    fn new(version: &str, evidence: Vec<Evidence>) -> Self {
+        Self {
+            version: version.into(),
+            score: evidence.iter().map(Evidence::score).sum(),
+            evidence,
+        }
+    }
+
+    /// Returns the SDK version.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the total score of the evidence.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Returns the names of the libraries with evidence.
+    pub fn libraries(&self) -> Vec<&str> {
+        let mut libraries: Vec<&str> = self.evidence.iter().map(|e| e.library()).collect();
+        libraries.sort();
+        libraries.dedup();
+        libraries
+    }
+
+    /// Returns the modules found to be similar to modules of the SDK.
+    pub fn evidence(&self) -> &[Evidence] {
+        &self.evidence
+    }
+}
+
+/// SDK versions ranked by score.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    candidates: Vec<Candidate>,
+}
+
+impl Report {
+    fn new(mut candidates: Vec<Candidate>) -> Self {
+        candidates.retain(|c| c.score > 0.0);
+        // the newest version wins a tie
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.version.cmp(&a.version))
+        });
+        Self { candidates }
+    }
+
+    /// Returns the SDK versions with any evidence from most to least likely.
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// Returns the most likely SDK version.
+    pub fn best(&self) -> Option<&Candidate> {
+        self.candidates.first()
+    }
+
+    /// Returns each library with evidence and its most likely SDK version.
+    pub fn libraries(&self) -> Vec<(&str, &str)> {
+        let mut scores: BTreeMap<&str, Vec<(&str, f64)>> = BTreeMap::new();
+        for candidate in &self.candidates {
+            for library in candidate.libraries() {
+                let score = candidate
+                    .evidence
+                    .iter()
+                    .filter(|e| e.library == library)
+                    .map(Evidence::score)
+                    .sum();
+                scores
+                    .entry(library)
+                    .or_default()
+                    .push((candidate.version(), score));
+            }
+        }
+        scores
+            .into_iter()
+            .filter_map(|(library, versions)| {
+                // candidates are ordered, so the first of equal scores is preferred
+                let (mut best, mut best_score) = *versions.first()?;
+                for (version, score) in versions {
+                    if score > best_score {
+                        (best, best_score) = (version, score);
+                    }
+                }
+                Some((library, best))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Report {
+    /// Writes the ranked versions, the most likely version of each library, and the evidence
+    /// for the most likely version.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(best) = self.best() else {
+            return writeln!(f, "No SDK version found");
+        };
+        writeln!(f, "  Version   Score     Libraries")?;
+        for candidate in &self.candidates {
+            writeln!(
+                f,
+                "  {:8}  {:<8.2}  {}",
+                candidate.version,
+                candidate.score,
+                candidate.libraries().join(", ")
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "  Library   Version")?;
+        for (library, version) in self.libraries() {
+            writeln!(f, "  {library:8}  {version}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "  Evidence for {}", best.version)?;
+        writeln!(f, "  Module    Library   Matched   Timestamp  Functions")?;
+        for evidence in &best.evidence {
+            writeln!(
+                f,
+                "  {:8}  {:8}  {:8}  {:9}  {}/{}",
+                evidence.module,
+                evidence.library,
+                evidence.matched,
+                if evidence.timestamp { "yes" } else { "no" },
+                evidence.functions,
+                evidence.total
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LNKHeader, Module, ModuleMetadata, Section, XDEF};
+    use std::time::{Duration, SystemTime};
+
+    // addiu $v0, $zero, n; addiu $v1, $zero, 2; addu $v0, $v0, $v1; jr $ra; nop
+    fn code(n: u8) -> Vec<u8> {
+        vec![
+            n, 0x00, 0x02, 0x24, 0x02, 0x00, 0x03, 0x24, 0x21, 0x10, 0x43, 0x00, 0x08, 0x00, 0xE0,
+            0x03, 0x00, 0x00, 0x00, 0x00,
+        ]
+    }
+
+    fn obj(name: &str, n: u8) -> OBJ {
+        OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(code(n)).unwrap()),
+            Section::XDEF(XDEF::new(2, 1, 0, name.into())),
+            Section::NOP,
+        ])
+    }
+
+    fn module(name: &str, n: u8, created: u64) -> Module {
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(created);
+        Module::new(
+            obj(&name.to_lowercase(), n),
+            ModuleMetadata::new(name.into(), created, 0, vec![]),
+        )
+    }
+
+    fn database() -> Database {
+        let mut database = Database::new();
+        let lib = LIB::new(vec![
+            module("A", 1, 820_000_000),
+            module("B", 2, 820_000_000),
+        ]);
+        database.add_lib("3.5", "LIBAPI", &lib).unwrap();
+        let lib = LIB::new(vec![
+            module("A", 1, 880_000_000),
+            module("B", 3, 880_000_000),
+        ]);
+        database.add_lib("4.0", "LIBAPI", &lib).unwrap();
+        let lib = LIB::new(vec![module("G", 4, 880_000_000)]);
+        database.add_lib("4.0", "LIBGPU", &lib).unwrap();
+        database
+    }
+
+    #[test]
+    fn test_add_listing() {
+        let mut database = Database::new();
+        let listing = "\nPsyLib version 2.04\n\n\
+            Module     Date     Time   Externals defined\n\n\
+            C57      20-05-97 11:38:26 InitHeap \n\
+            SYS      16-10-95 14:24:22 CdComstr CdRead \n\
+            \x20                          CdStatus \n\
+            FONTTEX  18-10-95 13:37:44 \n";
+        database.add_listing("4.0", "LIBAPI", listing).unwrap();
+        let modules = database.modules("4.0");
+        assert_eq!(3, modules.len());
+        assert_eq!("C57", modules[0].module());
+        assert_eq!("20-05-97 11:38:26", modules[0].created());
+        assert_eq!(vec!["CdComstr", "CdRead", "CdStatus"], modules[1].exports());
+        assert!(modules[2].exports().is_empty());
+        assert!(modules[2].functions().is_empty());
+
+        // timestamps alone identify the version
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(864_128_306);
+        let metadata = ModuleMetadata::new("C57".into(), created, 0, vec![]);
+        let lib = LIB::new(vec![Module::new(obj("c57", 1), metadata)]);
+        let report = database.detect_lib(&lib).unwrap();
+        assert_eq!(1.0, report.best().unwrap().score());
+        assert!(report.best().unwrap().evidence()[0].timestamp());
+
+        assert!(database
+            .add_listing("4.0", "NONE2", "Header : LNK version 2\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_detect_lib() {
+        let database = database();
+        assert_eq!(vec!["3.5", "4.0"], database.versions());
+        assert_eq!(3, database.modules("4.0").len());
+        assert_eq!("LIBGPU", database.modules("4.0")[2].library());
+
+        // B differs between versions, A's timestamp matches 3.5
+        let lib = LIB::new(vec![
+            module("A", 1, 820_000_000),
+            module("B", 2, 820_000_000),
+        ]);
+        let report = database.detect_lib(&lib).unwrap();
+        let scores: Vec<(&str, f64)> = report
+            .candidates()
+            .iter()
+            .map(|c| (c.version(), c.score()))
+            .collect();
+        assert_eq!(vec![("3.5", 4.0), ("4.0", 1.0)], scores);
+        assert_eq!(vec![("LIBAPI", "3.5")], report.libraries());
+
+        let evidence = &report.best().unwrap().evidence()[1];
+        assert_eq!("B", evidence.module());
+        assert_eq!("B", evidence.matched());
+        assert!(evidence.timestamp());
+        assert_eq!((1, 1), (evidence.functions(), evidence.total()));
+
+        // mixed libraries
+        let lib = LIB::new(vec![
+            module("A", 1, 820_000_000),
+            module("G", 4, 880_000_000),
+        ]);
+        let report = database.detect_lib(&lib).unwrap();
+        assert_eq!(
+            vec![("LIBAPI", "3.5"), ("LIBGPU", "4.0")],
+            report.libraries()
+        );
+
+        let report = database.detect_obj("B", &obj("b", 3)).unwrap();
+        assert_eq!("4.0", report.best().unwrap().version());
+        assert_eq!(1.0, report.best().unwrap().score());
+    }
+
+    #[test]
+    fn test_detect_code() {
+        let database = database();
+        let mut memory = vec![0; 0x10];
+        memory.extend(code(1));
+        memory.extend(code(3));
+        let report = database.detect_code(&memory, 0x8001_0000);
+        let best = report.best().unwrap();
+        assert_eq!("4.0", best.version());
+        assert_eq!(2.0, best.score());
+        assert_eq!(vec!["LIBAPI"], best.libraries());
+        assert_eq!(
+            "  Version   Score     Libraries\n\
+            \x20 4.0       2.00      LIBAPI\n\
+            \x20 3.5       1.00      LIBAPI\n\
+            \n\
+            \x20 Library   Version\n\
+            \x20 LIBAPI    4.0\n\
+            \n\
+            \x20 Evidence for 4.0\n\
+            \x20 Module    Library   Matched   Timestamp  Functions\n\
+            \x20 A         LIBAPI    A         no         1/1\n\
+            \x20 B         LIBAPI    B         no         1/1\n",
+            report.to_string()
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let database = database();
+        let mut json = Vec::new();
+        database.write(&mut json).unwrap();
+        assert_eq!(database, Database::read(json.as_slice()).unwrap());
+    }
+}
@@ -0,0 +1,820 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! C types from [Def] and [Def2] debug records.
+//!
+//! Psy-Q compilers describe the types, variables, and functions of a debug build with the
+//! symbolic debugging (SDB) encoding of COFF. Each record has a [StorageClass] and a 16-bit
+//! type: the low four bits are a [BaseType], and each following pair of bits derives a
+//! pointer, function, or array from it, starting with the derivation closest to the name.
+//! Arrays take their sizes from the dimensions of a [Def2], and structures, unions, and
+//! enumerations take their names from its tag.
+//!
+//! A structure, union, or enumeration tag is followed by its members and an end of structure
+//! (`.eos`) record. Parameters and locals follow the [FunctionStart](crate::FunctionStart) of
+//! their function. [Declarations] collects the records of an object and writes them as a C
+//! header.
+//!
+//! ```
+//! use psyk::types::Declarations;
+//! use psyk::{Def, Def2, Dim, Section, OBJ};
+//!
+//! let obj = OBJ::new(vec![
+//!     Section::Def(Def::new(0, 0, 10, 8, 8, "point".into())),
+//!     Section::Def(Def::new(0, 0, 8, 4, 4, "x".into())),
+//!     Section::Def(Def::new(0, 4, 8, 4, 4, "y".into())),
+//!     Section::Def2(Def2::new(0, 8, 102, 0, 8, Dim::None, "point".into(), ".eos".into())),
+//!     // function returning a pointer to struct point
+//!     Section::Def2(Def2::new(1, 0, 2, 0x68, 4, Dim::None, "point".into(), "origin".into())),
+//!     Section::NOP,
+//! ]);
+//! let declarations = Declarations::new(&obj);
+//! assert_eq!(
+//!     "struct point { /* size 0x8 */\n    int x; /* 0x0 */\n    int y; /* 0x4 */\n};\n\n\
+//!     struct point *origin(void);\n",
+//!     declarations.to_string()
+//! );
+//! ```
+
+use std::fmt;
+
+use crate::{Def, Def2, Section, OBJ};
+
+/// The type a [Type] is derived from, in the low four bits of an encoded type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    Null,
+    Void,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    Struct,
+    Union,
+    Enum,
+    EnumMember,
+    UnsignedChar,
+    UnsignedShort,
+    UnsignedInt,
+    UnsignedLong,
+}
+
+const BASE_TYPES: [BaseType; 16] = [
+    BaseType::Null,
+    BaseType::Void,
+    BaseType::Char,
+    BaseType::Short,
+    BaseType::Int,
+    BaseType::Long,
+    BaseType::Float,
+    BaseType::Double,
+    BaseType::Struct,
+    BaseType::Union,
+    BaseType::Enum,
+    BaseType::EnumMember,
+    BaseType::UnsignedChar,
+    BaseType::UnsignedShort,
+    BaseType::UnsignedInt,
+    BaseType::UnsignedLong,
+];
+
+impl BaseType {
+    /// Decodes the base type of the encoded type `def_type`.
+    pub fn decode(def_type: u16) -> Self {
+        BASE_TYPES[usize::from(def_type & 0xF)]
+    }
+}
+
+/// A step deriving a type from another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Derivation {
+    /// A pointer to the type.
+    Pointer,
+    /// A function returning the type.
+    Function,
+    /// An array of the type with the given number of elements.
+    Array(u32),
+}
+
+/// A decoded C type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Type {
+    base: BaseType,
+    tag: Option<String>,
+    // closest to the name first
+    derivations: Vec<Derivation>,
+}
+
+impl Type {
+    /// Decodes the encoded type `def_type`. `dims` are the sizes of its arrays, outermost
+    /// first, and `tag` names its structure, union, or enumeration.
+    pub fn decode(def_type: u16, dims: &[u32], tag: Option<String>) -> Self {
+        let mut dims = dims.iter();
+        let mut derivations = Vec::new();
+        for shift in (4..16).step_by(2) {
+            derivations.push(match (def_type >> shift) & 3 {
+                1 => Derivation::Pointer,
+                2 => Derivation::Function,
+                3 => Derivation::Array(dims.next().copied().unwrap_or(0)),
+                _ => break,
+            });
+        }
+        Self {
+            base: BaseType::decode(def_type),
+            tag,
+            derivations,
+        }
+    }
+
+    /// Returns the type the derivations start from.
+    pub fn base(&self) -> BaseType {
+        self.base
+    }
+
+    /// Returns the name of the structure, union, or enumeration of the base type.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Returns the derivations from the base type, starting with the one closest to the name.
+    pub fn derivations(&self) -> &[Derivation] {
+        &self.derivations
+    }
+
+    /// Returns `true` if the type is a function.
+    pub fn is_function(&self) -> bool {
+        self.derivations.first() == Some(&Derivation::Function)
+    }
+
+    /// Returns the type pointed to, returned, or contained by this type, or `None` for a base
+    /// type.
+    pub fn target(&self) -> Option<Type> {
+        if self.derivations.is_empty() {
+            return None;
+        }
+        Some(Self {
+            base: self.base,
+            tag: self.tag.clone(),
+            derivations: self.derivations[1..].to_vec(),
+        })
+    }
+
+    /// Returns a C declaration of `name` with this type, e.g. `char *name[4]`, or the type
+    /// alone if `name` is empty.
+    pub fn declare(&self, name: &str) -> String {
+        let mut declarator = name.to_string();
+        for derivation in &self.derivations {
+            if *derivation == Derivation::Pointer {
+                declarator.insert(0, '*');
+                continue;
+            }
+            if declarator.starts_with('*') {
+                declarator = format!("({declarator})");
+            }
+            match derivation {
+                Derivation::Array(0) => declarator.push_str("[]"),
+                Derivation::Array(n) => declarator.push_str(&format!("[{n}]")),
+                _ => declarator.push_str("()"),
+            }
+        }
+        let base = self.base_name();
+        if declarator.is_empty() {
+            base
+        } else {
+            format!("{base} {declarator}")
+        }
+    }
+
+    fn base_name(&self) -> String {
+        let keyword = match self.base {
+            BaseType::Null | BaseType::Void => "void",
+            BaseType::Char => "char",
+            BaseType::Short => "short",
+            BaseType::Int | BaseType::EnumMember => "int",
+            BaseType::Long => "long",
+            BaseType::Float => "float",
+            BaseType::Double => "double",
+            BaseType::Struct => "struct",
+            BaseType::Union => "union",
+            BaseType::Enum => "enum",
+            BaseType::UnsignedChar => "unsigned char",
+            BaseType::UnsignedShort => "unsigned short",
+            BaseType::UnsignedInt => "unsigned int",
+            BaseType::UnsignedLong => "unsigned long",
+        };
+        match (&self.tag, self.base) {
+            (Some(tag), BaseType::Struct | BaseType::Union | BaseType::Enum) => {
+                format!("{keyword} {}", identifier(tag))
+            }
+            _ => keyword.into(),
+        }
+    }
+}
+
+/// The storage class of a record, which says what it declares and what its value is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageClass {
+    /// The end of a function.
+    EndOfFunction,
+    Null,
+    /// A local on the stack. The value is its offset.
+    Automatic,
+    /// An external symbol. The value is its address.
+    External,
+    /// A static symbol. The value is its address.
+    Static,
+    /// A local in a register. The value is the register.
+    Register,
+    ExternalDefinition,
+    Label,
+    UndefinedLabel,
+    /// A member of a structure. The value is its offset.
+    StructMember,
+    /// A parameter on the stack. The value is its offset.
+    Argument,
+    /// The name of a structure.
+    StructTag,
+    /// A member of a union.
+    UnionMember,
+    /// The name of a union.
+    UnionTag,
+    Typedef,
+    UndefinedStatic,
+    /// The name of an enumeration.
+    EnumTag,
+    /// A member of an enumeration. The value is its value.
+    EnumMember,
+    /// A parameter in a register. The value is the register.
+    RegisterParameter,
+    /// A bit field of a structure. The value is its offset in bits and the size its width.
+    BitField,
+    /// The start or end of a block.
+    Block,
+    /// The start or end of a function.
+    Function,
+    /// The end of a structure, union, or enumeration. The value is its size.
+    EndOfStruct,
+    File,
+    Line,
+    Alias,
+    Hidden,
+    Other(u16),
+}
+
+impl From<u16> for StorageClass {
+    fn from(class: u16) -> Self {
+        match class {
+            0xFFFF => Self::EndOfFunction,
+            0 => Self::Null,
+            1 => Self::Automatic,
+            2 => Self::External,
+            3 => Self::Static,
+            4 => Self::Register,
+            5 => Self::ExternalDefinition,
+            6 => Self::Label,
+            7 => Self::UndefinedLabel,
+            8 => Self::StructMember,
+            9 => Self::Argument,
+            10 => Self::StructTag,
+            11 => Self::UnionMember,
+            12 => Self::UnionTag,
+            13 => Self::Typedef,
+            14 => Self::UndefinedStatic,
+            15 => Self::EnumTag,
+            16 => Self::EnumMember,
+            17 => Self::RegisterParameter,
+            18 => Self::BitField,
+            100 => Self::Block,
+            101 => Self::Function,
+            102 => Self::EndOfStruct,
+            103 => Self::File,
+            104 => Self::Line,
+            105 => Self::Alias,
+            106 => Self::Hidden,
+            _ => Self::Other(class),
+        }
+    }
+}
+
+/// A variable, parameter, member, or type name declared by a record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variable {
+    name: String,
+    ty: Type,
+    class: StorageClass,
+    section: u16,
+    value: u32,
+    size: u32,
+}
+
+impl Variable {
+    fn from_def(def: &Def) -> Self {
+        Self {
+            name: def.name(),
+            ty: Type::decode(def.def_type(), &[], None),
+            class: def.class().into(),
+            section: def.section(),
+            value: def.value(),
+            size: def.size(),
+        }
+    }
+
+    fn from_def2(def: &Def2) -> Self {
+        let tag = def.tag();
+        Self {
+            name: def.name(),
+            ty: Type::decode(
+                def.def_type(),
+                &def.dims().values(),
+                (!tag.is_empty()).then_some(tag),
+            ),
+            class: def.class().into(),
+            section: def.section(),
+            value: def.value(),
+            size: def.size(),
+        }
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type.
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    /// Returns the storage class.
+    pub fn class(&self) -> StorageClass {
+        self.class
+    }
+
+    /// Returns the section of a static or external variable.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the value, whose meaning depends on the [StorageClass].
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the size in bytes, or in bits for a bit field.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+/// The kind of a [Tag].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagKind {
+    Struct,
+    Union,
+    Enum,
+}
+
+/// A structure, union, or enumeration and its members.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+    kind: TagKind,
+    name: String,
+    size: u32,
+    members: Vec<Variable>,
+}
+
+impl Tag {
+    /// Returns whether the tag is a structure, union, or enumeration.
+    pub fn kind(&self) -> TagKind {
+        self.kind
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the size in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the members in the order they are declared.
+    pub fn members(&self) -> &[Variable] {
+        &self.members
+    }
+}
+
+impl fmt::Display for Tag {
+    /// Writes the C definition of the tag with the offset of each member.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keyword = match self.kind {
+            TagKind::Struct => "struct",
+            TagKind::Union => "union",
+            TagKind::Enum => "enum",
+        };
+        writeln!(
+            f,
+            "{keyword} {} {{ /* size {:#x} */",
+            identifier(&self.name),
+            self.size
+        )?;
+        for member in &self.members {
+            let declaration = member.ty.declare(&member.name);
+            match member.class {
+                StorageClass::EnumMember => {
+                    writeln!(f, "    {} = {},", member.name, member.value as i32)?
+                }
+                StorageClass::BitField => writeln!(
+                    f,
+                    "    {declaration} : {}; /* bit {} */",
+                    member.size, member.value
+                )?,
+                _ => writeln!(f, "    {declaration}; /* {:#x} */", member.value)?,
+            }
+        }
+        writeln!(f, "}};")
+    }
+}
+
+/// A function and its parameters and locals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    name: String,
+    ty: Type,
+    class: StorageClass,
+    parameters: Vec<Variable>,
+    locals: Vec<Variable>,
+}
+
+impl Function {
+    /// Returns the name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the function. Functions without a record are assumed to return
+    /// `int`.
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    /// Returns the type the function returns.
+    pub fn return_type(&self) -> Type {
+        self.ty.target().unwrap_or_else(|| self.ty.clone())
+    }
+
+    /// Returns [StorageClass::External] or [StorageClass::Static].
+    pub fn class(&self) -> StorageClass {
+        self.class
+    }
+
+    /// Returns the parameters in order.
+    pub fn parameters(&self) -> &[Variable] {
+        &self.parameters
+    }
+
+    /// Returns the locals in the order they are declared.
+    pub fn locals(&self) -> &[Variable] {
+        &self.locals
+    }
+
+    /// Returns the C prototype of the function, e.g. `int main(int argc, char **argv)`.
+    pub fn prototype(&self) -> String {
+        let parameters = if self.parameters.is_empty() {
+            "void".into()
+        } else {
+            self.parameters
+                .iter()
+                .map(|parameter| parameter.ty.declare(&parameter.name))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        self.return_type()
+            .declare(&format!("{}({parameters})", self.name))
+    }
+}
+
+/// The types, variables, and functions declared by the debug records of an [OBJ].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Declarations {
+    tags: Vec<Tag>,
+    typedefs: Vec<Variable>,
+    variables: Vec<Variable>,
+    functions: Vec<Function>,
+}
+
+impl Declarations {
+    /// Collects the declarations in the [Def] and [Def2] records of `obj`.
+    pub fn new(obj: &OBJ) -> Self {
+        let mut declarations = Self::default();
+        let mut tag: Option<Tag> = None;
+        let mut function: Option<usize> = None;
+        for section in obj.sections() {
+            let variable = match section {
+                Section::Def(def) => Variable::from_def(def),
+                Section::Def2(def) => Variable::from_def2(def),
+                Section::FunctionStart(start) => {
+                    function = Some(declarations.function(&start.name()));
+                    continue;
+                }
+                Section::FunctionEnd(_) => {
+                    function = None;
+                    continue;
+                }
+                _ => continue,
+            };
+            // parameters may precede the function start
+            let current = function.or(declarations.functions.len().checked_sub(1));
+            match variable.class {
+                StorageClass::StructTag | StorageClass::UnionTag | StorageClass::EnumTag => {
+                    let kind = match variable.class {
+                        StorageClass::StructTag => TagKind::Struct,
+                        StorageClass::UnionTag => TagKind::Union,
+                        _ => TagKind::Enum,
+                    };
+                    tag = Some(Tag {
+                        kind,
+                        name: variable.name,
+                        size: variable.size,
+                        members: Vec::new(),
+                    });
+                }
+                StorageClass::StructMember
+                | StorageClass::UnionMember
+                | StorageClass::BitField
+                | StorageClass::EnumMember => {
+                    if let Some(tag) = &mut tag {
+                        tag.members.push(variable);
+                    }
+                }
+                StorageClass::EndOfStruct => {
+                    if let Some(mut tag) = tag.take() {
+                        if tag.size == 0 {
+                            tag.size = variable.size;
+                        }
+                        declarations.tags.push(tag);
+                    }
+                }
+                StorageClass::Typedef => declarations.typedefs.push(variable),
+                StorageClass::Argument | StorageClass::RegisterParameter => {
+                    if let Some(i) = current {
+                        declarations.functions[i].parameters.push(variable);
+                    }
+                }
+                StorageClass::Automatic | StorageClass::Register => {
+                    if let Some(i) = function {
+                        declarations.functions[i].locals.push(variable);
+                    }
+                }
+                StorageClass::External
+                | StorageClass::Static
+                | StorageClass::ExternalDefinition
+                | StorageClass::UndefinedStatic => {
+                    if variable.ty.is_function() {
+                        let i = declarations.function(&variable.name);
+                        declarations.functions[i].ty = variable.ty;
+                        declarations.functions[i].class = variable.class;
+                    } else if let (Some(i), StorageClass::Static) = (function, variable.class) {
+                        declarations.functions[i].locals.push(variable);
+                    } else {
+                        declarations.variables.push(variable);
+                    }
+                }
+                _ => (),
+            }
+        }
+        declarations
+    }
+
+    /// Returns the structures, unions, and enumerations in the order they are declared.
+    pub fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    /// Returns the type names in the order they are declared.
+    pub fn typedefs(&self) -> &[Variable] {
+        &self.typedefs
+    }
+
+    /// Returns the static and external variables in the order they are declared.
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    /// Returns the functions in the order they are declared.
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
+    }
+
+    /// Returns `true` if the object has no declarations.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.typedefs.is_empty()
+            && self.variables.is_empty()
+            && self.functions.is_empty()
+    }
+
+    /// Returns the index of the function named `name`, adding it if it is not known.
+    fn function(&mut self, name: &str) -> usize {
+        if let Some(i) = self.functions.iter().position(|f| f.name == name) {
+            return i;
+        }
+        self.functions.push(Function {
+            name: name.into(),
+            ty: Type {
+                base: BaseType::Int,
+                tag: None,
+                derivations: vec![Derivation::Function],
+            },
+            class: StorageClass::External,
+            parameters: Vec::new(),
+            locals: Vec::new(),
+        });
+        self.functions.len() - 1
+    }
+}
+
+impl fmt::Display for Declarations {
+    /// Writes the declarations as a C header: tags, typedefs, variables, then function
+    /// prototypes.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let storage = |class| match class {
+            StorageClass::Static | StorageClass::UndefinedStatic => "static ",
+            StorageClass::External => "extern ",
+            _ => "",
+        };
+        let mut blocks: Vec<String> = self.tags.iter().map(Tag::to_string).collect();
+        let typedefs: String = self
+            .typedefs
+            .iter()
+            .map(|typedef| format!("typedef {};\n", typedef.ty.declare(&typedef.name)))
+            .collect();
+        let variables: String = self
+            .variables
+            .iter()
+            .map(|variable| {
+                let declaration = variable.ty.declare(&variable.name);
+                format!("{}{declaration};\n", storage(variable.class))
+            })
+            .collect();
+        let functions: String = self
+            .functions
+            .iter()
+            .map(|function| {
+                // functions are external unless declared static
+                let storage = match function.class {
+                    StorageClass::External => "",
+                    class => storage(class),
+                };
+                format!("{storage}{};\n", function.prototype())
+            })
+            .collect();
+        blocks.extend(
+            [typedefs, variables, functions]
+                .into_iter()
+                .filter(|block| !block.is_empty()),
+        );
+        write!(f, "{}", blocks.join("\n"))
+    }
+}
+
+/// Returns `name` as a C identifier. Compilers name anonymous tags like `.0fake`.
+fn identifier(name: &str) -> String {
+    match name.strip_prefix('.') {
+        Some(rest) => format!("__{rest}"),
+        None => name.into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Dim, SectionOffsetLine};
+    use binrw::BinRead;
+    use std::io::Cursor;
+
+    fn function_start(name: &str) -> Section {
+        let mut bytes = vec![74, 1, 0, 0, 0, 0, 0];
+        bytes.extend([0; 22]);
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+        Section::read(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    fn def(class: u16, def_type: u16, value: u32, size: u32, name: &str) -> Section {
+        Section::Def(Def::new(0, value, class, def_type, size, name.into()))
+    }
+
+    fn def2(class: u16, def_type: u16, value: u32, dims: Dim, tag: &str, name: &str) -> Section {
+        Section::Def2(Def2::new(
+            0,
+            value,
+            class,
+            def_type,
+            4,
+            dims,
+            tag.into(),
+            name.into(),
+        ))
+    }
+
+    #[test]
+    fn test_declare() {
+        let declare = |def_type, dims: &[u32]| Type::decode(def_type, dims, None).declare("x");
+        assert_eq!("int x", declare(0x04, &[]));
+        assert_eq!("char *x", declare(0x12, &[]));
+        // array of pointers, pointer to array
+        assert_eq!("char *x[4]", declare(0x72, &[4]));
+        assert_eq!("char (*x)[4]", declare(0xD2, &[4]));
+        assert_eq!("unsigned short x[2][3]", declare(0xFD, &[2, 3]));
+        // pointer to function returning int, function returning pointer
+        assert_eq!("int (*x)()", declare(0x94, &[]));
+        assert_eq!("void *x()", declare(0x61, &[]));
+        assert_eq!("unsigned long", Type::decode(0x0F, &[], None).declare(""));
+
+        let ty = Type::decode(0x18, &[], Some(".1fake".into()));
+        assert_eq!("struct __1fake *p", ty.declare("p"));
+        assert_eq!(BaseType::Struct, ty.base());
+        assert_eq!(&[Derivation::Pointer], ty.derivations());
+        assert_eq!("struct __1fake", ty.target().unwrap().declare(""));
+        assert_eq!(None, ty.target().unwrap().target());
+    }
+
+    #[test]
+    fn test_declarations() {
+        let obj = OBJ::new(vec![
+            // enum color { RED, GREEN = 4 };
+            def(15, 10, 0, 4, "color"),
+            def(16, 11, 0, 4, "RED"),
+            def(16, 11, 4, 4, "GREEN"),
+            def2(102, 0, 4, Dim::None, "color", ".eos"),
+            // struct flags { unsigned int a : 3; int b[2][3]; };
+            def(10, 8, 0, 28, "flags"),
+            def(18, 14, 0, 3, "a"),
+            def2(8, 0xF4, 4, Dim::Values { values: vec![2, 3] }, "", "b"),
+            def2(102, 0, 28, Dim::None, "flags", ".eos"),
+            // typedef struct flags Flags;
+            def2(13, 8, 0, Dim::None, "flags", "Flags"),
+            def2(2, 10, 0, Dim::None, "color", "current"),
+            def2(3, 0x32, 0, Dim::Value(16), "", "buffer"),
+            // static char *name(int n, enum color c) { register int i; }
+            def(3, 0x62, 0, 4, "name"),
+            function_start("name"),
+            def(9, 4, 0, 4, "n"),
+            def2(17, 10, 5, Dim::None, "color", "c"),
+            def(4, 4, 16, 4, "i"),
+            Section::FunctionEnd(SectionOffsetLine::new(1, 0x20, 3)),
+            // a function without a type record
+            function_start("main"),
+            Section::FunctionEnd(SectionOffsetLine::new(1, 0x40, 9)),
+            Section::NOP,
+        ]);
+        let declarations = Declarations::new(&obj);
+        assert_eq!(2, declarations.tags().len());
+        assert_eq!(TagKind::Enum, declarations.tags()[0].kind());
+        assert_eq!(28, declarations.tags()[1].size());
+        assert_eq!(1, declarations.typedefs().len());
+        assert_eq!(2, declarations.variables().len());
+        let name = &declarations.functions()[0];
+        assert_eq!(StorageClass::Static, name.class());
+        assert_eq!(2, name.parameters().len());
+        assert_eq!(
+            StorageClass::RegisterParameter,
+            name.parameters()[1].class()
+        );
+        assert_eq!("i", name.locals()[0].name());
+        assert_eq!(StorageClass::Register, name.locals()[0].class());
+        assert_eq!(
+            "enum color { /* size 0x4 */\n\
+            \x20   RED = 0,\n\
+            \x20   GREEN = 4,\n\
+            };\n\
+            \n\
+            struct flags { /* size 0x1c */\n\
+            \x20   unsigned int a : 3; /* bit 0 */\n\
+            \x20   int b[2][3]; /* 0x4 */\n\
+            };\n\
+            \n\
+            typedef struct flags Flags;\n\
+            \n\
+            extern enum color current;\n\
+            static char buffer[16];\n\
+            \n\
+            static char *name(int n, enum color c);\n\
+            int main(void);\n",
+            declarations.to_string()
+        );
+
+        assert!(Declarations::new(&OBJ::new(vec![Section::NOP])).is_empty());
+    }
+
+    #[test]
+    fn test_storage_class() {
+        assert_eq!(StorageClass::EndOfFunction, StorageClass::from(0xFFFF));
+        assert_eq!(StorageClass::EndOfStruct, StorageClass::from(102));
+        assert_eq!(StorageClass::Other(50), StorageClass::from(50));
+    }
+}
@@ -227,3 +227,671 @@ fn test_psyk_extract_file_not_found() {
         .failure()
         .stderr(predicate::str::contains("Error"));
 }
+
+#[test]
+fn test_psyk_link_missing_args() {
+    psyk()
+        .arg("link")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage:"));
+}
+
+#[test]
+fn test_psyk_link() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    // a single word pointing at itself
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".data".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0; 4]).unwrap()),
+        Section::Patch(Patch::new(16, 0, Expression::SymbolAddressIndex(2))),
+        Section::XDEF(XDEF::new(2, 1, 0, "start".into())),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+    std::fs::write(
+        temp_path.join("MAIN.LNK"),
+        "\torg\t$80010000\r\n\tinclude\t\"MAIN.OBJ\"\r\n\tregs\tpc=start\r\n",
+    )
+    .unwrap();
+
+    psyk()
+        .arg("link")
+        .arg(temp_path.join("MAIN.LNK"))
+        .arg("-o")
+        .arg(temp_path.join("MAIN.BIN"))
+        .arg("--map")
+        .arg(temp_path.join("MAIN.MAP"))
+        .assert()
+        .success();
+
+    assert_eq!(
+        vec![0x00, 0x00, 0x01, 0x80],
+        std::fs::read(temp_path.join("MAIN.BIN")).unwrap()
+    );
+    let map = std::fs::read_to_string(temp_path.join("MAIN.MAP")).unwrap();
+    assert!(map.contains("  80010000  start"), "{map}");
+
+    psyk()
+        .arg("link")
+        .arg(temp_path.join("MAIN.LNK"))
+        .arg("--format")
+        .arg("exe")
+        .arg("-o")
+        .arg(temp_path.join("MAIN.EXE"))
+        .assert()
+        .success();
+
+    psyk()
+        .arg("list")
+        .arg(temp_path.join("MAIN.EXE"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Header : PS-X EXE\n"))
+        .stdout(predicate::str::contains("PC     : $80010000\n"))
+        .stdout(predicate::str::contains("Text   : $80010000 2048 bytes\n"));
+
+    psyk()
+        .arg("link")
+        .arg(temp_path.join("MAIN.LNK"))
+        .arg("--format")
+        .arg("cpe")
+        .arg("-o")
+        .arg(temp_path.join("MAIN.CPE"))
+        .assert()
+        .success();
+
+    psyk()
+        .arg("list")
+        .arg(temp_path.join("MAIN.CPE"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\
+            Header : CPE version 1\n\
+            8 : Select unit 0\n\
+            1 : Load 4 bytes at $80010000\n\
+            3 : Set register pc to $80010000\n\
+            0 : End of file\n",
+        ));
+
+    psyk()
+        .arg("link")
+        .arg(temp_path.join("MISSING.LNK"))
+        .arg("-o")
+        .arg(temp_path.join("MISSING.BIN"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("File not found"));
+}
+
+#[test]
+fn test_psyk_cpe2x() {
+    use psyk::cpe::{Chunk, Load, CPE, PC};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let cpe = CPE::new(vec![
+        Chunk::Unit(0),
+        Chunk::Load(Load::new(0x80010000, vec![1, 2, 3, 4])),
+        Chunk::SetRegister(PC, 0x80010000),
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.CPE")).unwrap();
+    psyk::io::write_cpe(&cpe, &mut file).unwrap();
+
+    psyk()
+        .arg("cpe2x")
+        .arg("--region")
+        .arg("europe")
+        .arg(temp_path.join("MAIN.CPE"))
+        .assert()
+        .success();
+
+    let exe = psyk::io::read_exe(&temp_path.join("MAIN.EXE")).unwrap();
+    assert_eq!(0x80010000, exe.pc());
+    assert_eq!(psyk::exe::region::EUROPE, exe.region());
+
+    psyk()
+        .arg("cpe2x")
+        .arg(temp_path.join("MAIN.EXE"))
+        .arg("-o")
+        .arg(temp_path.join("ROUND.CPE"))
+        .assert()
+        .success();
+
+    let round = psyk::io::read_cpe(&temp_path.join("ROUND.CPE")).unwrap();
+    assert_eq!(Some(0x80010000), round.register(PC));
+
+    psyk()
+        .arg("cpe2x")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_obj2elf() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF, XREF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    // jal func; nop
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0, 0]).unwrap()),
+        Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(3))),
+        Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+        Section::XREF(XREF::new(3, "func".into())),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    psyk()
+        .arg("obj2elf")
+        .arg(temp_path.join("MAIN.OBJ"))
+        .assert()
+        .success();
+
+    let elf = psyk::io::read_elf(&temp_path.join("MAIN.o")).unwrap();
+    let symbols = elf.symbols().unwrap();
+    assert!(symbols.iter().any(|(name, _)| name == "main"));
+    let rels = elf
+        .section_by_name(".rel.text")
+        .unwrap()
+        .relocations()
+        .unwrap();
+    assert_eq!(1, rels.len());
+    assert_eq!(psyk::elf::R_MIPS_26, rels[0].rel_type());
+
+    psyk()
+        .arg("obj2elf")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_functions() {
+    use psyk::{Code, LNKHeader, LocalSymbol, Section, OBJ, XDEF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0; 0x40]).unwrap()),
+        Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+        Section::LocalSymbol(LocalSymbol::new(1, 0x28, "helper".into())),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    psyk()
+        .arg("functions")
+        .arg(temp_path.join("MAIN.OBJ"))
+        .assert()
+        .success()
+        .stdout(
+            ".text    00000000 00000028 main\n\
+             .text    00000028 00000018 helper\n",
+        );
+
+    psyk()
+        .arg("functions")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_addr2line() {
+    use psyk::{Code, Filename, LNKHeader, Section, SetSLDLineNumFile, OBJ};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::Filename(Filename::new(1, "main.c".into())),
+        Section::SectionSwitch(1),
+        Section::SetSLDLineNumFile(SetSLDLineNumFile::new(0, 12, 1)),
+        Section::IncSLDLineNumByte(8, 3),
+        Section::EndSLDInfo(0x10),
+        Section::Code(Code::new(vec![0; 0x14]).unwrap()),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    psyk()
+        .arg("addr2line")
+        .arg(temp_path.join("MAIN.OBJ"))
+        .arg(".text:4")
+        .arg("1:0xc")
+        .arg(".text:10")
+        .assert()
+        .success()
+        .stdout("main.c:12\nmain.c:15\n??:0\n");
+
+    psyk()
+        .arg("addr2line")
+        .arg(temp_path.join("MAIN.OBJ"))
+        .arg(".data:0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown section .data"));
+}
+
+#[test]
+fn test_psyk_headers() {
+    use psyk::{Def, Def2, Dim, Section, OBJ};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let obj = OBJ::new(vec![
+        Section::Def(Def::new(0, 0, 10, 8, 8, "point".into())),
+        Section::Def(Def::new(0, 0, 8, 4, 4, "x".into())),
+        Section::Def(Def::new(0, 4, 8, 4, 4, "y".into())),
+        Section::Def2(Def2::new(
+            0,
+            8,
+            102,
+            0,
+            8,
+            Dim::None,
+            "point".into(),
+            ".eos".into(),
+        )),
+        Section::Def2(Def2::new(
+            1,
+            0,
+            2,
+            0x18,
+            4,
+            Dim::None,
+            "point".into(),
+            "origin".into(),
+        )),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("POINT.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    psyk()
+        .arg("headers")
+        .arg(temp_path.join("POINT.OBJ"))
+        .assert()
+        .success()
+        .stdout(
+            "struct point { /* size 0x8 */\n\
+            \x20   int x; /* 0x0 */\n\
+            \x20   int y; /* 0x4 */\n\
+            };\n\
+            \n\
+            extern struct point *origin;\n",
+        );
+
+    psyk()
+        .arg("headers")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_fingerprint() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF, XREF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    // jal func; nop
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0, 0]).unwrap()),
+        Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(3))),
+        Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+        Section::XREF(XREF::new(3, "func".into())),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    let output = psyk()
+        .arg("fingerprint")
+        .arg(temp_path.join("MAIN.OBJ"))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!("MAIN", json[0]["module"]);
+    let function = &json[0]["functions"][0];
+    assert_eq!("main", function["name"]);
+    assert_eq!(8, function["size"]);
+    assert_eq!("000000fcffffffff", function["mask"]);
+    assert_eq!("func", function["symbols"][0]);
+
+    psyk()
+        .arg("fingerprint")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_identify() {
+    use psyk::{Code, LNKHeader, Section, OBJ, XDEF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    // addiu $v0, $zero, 1; addiu $v1, $zero, 2; addu $v0, $v0, $v1; jr $ra; nop
+    let code = vec![
+        0x01, 0x00, 0x02, 0x24, 0x02, 0x00, 0x03, 0x24, 0x21, 0x10, 0x43, 0x00, 0x08, 0x00, 0xE0,
+        0x03, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(code.clone()).unwrap()),
+        Section::XDEF(XDEF::new(2, 1, 0, "three".into())),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("THREE.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    let mut text = vec![0; 0x40];
+    text.extend(&code);
+    let exe = psyk::exe::EXE::new(0x8001_0000, 0, 0x8001_0000, text.clone());
+    let mut file = std::fs::File::create(temp_path.join("MAIN.EXE")).unwrap();
+    psyk::io::write_exe(&exe, &mut file).unwrap();
+
+    psyk()
+        .arg("identify")
+        .arg(temp_path.join("MAIN.EXE"))
+        .arg(temp_path.join("THREE.OBJ"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  80010040  00000014  THREE     three\n",
+        ));
+
+    std::fs::write(temp_path.join("RAM.BIN"), &text).unwrap();
+    psyk()
+        .arg("identify")
+        .arg(temp_path.join("RAM.BIN"))
+        .arg(temp_path.join("THREE.OBJ"))
+        .arg("--address")
+        .arg("0x80020000")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  80020040  00000014  THREE     three\n",
+        ));
+
+    psyk()
+        .arg("identify")
+        .arg(temp_path.join("RAM.BIN"))
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_sdk() {
+    use psyk::{Code, LNKHeader, Module, ModuleMetadata, Section, LIB, OBJ, XDEF};
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let mut build = psyk();
+    build
+        .arg("sdk-database")
+        .arg("-o")
+        .arg(temp_path.join("sdk.json"));
+    for version in ["3.3", "3.5", "3.6", "4.0"] {
+        build.arg(format!("{version}=tests/data/cmd/psy-q-psx/{version}"));
+    }
+    build.assert().success();
+
+    // C57 of LIBAPI in 4.0 was created at 20-05-97 11:38:26
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0x08, 0x00, 0xE0, 0x03, 0, 0, 0, 0]).unwrap()),
+        Section::XDEF(XDEF::new(2, 1, 0, "InitHeap".into())),
+        Section::NOP,
+    ]);
+    let created = SystemTime::UNIX_EPOCH + Duration::from_secs(864_128_306);
+    let metadata = ModuleMetadata::new("C57".into(), created, 0, vec![]);
+    let lib = LIB::new(vec![Module::new(obj, metadata)]);
+    let mut file = std::fs::File::create(temp_path.join("LIBAPI.LIB")).unwrap();
+    psyk::io::write_lib(&lib, &mut file).unwrap();
+
+    psyk()
+        .arg("sdk")
+        .arg(temp_path.join("sdk.json"))
+        .arg(temp_path.join("LIBAPI.LIB"))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "  Version   Score     Libraries\n  4.0       1.00      LIBAPI\n",
+        ))
+        .stdout(predicate::str::contains(
+            "  C57       LIBAPI    C57       yes        0/1\n",
+        ));
+
+    psyk()
+        .arg("sdk-database")
+        .arg("-o")
+        .arg(temp_path.join("sdk.json"))
+        .arg("tests/data/cmd/psy-q-psx/4.0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("VERSION=DIR"));
+}
+
+#[test]
+fn test_psyk_asm() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF, XREF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    // jal func; nop
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0, 0]).unwrap()),
+        Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(3))),
+        Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+        Section::XREF(XREF::new(3, "func".into())),
+        Section::NOP,
+    ]);
+    let mut file = std::fs::File::create(temp_path.join("MAIN.OBJ")).unwrap();
+    psyk::io::write_obj(&obj, &mut file).unwrap();
+
+    psyk()
+        .arg("asm")
+        .arg(temp_path.join("MAIN.OBJ"))
+        .assert()
+        .success();
+
+    let source = std::fs::read_to_string(temp_path.join("MAIN.s")).unwrap();
+    assert!(source.contains(".section .text, \"ax\"\n"));
+    assert!(source.contains("glabel main\n    /* 000000 0C000000 */  jal         func\n"));
+
+    psyk()
+        .arg("asm")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_elf2obj() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF, XREF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    // jal func; nop
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0, 0]).unwrap()),
+        Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(3))),
+        Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+        Section::XREF(XREF::new(3, "func".into())),
+        Section::NOP,
+    ]);
+    let elf = psyk::elf::ELF::from_obj(&obj).unwrap();
+    let mut file = std::fs::File::create(temp_path.join("main.o")).unwrap();
+    psyk::io::write_elf(&elf, &mut file).unwrap();
+
+    psyk()
+        .arg("elf2obj")
+        .arg(temp_path.join("main.o"))
+        .assert()
+        .success();
+
+    psyk()
+        .arg("list")
+        .arg(temp_path.join("main.obj"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Patch type 74 at offset 0 with [3]",
+        ))
+        .stdout(predicate::str::contains(
+            "XDEF symbol number 2 'main' at offset 0 in section 1",
+        ))
+        .stdout(predicate::str::contains("XREF symbol number 3 'func'"));
+
+    psyk()
+        .arg("elf2obj")
+        .arg("tests/data/truncated.obj")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_psyk_list_disassemble_relocations() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XDEF, XREF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("MAIN.OBJ");
+
+    // nop; jal InitGeom; lui a0, %hi(table+0x10)
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text".into())),
+        Section::SectionSwitch(1),
+        Section::Code(Code::new(vec![0; 4]).unwrap()),
+        Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0x04, 0x3C]).unwrap()),
+        Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(3))),
+        Section::Patch(Patch::new(
+            82,
+            4,
+            Expression::Add(
+                Box::new(Expression::SymbolAddressIndex(4)),
+                Box::new(Expression::Constant(0x10)),
+            ),
+        )),
+        Section::XDEF(XDEF::new(2, 1, 0, "main".into())),
+        Section::XREF(XREF::new(3, "InitGeom".into())),
+        Section::XREF(XREF::new(4, "table".into())),
+        Section::NOP,
+    ]);
+    psyk::io::write_obj(&obj, &mut std::fs::File::create(&path).unwrap()).unwrap();
+
+    psyk()
+        .arg("list")
+        .arg("--disassemble")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\
+            2 : Code 8 bytes\n\
+            \n    \
+            /* 0c000000 */   jal         InitGeom\n    \
+            /* 3c040000 */   lui         $a0, %hi(table+0x10)\n\
+            \n\
+            10 : Patch type 74 at offset 0 with [3]\n\
+        ",
+        ));
+}
+
+#[test]
+fn test_psyk_list_symbolic() {
+    use psyk::{Code, Expression, LNKHeader, Patch, Section, OBJ, XREF};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("MAIN.OBJ");
+
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(0xF001, 0, 8, ".text".into())),
+        Section::SectionSwitch(0xF001),
+        Section::Code(Code::new(vec![0, 0, 0, 0x0C, 0, 0, 0x04, 0x3C]).unwrap()),
+        Section::Patch(Patch::new(74, 0, Expression::SymbolAddressIndex(0x14))),
+        Section::Patch(Patch::new(
+            82,
+            4,
+            Expression::Add(
+                Box::new(Expression::Constant(0x20)),
+                Box::new(Expression::SectionAddressIndex(0xF001)),
+            ),
+        )),
+        Section::XREF(XREF::new(0x14, "InitGeom".into())),
+        Section::NOP,
+    ]);
+    psyk::io::write_obj(&obj, &mut std::fs::File::create(&path).unwrap()).unwrap();
+
+    psyk()
+        .arg("list")
+        .arg("--symbolic")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\
+            10 : Patch type 74 at offset 0 with [14 InitGeom]\n\
+            10 : Patch type 82 at offset 4 with ($20+sectbase(.text))\n\
+        ",
+        ));
+
+    // DOS compatible output is the default
+    psyk()
+        .arg("list")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "10 : Patch type 74 at offset 0 with [14]\n",
+        ));
+}
@@ -143,7 +143,7 @@ fn test_info_obj() -> Result<()> {
     assert_eq!(
         "\
         Header : LNK version 2\n\
-        46 : Processor type 7\n\
+        46 : Processor type 7 (MIPS R3000)\n\
         16 : Section symbol number 2808 '.rdata' in group 0 alignment 8\n\
         16 : Section symbol number 2809 '.text' in group 0 alignment 8\n\
         16 : Section symbol number 280a '.data' in group 0 alignment 8\n\